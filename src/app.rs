@@ -1,8 +1,17 @@
 use Renderer;
 use context::Context;
+use timestep::FixedTimestep;
+use input::GamepadId;
+use input::GamepadButton;
+use input::GamepadAxis;
 
 pub use glium::glutin::VirtualKeyCode as KeyCode;
 pub use glium::glutin::MouseButton;
+pub use glium::glutin::MouseScrollDelta as ScrollDelta;
+pub use glium::glutin::Touch;
+pub use glium::glutin::TouchPhase;
+
+use std::path::PathBuf;
 
 pub trait App {
 
@@ -19,7 +28,9 @@ pub trait App {
      * Main loop
      */
 
-    // Updates the app
+    // Updates the app.
+    // The input's view is the one the last frame was rendered with, so apps moving the camera here
+    // should find the cursor with Input::mouse_position_in and the new view
     #[allow(unused_variables)]
     fn update(&mut self, delta_time: f64);
 
@@ -29,6 +40,27 @@ pub trait App {
     fn render(&mut self, renderer: &mut Renderer);
 
 
+    // Returns the timestep to call fixed_update with, or None to only use update.
+    // Called once after init, the clock can be changed later through Context::set_fixed_timestep
+    // or Context::set_timestep
+    fn fixed_timestep(&self) -> Option<FixedTimestep> {
+        None
+    }
+
+
+    // Updates the app by a single fixed timestep, called before update as many times as needed to catch up
+    #[allow(unused_variables)]
+    fn fixed_update(&mut self, timestep: f64) {}
+
+
+    // Renders the app, alpha is how far the time is between the last fixed update and the next.
+    // Calls render by default
+    #[allow(unused_variables)]
+    fn render_interpolated(&mut self, renderer: &mut Renderer, alpha: f64) {
+        self.render(renderer);
+    }
+
+
     /*
      * Event Handling
      */
@@ -37,6 +69,18 @@ pub trait App {
     #[allow(unused_variables)]
     fn size_changed(&mut self, width: u32, height: u32) {}
 
+    // Called when the window moves on the screen
+    #[allow(unused_variables)]
+    fn window_moved(&mut self, x: i32, y: i32) {}
+
+    // Called when the window gains or loses focus
+    #[allow(unused_variables)]
+    fn focus_changed(&mut self, focused: bool) {}
+
+    // Called when the ratio between physical pixels and screen points changes, such as when moving to another monitor
+    #[allow(unused_variables)]
+    fn dpi_changed(&mut self, factor: f32) {}
+
 
     // Called when a key is pressed
     #[allow(unused_variables)]
@@ -47,11 +91,22 @@ pub trait App {
     fn key_released(&mut self, key: KeyCode) {}
 
 
+    // Called when a character is typed, with repeats and keyboard layout applied
+    #[allow(unused_variables)]
+    fn text_input(&mut self, character: char) {}
+
+
 
     // Called when the cursor moves
     #[allow(unused_variables)]
     fn cursor_moved(&mut self, x: f64, y: f64) {}
 
+    // Called when the cursor enters the window
+    fn cursor_entered(&mut self) {}
+
+    // Called when the cursor leaves the window
+    fn cursor_left(&mut self) {}
+
 
     // Called when a mouse button is pressed
     #[allow(unused_variables)]
@@ -60,6 +115,52 @@ pub trait App {
     // Called when a mouse button is released
     #[allow(unused_variables)]
     fn mouse_released(&mut self, button: MouseButton) {}
+
+    // Called when the mouse wheel or touchpad scrolls
+    #[allow(unused_variables)]
+    fn mouse_scrolled(&mut self, delta: ScrollDelta) {}
+
+
+    // Called when a finger touches, moves on or leaves a touch screen
+    #[allow(unused_variables)]
+    fn touch(&mut self, touch: Touch) {}
+
+
+    // Called when a file is dropped on the window
+    #[allow(unused_variables)]
+    fn file_dropped(&mut self, path: PathBuf) {}
+
+    // Called when a file is dragged over the window
+    #[allow(unused_variables)]
+    fn file_hovered(&mut self, path: PathBuf) {}
+
+    // Called when a file dragged over the window leaves it without being dropped
+    fn file_hover_cancelled(&mut self) {}
+
+
+    /*
+     * Controllers
+     */
+
+    // Called when a controller is connected
+    #[allow(unused_variables)]
+    fn gamepad_connected(&mut self, id: GamepadId) {}
+
+    // Called when a controller is disconnected, after releasing its buttons
+    #[allow(unused_variables)]
+    fn gamepad_disconnected(&mut self, id: GamepadId) {}
+
+    // Called when a controller button is pressed
+    #[allow(unused_variables)]
+    fn gamepad_button_pressed(&mut self, id: GamepadId, button: GamepadButton) {}
+
+    // Called when a controller button is released
+    #[allow(unused_variables)]
+    fn gamepad_button_released(&mut self, id: GamepadId, button: GamepadButton) {}
+
+    // Called when a controller axis changes, with its dead zone applied
+    #[allow(unused_variables)]
+    fn gamepad_axis_moved(&mut self, id: GamepadId, axis: GamepadAxis, value: f64) {}
 }
 
 
@@ -0,0 +1,645 @@
+use Vector2;
+use Rectangle;
+use collision::Collide;
+use collision::CollisionFilter;
+use collision::Ray;
+use collision::raycast::ray_hits_box;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+
+/// Objects covering more cells than this are kept out of the grid and tested against everything
+const MAX_CELLS: f64 = 1024.0;
+
+/// Cell coordinates beyond this are treated like infinity, so they can't overflow
+const MAX_COORDINATE: f64 = 1e15;
+
+/// Rays crossing more cells than this test every object instead of walking the grid
+const MAX_WALK: f64 = 65536.0;
+
+
+/// Identifies an object stored in a broad phase
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ProxyId {
+    index: usize,
+
+    // Distinguishes objects that have been stored in the same slot
+    generation: u32,
+}
+
+
+struct Entry<T> {
+    bounds: Rectangle,
+    data: Option<T>,
+    generation: u32,
+    filter: CollisionFilter,
+
+    // The range of cells the bounds cover: left, right, bottom, top,
+    // or None for objects too large or far away for the grid
+    cells: Option<(i64, i64, i64, i64)>,
+}
+
+
+/// Finds objects that may be colliding without testing every pair, by sorting their
+/// bounding boxes into a uniform grid of cells.
+///
+/// Objects too large for the grid, such as a ground plane, or with non-finite bounds
+/// are kept in a separate list and tested against every other object.
+pub struct BroadPhase<T> {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+
+    // Objects that aren't in any cell
+    large: Vec<usize>,
+
+    entries: Vec<Entry<T>>,
+    free: Vec<usize>,
+}
+
+
+impl<T> BroadPhase<T> {
+    /// Creates an empty broad phase, cells work best when slightly larger than a typical object.
+    /// Panics if the cell size isn't a positive finite number.
+    pub fn new(cell_size: f64) -> BroadPhase<T> {
+        assert!(cell_size > 0.0 && cell_size.is_finite(), "BroadPhase: invalid cell size {}", cell_size);
+
+        BroadPhase {
+            cell_size,
+            cells: HashMap::new(),
+            large: Vec::new(),
+
+            entries: Vec::new(),
+            free: Vec::new(),
+        }
+    }
+
+
+    /// Adds an object by its bounding box
+    pub fn insert(&mut self, shape: &Collide, data: T) -> ProxyId {
+        self.insert_bounds(shape.get_bounding_box(), data)
+    }
+
+
+    /// Adds an object by its bounding box, colliding only with what its filter allows
+    pub fn insert_filtered(&mut self, shape: &Collide, data: T, filter: CollisionFilter) -> ProxyId {
+        let id = self.insert(shape, data);
+        self.entries[id.index].filter = filter;
+        id
+    }
+
+
+    /// Adds an object covering an area
+    pub fn insert_bounds(&mut self, bounds: Rectangle, data: T) -> ProxyId {
+        let cells = self.cell_range(&bounds);
+
+        let index = match self.free.pop() {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.bounds = bounds;
+                entry.data = Some(data);
+                entry.generation = entry.generation.wrapping_add(1);
+                entry.filter = CollisionFilter::default();
+                entry.cells = cells;
+                index
+            },
+
+            None => {
+                self.entries.push(Entry {
+                    bounds,
+                    data: Some(data),
+                    generation: 0,
+                    filter: CollisionFilter::default(),
+                    cells,
+                });
+                self.entries.len() - 1
+            }
+        };
+
+        self.add_to_cells(index, cells);
+
+        ProxyId { index, generation: self.entries[index].generation }
+    }
+
+
+    /// Moves an object to the bounding box of a shape, returns false if the object doesn't exist
+    pub fn update(&mut self, id: ProxyId, shape: &Collide) -> bool {
+        self.update_bounds(id, shape.get_bounding_box())
+    }
+
+
+    /// Moves an object to cover an area, returns false if the object doesn't exist
+    pub fn update_bounds(&mut self, id: ProxyId, bounds: Rectangle) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+
+        let cells = self.cell_range(&bounds);
+        let previous = self.entries[id.index].cells;
+
+        if cells != previous {
+            self.remove_from_cells(id.index, previous);
+            self.add_to_cells(id.index, cells);
+        }
+
+        let entry = &mut self.entries[id.index];
+        entry.bounds = bounds;
+        entry.cells = cells;
+
+        true
+    }
+
+
+    /// Removes an object, returning its data
+    pub fn remove(&mut self, id: ProxyId) -> Option<T> {
+        if !self.contains(id) {
+            return None;
+        }
+
+        let cells = self.entries[id.index].cells;
+        self.remove_from_cells(id.index, cells);
+        self.free.push(id.index);
+
+        self.entries[id.index].data.take()
+    }
+
+
+    /// Returns true if an object is stored
+    pub fn contains(&self, id: ProxyId) -> bool {
+        match self.entries.get(id.index) {
+            Some(entry) => entry.generation == id.generation && entry.data.is_some(),
+            None => false
+        }
+    }
+
+
+    pub fn get(&self, id: ProxyId) -> Option<&T> {
+        if self.contains(id) {
+            self.entries[id.index].data.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, id: ProxyId) -> Option<&mut T> {
+        if self.contains(id) {
+            self.entries[id.index].data.as_mut()
+        } else {
+            None
+        }
+    }
+
+
+    pub fn filter(&self, id: ProxyId) -> Option<CollisionFilter> {
+        if self.contains(id) {
+            Some(self.entries[id.index].filter)
+        } else {
+            None
+        }
+    }
+
+    /// Changes which objects an object collides with, returns false if the object doesn't exist
+    pub fn set_filter(&mut self, id: ProxyId, filter: CollisionFilter) -> bool {
+        if !self.contains(id) {
+            return false;
+        }
+
+        self.entries[id.index].filter = filter;
+        true
+    }
+
+
+    /// Returns the area covered by an object
+    pub fn bounds(&self, id: ProxyId) -> Option<Rectangle> {
+        if self.contains(id) {
+            Some(self.entries[id.index].bounds)
+        } else {
+            None
+        }
+    }
+
+
+    /// Returns the number of objects stored
+    pub fn len(&self) -> usize {
+        self.entries.len() - self.free.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+
+    /// Returns all stored objects
+    pub fn ids(&self) -> Vec<ProxyId> {
+        self.entries.iter().enumerate()
+            .filter(|&(_, entry)| entry.data.is_some())
+            .map(|(index, entry)| ProxyId { index, generation: entry.generation })
+            .collect()
+    }
+
+
+    /// Returns every pair of objects with touching bounding boxes whose filters let them collide, each pair only once
+    pub fn pairs(&self) -> Vec<(ProxyId, ProxyId)> {
+        let mut visited = HashSet::new();
+        let mut pairs = Vec::new();
+
+        for indices in self.cells.values() {
+            for i in 0..indices.len() {
+                for j in i + 1..indices.len() {
+                    let (a, b) = if indices[i] < indices[j] {
+                        (indices[i], indices[j])
+                    } else {
+                        (indices[j], indices[i])
+                    };
+
+                    let (entry_a, entry_b) = (&self.entries[a], &self.entries[b]);
+                    if !entry_a.filter.collides_with(&entry_b.filter) || !boxes_touch(&entry_a.bounds, &entry_b.bounds) {
+                        continue;
+                    }
+
+                    if visited.insert((a, b)) {
+                        pairs.push((self.id(a), self.id(b)));
+                    }
+                }
+            }
+        }
+
+        for &large in self.large.iter() {
+            for index in self.live() {
+                if index == large {
+                    continue;
+                }
+
+                let (a, b) = if large < index { (large, index) } else { (index, large) };
+
+                let (entry_a, entry_b) = (&self.entries[a], &self.entries[b]);
+                if !entry_a.filter.collides_with(&entry_b.filter) || !boxes_touch(&entry_a.bounds, &entry_b.bounds) {
+                    continue;
+                }
+
+                if visited.insert((a, b)) {
+                    pairs.push((self.id(a), self.id(b)));
+                }
+            }
+        }
+
+        pairs.sort();
+        pairs
+    }
+
+
+    /// Returns all objects with bounding boxes touching an area
+    pub fn query_region(&self, region: &Rectangle) -> Vec<ProxyId> {
+        self.region(region, None)
+    }
+
+    /// Returns all objects with bounding boxes touching an area that collide with a filter
+    pub fn query_region_filtered(&self, region: &Rectangle, filter: &CollisionFilter) -> Vec<ProxyId> {
+        self.region(region, Some(filter))
+    }
+
+
+    /// Returns all objects with bounding boxes containing a point
+    pub fn query_point(&self, point: Vector2) -> Vec<ProxyId> {
+        self.point(point, None)
+    }
+
+    /// Returns all objects with bounding boxes containing a point that collide with a filter
+    pub fn query_point_filtered(&self, point: Vector2, filter: &CollisionFilter) -> Vec<ProxyId> {
+        self.point(point, Some(filter))
+    }
+
+
+    /// Returns all objects with bounding boxes crossed by a ray before reaching a fraction,
+    /// roughly in the order the ray reaches them
+    pub fn query_ray(&self, ray: &Ray, max_fraction: f64) -> Vec<ProxyId> {
+        self.ray(ray, max_fraction, None)
+    }
+
+    /// Returns all objects with bounding boxes crossed by a ray before reaching a fraction
+    /// that collide with a filter, roughly in the order the ray reaches them
+    pub fn query_ray_filtered(&self, ray: &Ray, max_fraction: f64, filter: &CollisionFilter) -> Vec<ProxyId> {
+        self.ray(ray, max_fraction, Some(filter))
+    }
+
+
+    fn region(&self, region: &Rectangle, filter: Option<&CollisionFilter>) -> Vec<ProxyId> {
+        let mut found = HashSet::new();
+        let mut ids = Vec::new();
+
+        let candidates: Vec<usize> = match self.cell_range(region) {
+            Some((left, right, bottom, top)) => {
+                let mut candidates = self.large.clone();
+
+                for x in left..right + 1 {
+                    for y in bottom..top + 1 {
+                        if let Some(indices) = self.cells.get(&(x, y)) {
+                            candidates.extend(indices.iter().cloned());
+                        }
+                    }
+                }
+
+                candidates
+            },
+
+            // Regions too large for the grid test every object
+            None => self.live().collect()
+        };
+
+        for index in candidates {
+            if self.passes(index, filter) && boxes_touch(&self.entries[index].bounds, region) && found.insert(index) {
+                ids.push(self.id(index));
+            }
+        }
+
+        ids.sort();
+        ids
+    }
+
+
+    fn point(&self, point: Vector2, filter: Option<&CollisionFilter>) -> Vec<ProxyId> {
+        let cell = self.cell(point);
+        let mut ids = Vec::new();
+
+        let indices = self.cells.get(&cell).map_or(&[][..], |indices| &indices[..]);
+
+        for &index in indices.iter().chain(self.large.iter()) {
+            if self.passes(index, filter) && self.entries[index].bounds.contains(point) {
+                ids.push(self.id(index));
+            }
+        }
+
+        ids.sort();
+        ids
+    }
+
+
+    fn ray(&self, ray: &Ray, max_fraction: f64, filter: Option<&CollisionFilter>) -> Vec<ProxyId> {
+        use std::f64::INFINITY;
+
+        let mut found = HashSet::new();
+        let mut ids = Vec::new();
+
+        // Rays that are too long or far away for the grid test every object, ordered along the ray
+        let end = ray.point_at(max_fraction);
+        if !self.walkable(ray.origin, end) {
+            let mut hits: Vec<(f64, usize)> = self.live()
+                .filter(|&index| self.passes(index, filter) && ray_hits_box(ray, &self.entries[index].bounds, max_fraction))
+                .map(|index| {
+                    let bounds = &self.entries[index].bounds;
+                    let center = Vector2::new(0.5 * (bounds.left + bounds.right), 0.5 * (bounds.bottom + bounds.top));
+                    ((center - ray.origin).dot(ray.direction), index)
+                })
+                .collect();
+
+            hits.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(::std::cmp::Ordering::Equal));
+            return hits.into_iter().map(|(_, index)| self.id(index)).collect();
+        }
+
+        let (mut x, mut y) = self.cell(ray.origin);
+        let (end_x, end_y) = self.cell(end);
+
+        // Walk through the cells along the ray, one cell boundary at a time
+        let step = |direction: f64, origin: f64, cell: i64| -> (i64, f64, f64) {
+            if direction > 0.0 {
+                (1, ((cell + 1) as f64 * self.cell_size - origin) / direction, self.cell_size / direction)
+            } else if direction < 0.0 {
+                (-1, (cell as f64 * self.cell_size - origin) / direction, -self.cell_size / direction)
+            } else {
+                (0, INFINITY, INFINITY)
+            }
+        };
+
+        let (step_x, mut next_x, delta_x) = step(ray.direction.x, ray.origin.x, x);
+        let (step_y, mut next_y, delta_y) = step(ray.direction.y, ray.origin.y, y);
+
+        let max_steps = (end_x - x).abs() + (end_y - y).abs() + 1;
+
+        for _ in 0..max_steps + 1 {
+            if let Some(indices) = self.cells.get(&(x, y)) {
+                for &index in indices.iter() {
+                    if !found.contains(&index) && self.passes(index, filter) && ray_hits_box(ray, &self.entries[index].bounds, max_fraction) {
+                        found.insert(index);
+                        ids.push(self.id(index));
+                    }
+                }
+            }
+
+            if x == end_x && y == end_y {
+                break;
+            }
+
+            if next_x < next_y {
+                if next_x > max_fraction { break; }
+                x += step_x;
+                next_x += delta_x;
+            } else {
+                if next_y > max_fraction { break; }
+                y += step_y;
+                next_y += delta_y;
+            }
+        }
+
+        // Objects outside the grid are reached last
+        for &index in self.large.iter() {
+            if !found.contains(&index) && self.passes(index, filter) && ray_hits_box(ray, &self.entries[index].bounds, max_fraction) {
+                found.insert(index);
+                ids.push(self.id(index));
+            }
+        }
+
+        ids
+    }
+
+
+    /// Returns true if the cells between two points can be walked one at a time
+    fn walkable(&self, start: Vector2, end: Vector2) -> bool {
+        let coordinates = [
+            start.x / self.cell_size, start.y / self.cell_size,
+            end.x / self.cell_size, end.y / self.cell_size,
+        ];
+
+        if coordinates.iter().any(|c| !c.is_finite() || c.abs() > MAX_COORDINATE) {
+            return false;
+        }
+
+        let walk = (coordinates[2].floor() - coordinates[0].floor()).abs() + (coordinates[3].floor() - coordinates[1].floor()).abs();
+        walk <= MAX_WALK
+    }
+
+
+    /// Returns the indices of all stored objects
+    fn live<'a>(&'a self) -> Box<Iterator<Item = usize> + 'a> {
+        Box::new(self.entries.iter().enumerate()
+            .filter(|&(_, entry)| entry.data.is_some())
+            .map(|(index, _)| index))
+    }
+
+
+    fn passes(&self, index: usize, filter: Option<&CollisionFilter>) -> bool {
+        match filter {
+            Some(filter) => filter.collides_with(&self.entries[index].filter),
+            None => true
+        }
+    }
+
+
+    fn id(&self, index: usize) -> ProxyId {
+        ProxyId { index, generation: self.entries[index].generation }
+    }
+
+
+    fn cell(&self, point: Vector2) -> (i64, i64) {
+        ((point.x / self.cell_size).floor() as i64, (point.y / self.cell_size).floor() as i64)
+    }
+
+
+    /// Returns the cells covered by an area, or None if the area is too large, too far away or not finite
+    fn cell_range(&self, bounds: &Rectangle) -> Option<(i64, i64, i64, i64)> {
+        let left = (bounds.left / self.cell_size).floor();
+        let right = (bounds.right / self.cell_size).floor();
+        let bottom = (bounds.bottom / self.cell_size).floor();
+        let top = (bounds.top / self.cell_size).floor();
+
+        let coordinates = [left, right, bottom, top];
+        if coordinates.iter().any(|c| !c.is_finite() || c.abs() > MAX_COORDINATE) {
+            return None;
+        }
+
+        if right < left || top < bottom || (right - left + 1.0) * (top - bottom + 1.0) > MAX_CELLS {
+            return None;
+        }
+
+        Some((left as i64, right as i64, bottom as i64, top as i64))
+    }
+
+
+    fn add_to_cells(&mut self, index: usize, cells: Option<(i64, i64, i64, i64)>) {
+        let (left, right, bottom, top) = match cells {
+            Some(cells) => cells,
+            None => {
+                self.large.push(index);
+                return;
+            }
+        };
+
+        for x in left..right + 1 {
+            for y in bottom..top + 1 {
+                self.cells.entry((x, y)).or_insert_with(Vec::new).push(index);
+            }
+        }
+    }
+
+
+    fn remove_from_cells(&mut self, index: usize, cells: Option<(i64, i64, i64, i64)>) {
+        let (left, right, bottom, top) = match cells {
+            Some(cells) => cells,
+            None => {
+                self.large.retain(|&i| i != index);
+                return;
+            }
+        };
+
+        for x in left..right + 1 {
+            for y in bottom..top + 1 {
+                let empty = match self.cells.get_mut(&(x, y)) {
+                    Some(indices) => {
+                        indices.retain(|&i| i != index);
+                        indices.is_empty()
+                    },
+                    None => false
+                };
+
+                if empty {
+                    self.cells.remove(&(x, y));
+                }
+            }
+        }
+    }
+}
+
+
+/// Returns true if two rectangles overlap or share an edge
+fn boxes_touch(a: &Rectangle, b: &Rectangle) -> bool {
+    a.left <= b.right && b.left <= a.right &&
+        a.bottom <= b.top && b.bottom <= a.top
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::INFINITY;
+    use std::f64::NAN;
+
+
+    #[test]
+    #[should_panic]
+    fn zero_cell_size_is_rejected() {
+        BroadPhase::<()>::new(0.0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn nan_cell_size_is_rejected() {
+        BroadPhase::<()>::new(NAN);
+    }
+
+
+    #[test]
+    fn large_objects_stay_out_of_the_grid() {
+        let mut broad_phase = BroadPhase::new(1.0);
+
+        let ground = broad_phase.insert_bounds(Rectangle::new(-1e9, 1e9, 0.0, -1.0), "ground");
+        let box_a = broad_phase.insert_bounds(Rectangle::new(5e8, 5e8 + 1.0, 1.0, -0.5), "a");
+        let box_b = broad_phase.insert_bounds(Rectangle::new(0.0, 1.0, 5.0, 4.0), "b");
+
+        assert!(broad_phase.cells.len() <= 10);
+        assert_eq!(broad_phase.pairs(), vec![(ground, box_a)]);
+
+        assert_eq!(broad_phase.query_point(Vector2::new(-3e8, -0.5)), vec![ground]);
+        assert_eq!(broad_phase.query_region(&Rectangle::new(0.0, 1.0, 5.0, -0.5)), vec![ground, box_b]);
+
+        // Shrinking the object moves it back into the grid
+        assert!(broad_phase.update_bounds(ground, Rectangle::new(-1.0, 1.0, 0.0, -1.0)));
+        assert!(broad_phase.large.is_empty());
+        assert_eq!(broad_phase.pairs(), Vec::new());
+
+        assert_eq!(broad_phase.remove(ground), Some("ground"));
+        assert!(broad_phase.cells.values().all(|indices| !indices.contains(&ground.index)));
+    }
+
+
+    #[test]
+    fn non_finite_bounds_do_not_panic() {
+        let mut broad_phase = BroadPhase::new(1.0);
+
+        let a = broad_phase.insert_bounds(Rectangle::new(0.0, 1.0, 1.0, 0.0), ());
+        let b = broad_phase.insert_bounds(Rectangle { left: NAN, right: NAN, top: NAN, bottom: NAN }, ());
+        let c = broad_phase.insert_bounds(Rectangle { left: INFINITY, right: -INFINITY, top: -INFINITY, bottom: INFINITY }, ());
+        let d = broad_phase.insert_bounds(Rectangle::new(1e300, 1e300, 1e300, 1e300), ());
+
+        assert_eq!(broad_phase.pairs(), Vec::new());
+        assert_eq!(broad_phase.query_region(&Rectangle::new(-1.0, 2.0, 2.0, -1.0)), vec![a]);
+        assert_eq!(broad_phase.query_point(Vector2::new(1e300, 1e300)), vec![d]);
+
+        assert!(broad_phase.remove(b).is_some());
+        assert!(broad_phase.remove(c).is_some());
+        assert!(broad_phase.remove(d).is_some());
+        assert!(broad_phase.large.is_empty());
+    }
+
+
+    #[test]
+    fn huge_queries_scan_every_object() {
+        let mut broad_phase = BroadPhase::new(1.0);
+
+        let a = broad_phase.insert_bounds(Rectangle::new(0.0, 1.0, 1.0, 0.0), ());
+        let b = broad_phase.insert_bounds(Rectangle::new(10.0, 11.0, 1.0, 0.0), ());
+
+        assert_eq!(broad_phase.query_region(&Rectangle::new(-1e12, 1e12, 1e12, -1e12)), vec![a, b]);
+
+        let ray = Ray::new(Vector2::new(-1e12, 0.5), Vector2::new(1.0, 0.0));
+        assert_eq!(broad_phase.query_ray(&ray, 2e12), vec![a, b]);
+    }
+}
@@ -0,0 +1,445 @@
+use Vector2;
+use shapes::Shape;
+use shapes::unit;
+use collision::Collide;
+use collision::intersect;
+use collision::overlap;
+use collision::shape_cast;
+use collision::shape_cast::Translated;
+
+use std::f64::consts::PI;
+
+
+/// Something a character can collide with
+#[derive(Copy, Clone)]
+pub struct Obstacle<'a> {
+    pub shape: &'a Collide,
+
+    /// One-way obstacles can be passed from below and from the sides, but can be stood on
+    pub one_way: bool,
+}
+
+
+impl<'a> Obstacle<'a> {
+    pub fn solid(shape: &'a Collide) -> Obstacle<'a> {
+        Obstacle { shape, one_way: false }
+    }
+
+    pub fn one_way(shape: &'a Collide) -> Obstacle<'a> {
+        Obstacle { shape, one_way: true }
+    }
+}
+
+
+/// What a character touched during its last move
+#[derive(Copy, Clone, Debug)]
+pub struct CharacterState {
+    /// How far the character actually moved
+    pub displacement: Vector2,
+
+    /// Standing on a surface flat enough to walk on
+    pub grounded: bool,
+
+    /// Hit something above
+    pub ceiling: bool,
+
+    /// Hit a surface too steep to walk on
+    pub wall: bool,
+
+    /// The normal of the surface the character is standing on
+    pub ground_normal: Option<Vector2>,
+}
+
+
+impl CharacterState {
+    fn new() -> CharacterState {
+        CharacterState {
+            displacement: Vector2::new(0.0, 0.0),
+            grounded: false,
+            ceiling: false,
+            wall: false,
+            ground_normal: None,
+        }
+    }
+}
+
+
+/// The kind of surface a character hit, depending on the slope
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Surface {
+    Ground,
+    Ceiling,
+    Wall,
+}
+
+
+/// Moves a shape around obstacles the way a character in a platformer moves
+pub struct CharacterController {
+    /// The direction the character stands up in, opposite of gravity
+    pub up: Vector2,
+
+    /// The steepest slope, in radians, that can be walked on
+    pub max_slope: f64,
+
+    /// The highest ledge the character can walk onto without jumping
+    pub step_height: f64,
+
+    /// How far the character follows the ground down when walking down slopes and stairs
+    pub snap_distance: f64,
+
+    /// The gap kept between the character and the surfaces it touches
+    pub skin: f64,
+
+    /// How many surfaces the character can slide along during a single move
+    pub max_iterations: u32,
+
+    state: CharacterState,
+}
+
+
+impl CharacterController {
+    pub fn new() -> CharacterController {
+        CharacterController {
+            up: Vector2::new(0.0, 1.0),
+            max_slope: PI / 4.0,
+            step_height: 0.0,
+            snap_distance: 0.0,
+            skin: 0.01,
+            max_iterations: 4,
+
+            state: CharacterState::new(),
+        }
+    }
+
+
+    /// Returns what the character touched during its last move
+    pub fn state(&self) -> CharacterState {
+        self.state
+    }
+
+    pub fn grounded(&self) -> bool {
+        self.state.grounded
+    }
+
+
+    /// Moves a shape by a displacement, sliding along the obstacles it hits.
+    /// Shapes that start inside a solid obstacle are pushed out first.
+    pub fn move_shape<S: Shape + ?Sized>(&mut self, shape: &mut S, displacement: Vector2,
+                                         obstacles: &[Obstacle]) -> CharacterState {
+        const EPSILON: f64 = 1e-9;
+
+        let up = unit(self.up);
+        let was_grounded = self.state.grounded;
+        let mut state = CharacterState::new();
+
+        let mut offset = self.depenetrate(shape.as_collide(), obstacles);
+        let mut remaining = displacement;
+
+        for _ in 0..self.max_iterations {
+            if remaining.dot(remaining) <= EPSILON * EPSILON {
+                break;
+            }
+
+            let (time, normal) = match self.cast(shape.as_collide(), offset, remaining, obstacles) {
+                Some(hit) => hit,
+                None => {
+                    offset += remaining;
+                    break;
+                }
+            };
+
+            offset += remaining * time + normal * self.skin;
+            remaining = remaining * (1.0 - time);
+
+            match self.surface(normal) {
+                Surface::Ground => {
+                    state.grounded = true;
+                    state.ground_normal = Some(normal);
+
+                    // Follow the slope without sliding down it
+                    let lift = up.dot(normal);
+                    remaining = if lift > EPSILON {
+                        remaining - up * (remaining.dot(normal) / lift)
+                    } else {
+                        slide(remaining, normal)
+                    };
+                },
+
+                Surface::Ceiling => {
+                    state.ceiling = true;
+                    remaining = slide(remaining, normal);
+                },
+
+                Surface::Wall => {
+                    if self.step_height > 0.0 && (was_grounded || state.grounded) {
+                        if let Some(stepped) = self.step(shape.as_collide(), offset, remaining, obstacles) {
+                            offset = stepped.0;
+                            remaining = Vector2::new(0.0, 0.0);
+
+                            state.grounded = true;
+                            state.ground_normal = Some(stepped.1);
+                            continue;
+                        }
+                    }
+
+                    state.wall = true;
+
+                    // Steep surfaces can't be climbed by walking into them
+                    let slid = slide(remaining, normal);
+                    remaining = if slid.dot(up) > 0.0 && remaining.dot(up) <= 0.0 {
+                        slid - up * slid.dot(up)
+                    } else {
+                        slid
+                    };
+                },
+            }
+        }
+
+        // Look for ground below, and stick to it if the character was walking on it
+        let snap = was_grounded && displacement.dot(up) <= 0.0;
+        let reach = if snap { self.snap_distance.max(2.0 * self.skin) } else { 2.0 * self.skin };
+        let probe = up * -reach;
+
+        if let Some((time, normal)) = self.cast(shape.as_collide(), offset, probe, obstacles) {
+            if self.surface(normal) == Surface::Ground {
+                state.grounded = true;
+                state.ground_normal = Some(normal);
+
+                if snap && reach * time > self.skin {
+                    offset += probe * time + normal * self.skin;
+                }
+            }
+        }
+
+        shape.translate(offset);
+
+        state.displacement = offset;
+        self.state = state;
+        state
+    }
+
+
+    /// Returns how far a shape has to move to get out of every solid obstacle
+    fn depenetrate(&self, shape: &Collide, obstacles: &[Obstacle]) -> Vector2 {
+        let mut offset = Vector2::new(0.0, 0.0);
+
+        for _ in 0..self.max_iterations {
+            let mut moved = false;
+
+            for obstacle in obstacles.iter().filter(|obstacle| !obstacle.one_way) {
+                if let Some(translation) = overlap(&Translated { shape, offset }, obstacle.shape) {
+                    offset = offset - translation - unit(translation) * self.skin;
+                    moved = true;
+                }
+            }
+
+            if !moved {
+                break;
+            }
+        }
+
+        offset
+    }
+
+
+    /// Returns the time and surface normal of the first obstacle a shape hits when moving
+    fn cast(&self, shape: &Collide, offset: Vector2, motion: Vector2, obstacles: &[Obstacle]) -> Option<(f64, Vector2)> {
+        let moved = Translated { shape, offset };
+        let falling = motion.dot(self.up) < 0.0;
+
+        let mut first: Option<(f64, Vector2)> = None;
+
+        for obstacle in obstacles.iter() {
+            // One-way obstacles only stop shapes falling onto them from above
+            if obstacle.one_way && (!falling || intersect(&moved, obstacle.shape)) {
+                continue;
+            }
+
+            let impact = match shape_cast(&moved, motion, obstacle.shape) {
+                Some(impact) => impact,
+                None => continue
+            };
+
+            // Surfaces the shape is already moving away from don't stop it
+            if motion.dot(impact.normal) >= 0.0 {
+                continue;
+            }
+
+            if obstacle.one_way && self.surface(impact.normal) != Surface::Ground {
+                continue;
+            }
+
+            let earlier = match first {
+                Some((time, _)) => impact.time < time,
+                None => true
+            };
+
+            if earlier {
+                first = Some((impact.time, impact.normal));
+            }
+        }
+
+        first
+    }
+
+
+    /// Tries to walk up onto a ledge, returning where the shape ends up and the ground it stands on
+    fn step(&self, shape: &Collide, offset: Vector2, remaining: Vector2, obstacles: &[Obstacle]) -> Option<(Vector2, Vector2)> {
+        let up = unit(self.up);
+
+        // Only the part of the movement along the ground steps up
+        let forward = remaining - up * remaining.dot(up);
+        if forward.dot(forward) <= self.skin * self.skin {
+            return None;
+        }
+
+        let mut position = offset;
+
+        let lift = up * self.step_height;
+        position += match self.cast(shape, position, lift, obstacles) {
+            Some((time, normal)) => lift * time + normal * self.skin,
+            None => lift
+        };
+
+        let start = position;
+        position += match self.cast(shape, position, forward, obstacles) {
+            Some((time, normal)) => forward * time + normal * self.skin,
+            None => forward
+        };
+
+        // The ledge has to be cleared, not just pushed against
+        if (position - start).dot(unit(forward)) <= self.skin {
+            return None;
+        }
+
+        let drop = up * -(self.step_height + 2.0 * self.skin);
+        match self.cast(shape, position, drop, obstacles) {
+            Some((time, normal)) if self.surface(normal) == Surface::Ground => {
+                Some((position + drop * time + normal * self.skin, normal))
+            },
+
+            _ => None
+        }
+    }
+
+
+    fn surface(&self, normal: Vector2) -> Surface {
+        let threshold = self.max_slope.cos();
+        let slope = normal.dot(unit(self.up));
+
+        if slope >= threshold {
+            Surface::Ground
+        } else if slope <= -threshold {
+            Surface::Ceiling
+        } else {
+            Surface::Wall
+        }
+    }
+}
+
+
+/// Removes the part of a movement going into a surface
+fn slide(motion: Vector2, normal: Vector2) -> Vector2 {
+    motion - normal * motion.dot(normal)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Rectangle;
+
+    const EPSILON: f64 = 1e-6;
+
+
+    fn floor() -> Rectangle {
+        Rectangle::new(-10.0, 10.0, 0.0, -1.0)
+    }
+
+    // A character standing a skin's width above the floor
+    fn standing(controller: &mut CharacterController, obstacles: &[Obstacle]) -> Rectangle {
+        let mut shape = Rectangle::new(0.0, 0.5, 1.0 + controller.skin, controller.skin);
+        controller.move_shape(&mut shape, Vector2::new(0.0, 0.0), obstacles);
+        assert!(controller.grounded());
+        shape
+    }
+
+
+    #[test]
+    fn grounded_on_the_floor_but_not_in_the_air() {
+        let floor = floor();
+        let obstacles = [Obstacle::solid(&floor)];
+        let mut controller = CharacterController::new();
+
+        let mut shape = standing(&mut controller, &obstacles);
+
+        let state = controller.move_shape(&mut shape, Vector2::new(1.0, 0.0), &obstacles);
+        assert!(state.grounded);
+        assert!((shape.left - 1.0).abs() < EPSILON);
+
+        let state = controller.move_shape(&mut shape, Vector2::new(0.0, 2.0), &obstacles);
+        assert!(!state.grounded);
+        assert!(state.ground_normal.is_none());
+
+        // Falling lands on the floor again
+        let state = controller.move_shape(&mut shape, Vector2::new(0.0, -5.0), &obstacles);
+        assert!(state.grounded);
+        assert!(shape.bottom >= 0.0 && shape.bottom < 2.0 * controller.skin, "{}", shape.bottom);
+    }
+
+
+    #[test]
+    fn walks_up_low_steps() {
+        let floor = floor();
+        let step = Rectangle::new(1.0, 3.0, 0.2, 0.0);
+        let obstacles = [Obstacle::solid(&floor), Obstacle::solid(&step)];
+
+        let mut controller = CharacterController::new();
+        controller.step_height = 0.3;
+
+        let mut shape = standing(&mut controller, &obstacles);
+        let state = controller.move_shape(&mut shape, Vector2::new(2.0, 0.0), &obstacles);
+
+        assert!(state.grounded);
+        assert!(!state.wall);
+        assert!(shape.left > 1.0, "didn't get onto the step: {}", shape.left);
+        assert!(shape.bottom >= 0.2 && shape.bottom < 0.2 + 3.0 * controller.skin, "{}", shape.bottom);
+    }
+
+
+    #[test]
+    fn walls_taller_than_a_step_stop_the_character() {
+        let floor = floor();
+        let wall = Rectangle::new(1.0, 3.0, 1.0, 0.0);
+        let obstacles = [Obstacle::solid(&floor), Obstacle::solid(&wall)];
+
+        let mut controller = CharacterController::new();
+        controller.step_height = 0.3;
+
+        let mut shape = standing(&mut controller, &obstacles);
+        let state = controller.move_shape(&mut shape, Vector2::new(2.0, 0.0), &obstacles);
+
+        assert!(state.wall);
+        assert!(state.grounded);
+        assert!(shape.right <= 1.0 && shape.right > 1.0 - 2.0 * controller.skin, "{}", shape.right);
+        assert!(shape.bottom < 2.0 * controller.skin);
+    }
+
+
+    #[test]
+    fn one_way_obstacles_only_stop_falling() {
+        let platform = Rectangle::new(-1.0, 1.0, 2.0, 1.8);
+        let obstacles = [Obstacle::one_way(&platform)];
+        let mut controller = CharacterController::new();
+
+        // Jumping up through the platform
+        let mut shape = Rectangle::new(0.0, 0.5, 1.5, 0.5);
+        controller.move_shape(&mut shape, Vector2::new(0.0, 2.0), &obstacles);
+        assert!((shape.bottom - 2.5).abs() < EPSILON);
+
+        // And landing on it
+        let state = controller.move_shape(&mut shape, Vector2::new(0.0, -2.0), &obstacles);
+        assert!(state.grounded);
+        assert!(shape.bottom >= 2.0 && shape.bottom < 2.0 + 2.0 * controller.skin, "{}", shape.bottom);
+    }
+}
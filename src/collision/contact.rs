@@ -0,0 +1,240 @@
+use Vector2;
+use collision::Collide;
+use collision::gjk_simplex;
+use collision::epa;
+
+
+/// A single point of contact between two objects
+#[derive(Copy, Clone, Debug)]
+pub struct ContactPoint {
+    /// The deepest point of the first object inside the second
+    pub on_a: Vector2,
+
+    /// The deepest point of the second object inside the first
+    pub on_b: Vector2,
+
+    /// How far the objects overlap at this point
+    pub depth: f64,
+}
+
+
+/// Describes how two objects overlap
+#[derive(Clone, Debug)]
+pub struct Manifold {
+    /// The direction from the first object toward the second
+    pub normal: Vector2,
+
+    /// How far the objects have to move along the normal to stop overlapping
+    pub depth: f64,
+
+    /// One point for corners and curves, or two for edges resting against each other
+    pub points: Vec<ContactPoint>,
+}
+
+
+/// Returns the contact information between two overlapping convex objects
+pub fn manifold(a: &Collide, b: &Collide) -> Option<Manifold> {
+    let simplex = gjk_simplex(a, b)?;
+    let penetration = epa(a, b, simplex);
+    let normal = penetration.normal;
+    let depth = penetration.depth;
+
+    if let Some(points) = clip_edges(a, b, normal) {
+        return Some(Manifold { normal, depth, points });
+    }
+
+    // The closest point on the edge of the minkowski difference maps back to a point on each object
+    let start = penetration.start;
+    let end = penetration.end;
+    let edge = end.point - start.point;
+    let length = edge.dot(edge);
+
+    let t = if length > 0.0 {
+        (-start.point.dot(edge) / length).max(0.0).min(1.0)
+    } else {
+        0.0
+    };
+
+    Some(Manifold {
+        normal,
+        depth,
+        points: vec![ContactPoint {
+            on_a: start.a + (end.a - start.a) * t,
+            on_b: start.b + (end.b - start.b) * t,
+            depth,
+        }],
+    })
+}
+
+
+/// Returns the flat edge of an object facing a direction, if it has one
+fn flat_edge(shape: &Collide, direction: Vector2) -> Option<(Vector2, Vector2)> {
+    // Edges tilted less than this from the direction count as facing it
+    const ANGLE: f64 = 0.02;
+    const TOLERANCE: f64 = 1e-9;
+
+    let farthest = shape.get_farthest_point(direction);
+    let right = shape.get_farthest_point(rotate(direction, -ANGLE));
+    let left = shape.get_farthest_point(rotate(direction, ANGLE));
+
+    let edge = left - right;
+    let length = edge.dot(edge).sqrt();
+
+    // Curved surfaces also return different points when the direction turns,
+    // but only a flat edge has its end points as far out as the farthest point
+    let reach = direction.dot(farthest);
+    let scale = TOLERANCE * (1.0 + reach.abs() + length);
+
+    if length > scale && direction.dot(left).max(direction.dot(right)) >= reach - scale {
+        Some((right, left))
+    } else {
+        None
+    }
+}
+
+
+/// Finds up to two contact points by clipping the facing edges of both objects against each other
+fn clip_edges(a: &Collide, b: &Collide, normal: Vector2) -> Option<Vec<ContactPoint>> {
+    const TOLERANCE: f64 = 1e-6;
+
+    let edge_a = flat_edge(a, normal)?;
+    let edge_b = flat_edge(b, -normal)?;
+
+    // The edge most perpendicular to the normal is used as reference
+    let along = |edge: (Vector2, Vector2)| {
+        let d = edge.1 - edge.0;
+        normal.dot(d).abs() / d.dot(d).sqrt()
+    };
+
+    let a_is_reference = along(edge_a) <= along(edge_b);
+    let (reference, incident, outward) = if a_is_reference {
+        (edge_a, edge_b, normal)
+    } else {
+        (edge_b, edge_a, -normal)
+    };
+
+    // Cut the incident edge to the extent of the reference edge
+    let tangent = reference.1 - reference.0;
+    let tangent = tangent / tangent.dot(tangent).sqrt();
+
+    let (min, max) = {
+        let start = tangent.dot(reference.0);
+        let end = tangent.dot(reference.1);
+        (start.min(end), start.max(end))
+    };
+
+    let (mut p, mut q) = incident;
+    let (tp, tq) = (tangent.dot(p), tangent.dot(q));
+
+    if tp.max(tq) < min || tp.min(tq) > max {
+        return None;
+    }
+
+    if tp != tq {
+        let clip = |point: Vector2, t: f64, other: Vector2, t_other: f64| {
+            if t < min {
+                point + (other - point) * ((min - t) / (t_other - t))
+            } else if t > max {
+                point + (other - point) * ((max - t) / (t_other - t))
+            } else {
+                point
+            }
+        };
+
+        let clipped_p = clip(p, tp, q, tq);
+        let clipped_q = clip(q, tq, p, tp);
+        p = clipped_p;
+        q = clipped_q;
+    }
+
+    // Only points behind the reference edge are touching
+    let surface = outward.dot(reference.0);
+    let mut points = Vec::with_capacity(2);
+
+    for &point in [p, q].iter() {
+        let depth = surface - outward.dot(point);
+
+        if depth >= -TOLERANCE {
+            let on_reference = point + outward * depth;
+
+            points.push(if a_is_reference {
+                ContactPoint { on_a: on_reference, on_b: point, depth }
+            } else {
+                ContactPoint { on_a: point, on_b: on_reference, depth }
+            });
+        }
+    }
+
+    if points.is_empty() {
+        None
+    } else {
+        Some(points)
+    }
+}
+
+
+/// Returns a vector rotated counter-clockwise
+fn rotate(v: Vector2, angle: f64) -> Vector2 {
+    let (sin, cos) = angle.sin_cos();
+    Vector2::new(cos * v.x - sin * v.y, sin * v.x + cos * v.y)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+    use shapes::Rectangle;
+
+    const EPSILON: f64 = 1e-9;
+
+
+    #[test]
+    fn boxes_resting_on_boxes_touch_at_two_points() {
+        let ground = Rectangle::new(-2.0, 2.0, 0.0, -1.0);
+        let block = Rectangle::new(-0.5, 0.5, 0.9, -0.1);
+
+        let manifold = manifold(&ground, &block).unwrap();
+
+        assert!((manifold.normal.x).abs() < EPSILON && (manifold.normal.y - 1.0).abs() < EPSILON);
+        assert!((manifold.depth - 0.1).abs() < EPSILON);
+        assert_eq!(manifold.points.len(), 2);
+
+        let mut corners: Vec<f64> = manifold.points.iter().map(|point| point.on_b.x).collect();
+        corners.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        assert!((corners[0] + 0.5).abs() < EPSILON && (corners[1] - 0.5).abs() < EPSILON);
+
+        for point in manifold.points.iter() {
+            assert!((point.depth - 0.1).abs() < EPSILON);
+            assert!((point.on_b.y + 0.1).abs() < EPSILON);
+            assert!(point.on_a.y.abs() < EPSILON);
+        }
+    }
+
+
+    #[test]
+    fn overlapping_circles_touch_at_one_point() {
+        let a = Circle::new(Vector2::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Vector2::new(1.5, 0.0), 1.0);
+
+        let manifold = manifold(&a, &b).unwrap();
+
+        assert!((manifold.depth - 0.5).abs() < 1e-4);
+        assert!((manifold.normal.x - 1.0).abs() < 1e-3);
+        assert_eq!(manifold.points.len(), 1);
+
+        // The deepest points are where each circle crosses the line between the centers
+        let point = manifold.points[0];
+        assert!((point.on_a.x - 1.0).abs() < 1e-3 && point.on_a.y.abs() < 1e-2);
+        assert!((point.on_b.x - 0.5).abs() < 1e-3 && point.on_b.y.abs() < 1e-2);
+    }
+
+
+    #[test]
+    fn separated_objects_have_no_manifold() {
+        let a = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+        let b = Rectangle::new(2.0, 3.0, 1.0, 0.0);
+
+        assert!(manifold(&a, &b).is_none());
+    }
+}
@@ -0,0 +1,233 @@
+use Vector2;
+use shapes::cross;
+use collision::Collide;
+use collision::SupportPoint;
+use collision::support;
+use collision::intersect;
+
+
+/// The closest points between two separated objects
+#[derive(Copy, Clone, Debug)]
+pub struct Distance {
+    /// The distance between the objects
+    pub distance: f64,
+
+    /// The point on the first object closest to the second
+    pub point_a: Vector2,
+
+    /// The point on the second object closest to the first
+    pub point_b: Vector2,
+
+    /// The direction from the first object toward the second
+    pub normal: Vector2,
+}
+
+
+/// Returns the distance and closest points between two convex objects, or None if they overlap
+pub fn distance(a: &Collide, b: &Collide) -> Option<Distance> {
+    const MAX_ITERATIONS: usize = 64;
+    const TOLERANCE: f64 = 1e-10;
+
+    // Start searching from the direction between the objects' bounding boxes
+    let (box_a, box_b) = (a.get_bounding_box(), b.get_bounding_box());
+    let mut direction = Vector2::new(
+        (box_b.left + box_b.right) - (box_a.left + box_a.right),
+        (box_b.top + box_b.bottom) - (box_a.top + box_a.bottom)
+    );
+    if direction.dot(direction) == 0.0 {
+        direction = Vector2::new(1.0, 0.0);
+    }
+
+    let mut simplex = vec![support(a, b, direction)];
+    let mut closest = closest_to_origin(&simplex)?;
+
+    for _ in 0..MAX_ITERATIONS {
+        let v = closest.point;
+        let length_squared = v.dot(v);
+
+        if length_squared <= TOLERANCE * TOLERANCE {
+            // The objects are touching, unless the origin is inside the minkowski difference
+            return if intersect(a, b) { None } else { Some(closest.distance()) };
+        }
+
+        let next = support(a, b, -v);
+
+        // Stop once the new point doesn't get any closer to the origin
+        if length_squared - v.dot(next.point) <= TOLERANCE * length_squared {
+            break;
+        }
+
+        if closest.simplex.iter().any(|s| s.point.x == next.point.x && s.point.y == next.point.y) {
+            break;
+        }
+
+        simplex = closest.simplex.clone();
+        simplex.push(next);
+
+        closest = closest_to_origin(&simplex)?;
+    }
+
+    Some(closest.distance())
+}
+
+
+/// The point on a simplex closest to the origin
+struct Closest {
+    point: Vector2,
+
+    /// The smallest part of the simplex containing the point
+    simplex: Vec<SupportPoint>,
+
+    /// How much each point of the reduced simplex contributes to the closest point
+    weights: Vec<f64>,
+}
+
+
+impl Closest {
+    fn distance(&self) -> Distance {
+        let mut point_a = Vector2::new(0.0, 0.0);
+        let mut point_b = Vector2::new(0.0, 0.0);
+
+        for (support, &weight) in self.simplex.iter().zip(self.weights.iter()) {
+            point_a += support.a * weight;
+            point_b += support.b * weight;
+        }
+
+        let distance = self.point.dot(self.point).sqrt();
+        let normal = if distance > 0.0 {
+            -self.point / distance
+        } else {
+            Vector2::new(0.0, 0.0)
+        };
+
+        Distance { distance, point_a, point_b, normal }
+    }
+}
+
+
+/// Returns the closest point on a simplex to the origin, or None if the origin is inside it
+fn closest_to_origin(simplex: &[SupportPoint]) -> Option<Closest> {
+    match simplex.len() {
+        1 => Some(Closest {
+            point: simplex[0].point,
+            simplex: simplex.to_vec(),
+            weights: vec![1.0],
+        }),
+
+        2 => Some(closest_on_segment(simplex[0], simplex[1])),
+
+        _ => {
+            let (a, b, c) = (simplex[0], simplex[1], simplex[2]);
+
+            let area = cross(b.point - a.point, c.point - a.point);
+            if area != 0.0 {
+                let u = cross(b.point, c.point) / area;
+                let v = cross(c.point, a.point) / area;
+                let w = cross(a.point, b.point) / area;
+
+                if u >= 0.0 && v >= 0.0 && w >= 0.0 {
+                    return None;
+                }
+            }
+
+            // The closest point is on one of the edges
+            let mut best: Option<Closest> = None;
+
+            for &(p, q) in [(a, b), (b, c), (c, a)].iter() {
+                let candidate = closest_on_segment(p, q);
+
+                let closer = match best {
+                    Some(ref best) => candidate.point.dot(candidate.point) < best.point.dot(best.point),
+                    None => true
+                };
+
+                if closer {
+                    best = Some(candidate);
+                }
+            }
+
+            best
+        }
+    }
+}
+
+
+fn closest_on_segment(a: SupportPoint, b: SupportPoint) -> Closest {
+    let edge = b.point - a.point;
+    let length = edge.dot(edge);
+
+    let t = if length > 0.0 { -a.point.dot(edge) / length } else { 0.0 };
+
+    if t <= 0.0 {
+        Closest { point: a.point, simplex: vec![a], weights: vec![1.0] }
+    } else if t >= 1.0 {
+        Closest { point: b.point, simplex: vec![b], weights: vec![1.0] }
+    } else {
+        Closest {
+            point: a.point + edge * t,
+            simplex: vec![a, b],
+            weights: vec![1.0 - t, t],
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+    use shapes::Rectangle;
+
+
+    fn length(v: Vector2) -> f64 {
+        v.dot(v).sqrt()
+    }
+
+
+    #[test]
+    fn circles_are_their_center_distance_minus_the_radii_apart() {
+        for i in 0..32 {
+            let angle = i as f64 * 0.2;
+            let (r1, r2) = (0.5 + 0.1 * (i % 5) as f64, 0.3 + 0.2 * (i % 3) as f64);
+            let d = r1 + r2 + 0.05 + 0.3 * i as f64;
+
+            let direction = Vector2::new(angle.cos(), angle.sin());
+            let a = Circle::new(Vector2::new(1.0, -2.0), r1);
+            let b = Circle::new(a.center + direction * d, r2);
+
+            let found = distance(&a, &b).unwrap();
+
+            assert!((found.distance - (d - r1 - r2)).abs() < 1e-4, "{} instead of {}", found.distance, d - r1 - r2);
+            assert!(length(found.normal - direction) < 1e-3);
+            assert!(length(found.point_a - (a.center + direction * r1)) < 1e-3);
+            assert!(length(found.point_b - (b.center - direction * r2)) < 1e-3);
+        }
+    }
+
+
+    #[test]
+    fn boxes_measure_between_faces_and_corners() {
+        let a = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+
+        let beside = Rectangle::new(3.0, 4.0, 1.5, 0.5);
+        let found = distance(&a, &beside).unwrap();
+        assert!((found.distance - 2.0).abs() < 1e-9);
+        assert!(length(found.normal - Vector2::new(1.0, 0.0)) < 1e-9);
+        assert!((found.point_a.x - 1.0).abs() < 1e-9 && (found.point_b.x - 3.0).abs() < 1e-9);
+
+        let diagonal = Rectangle::new(2.0, 3.0, 3.0, 2.0);
+        let found = distance(&a, &diagonal).unwrap();
+        assert!((found.distance - 2.0f64.sqrt()).abs() < 1e-9);
+        assert!(length(found.point_a - Vector2::new(1.0, 1.0)) < 1e-9);
+        assert!(length(found.point_b - Vector2::new(2.0, 2.0)) < 1e-9);
+    }
+
+
+    #[test]
+    fn overlapping_objects_have_no_distance() {
+        let a = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+        let b = Circle::new(Vector2::new(1.0, 0.5), 0.5);
+
+        assert!(distance(&a, &b).is_none());
+    }
+}
@@ -0,0 +1,47 @@
+/// Decides which objects collide with each other.
+///
+/// Two objects collide if each one's mask contains the other's category,
+/// unless they share a group: a positive group always collides, a negative one never does.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CollisionFilter {
+    /// The bits describing what the object is
+    pub category: u32,
+
+    /// The bits of the categories the object collides with
+    pub mask: u32,
+
+    /// Objects with the same non-zero group ignore the category and mask bits
+    pub group: i32,
+}
+
+
+impl CollisionFilter {
+    /// A filter in a category, colliding with the categories in the mask
+    pub fn new(category: u32, mask: u32) -> CollisionFilter {
+        CollisionFilter { category, mask, group: 0 }
+    }
+
+
+    /// The same filter in a group
+    pub fn with_group(self, group: i32) -> CollisionFilter {
+        CollisionFilter { group, ..self }
+    }
+
+
+    /// Returns true if objects with these filters collide
+    pub fn collides_with(&self, other: &CollisionFilter) -> bool {
+        if self.group != 0 && self.group == other.group {
+            return self.group > 0;
+        }
+
+        self.mask & other.category != 0 && other.mask & self.category != 0
+    }
+}
+
+
+/// The first category, colliding with everything
+impl Default for CollisionFilter {
+    fn default() -> CollisionFilter {
+        CollisionFilter::new(1, !0)
+    }
+}
@@ -1,6 +1,50 @@
 use Vector2;
+use shapes::cross;
 use Rectangle;
 
+
+mod tilemap;
+pub use self::tilemap::TileMap;
+pub use self::tilemap::Tile;
+pub use self::tilemap::TileContact;
+
+mod contact;
+pub use self::contact::Manifold;
+pub use self::contact::ContactPoint;
+pub use self::contact::manifold;
+
+mod distance;
+pub use self::distance::Distance;
+pub use self::distance::distance;
+
+mod shape_cast;
+pub use self::shape_cast::TimeOfImpact;
+pub use self::shape_cast::shape_cast;
+
+mod raycast;
+pub use self::raycast::Ray;
+pub use self::raycast::RayHit;
+pub use self::raycast::raycast;
+pub use self::raycast::raycast_all;
+pub use self::raycast::raycast_first;
+
+mod broad_phase;
+pub use self::broad_phase::BroadPhase;
+pub use self::broad_phase::ProxyId;
+
+mod filter;
+pub use self::filter::CollisionFilter;
+
+mod sensor;
+pub use self::sensor::Sensors;
+pub use self::sensor::SensorEvent;
+pub(crate) use self::sensor::sensor_events;
+
+mod character;
+pub use self::character::CharacterController;
+pub use self::character::CharacterState;
+pub use self::character::Obstacle;
+
 pub trait Collide: AsCollide {
     /// Return the point the furthest along an axis
     fn get_farthest_point(&self, axis: Vector2) -> Vector2;
@@ -11,6 +55,17 @@ pub trait Collide: AsCollide {
 }
 
 
+impl<C: Collide + ?Sized> Collide for Box<C> {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        (**self).get_farthest_point(axis)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        (**self).get_bounding_box()
+    }
+}
+
+
 pub trait AsCollide {
     fn as_collide(&self) -> &Collide;
 }
@@ -23,63 +78,184 @@ impl<T: Collide> AsCollide for T {
 
 
 
-/// Returns true if two convex objects are intersecting
+/// Returns true if two convex objects are intersecting.
+/// Objects that only touch, without overlapping, are not intersecting.
 pub fn intersect(a: &Collide, b: &Collide) -> bool {
     gjk_simplex(a, b).is_some()
 }
 
 
-/// Returns the minimum translation vector to move two objects out of an overlap
+/// Returns the minimum translation vector to move two objects out of an overlap.
+/// Moving the first object by the negated vector separates them.
 pub fn overlap(a: &Collide, b: &Collide) -> Option<Vector2> {
+    if let Some(simplex) = gjk_simplex(a, b).take() {
+        let penetration = epa(a, b, simplex);
+        return Some(penetration.normal * penetration.depth);
+    }
+
+    None
+}
+
+
+/// A point on the minkowski difference, along with the points on each object that produced it
+#[derive(Copy, Clone, Debug)]
+pub(crate) struct SupportPoint {
+    pub point: Vector2,
+    pub a: Vector2,
+    pub b: Vector2,
+}
+
+
+/// The edge of the minkowski difference closest to the origin
+pub(crate) struct Penetration {
+    /// Points from the first object toward the second
+    pub normal: Vector2,
+    pub depth: f64,
+
+    pub start: SupportPoint,
+    pub end: SupportPoint,
+}
+
+
+/// The most iterations GJK and EPA may take before settling for their current result.
+/// Both converge in a handful of iterations for polygons, curved objects may need more.
+const MAX_ITERATIONS: usize = 64;
+
+
+/// Expands a simplex containing the origin until the closest edge of the minkowski difference is found
+pub(crate) fn epa(a: &Collide, b: &Collide, simplex: Vec<SupportPoint>) -> Penetration {
     const TOLERANCE: f64 = 1e-7;
 
-    if let Some(mut simplex) = gjk_simplex(a, b).take() {
-//        println!("EPA: {:?}", simplex);
+    let mut polytope = match initial_polytope(a, b, simplex) {
+        Ok(polytope) => polytope,
+
+        // The minkowski difference has no area, so the objects only touch
+        Err(penetration) => return penetration,
+    };
+
+    let mut closest = closest_edge(&polytope);
 
-        if simplex[0].x.is_nan() {
-            return Some(Vector2::new(0.0, 0.0));
+    for _ in 0..MAX_ITERATIONS {
+        let (index, normal, distance) = closest;
+
+        let p = support(a, b, normal);
+
+        // Stop once the boundary can't be pushed out any further along the normal
+        if normal.dot(p.point) - distance < TOLERANCE {
+            break;
         }
 
-        loop {
-            use std::f64::INFINITY;
-            // Find the edge closest to the origin
-            let mut normal = Vector2::new(0.0, 0.0);
-            let mut min_distance = INFINITY;
-            let mut index = 0;
-
-            for i in 0..simplex.len() {
-                let a = simplex[i];
-                let b = simplex[(i + 1) % simplex.len()];
-
-                let n = -toward_origin(a, b).normal();
-                let distance = n.dot(a);
-                if distance < min_distance {
-                    normal = n;
-                    min_distance = distance;
-                    index = i;
-                }
-            }
+        polytope.insert(index + 1, p);
+        closest = closest_edge(&polytope);
+    }
 
+    let (index, normal, distance) = closest;
 
-            let p = support(a, b, normal);
-            let delta = min_distance - normal.dot(p);
+    Penetration {
+        normal,
+        depth: distance.max(0.0),
+        start: polytope[index],
+        end: polytope[(index + 1) % polytope.len()],
+    }
+}
 
-//            println!("Delta: {}", delta);
 
-            if delta.abs() < TOLERANCE {
-                return Some(normal * min_distance);
-            } else {
-                simplex.insert(index + 1, p);
+/// Turns the final GJK simplex into a counter-clockwise triangle, or returns the
+/// penetration directly if the minkowski difference is flat
+fn initial_polytope(a: &Collide, b: &Collide, mut simplex: Vec<SupportPoint>) -> Result<Vec<SupportPoint>, Penetration> {
+    const TOLERANCE: f64 = 1e-10;
+
+    let area = if simplex.len() == 3 {
+        cross(simplex[1].point - simplex[0].point, simplex[2].point - simplex[0].point)
+    } else {
+        0.0
+    };
+
+    if area > 0.0 {
+        return Ok(simplex);
+    } else if area < 0.0 {
+        simplex.swap(0, 1);
+        return Ok(simplex);
+    }
+
+    // The simplex is a line through the origin, use its longest side and look for points on either side of it
+    let mut start = simplex[0];
+    let mut end = simplex[simplex.len() - 1];
+    for i in 0..simplex.len() {
+        for j in i + 1..simplex.len() {
+            let d = simplex[j].point - simplex[i].point;
+            let longest = end.point - start.point;
+            if d.dot(d) > longest.dot(longest) {
+                start = simplex[i];
+                end = simplex[j];
             }
         }
     }
 
-    None
+    let edge = end.point - start.point;
+    let length = edge.dot(edge).sqrt();
+    let normal = if length > 0.0 {
+        Vector2::new(edge.y, -edge.x) / length
+    } else {
+        Vector2::new(1.0, 0.0)
+    };
+
+    for &direction in [normal, -normal].iter() {
+        let p = support(a, b, direction);
+
+        if direction.dot(p.point - start.point) > TOLERANCE * (1.0 + length) {
+            return Ok(if direction.dot(normal) > 0.0 {
+                vec![end, start, p]
+            } else {
+                vec![start, end, p]
+            });
+        }
+    }
+
+    Err(Penetration {
+        normal,
+        depth: 0.0,
+        start,
+        end,
+    })
+}
+
+
+/// Returns the index, outward normal and distance of the edge of a counter-clockwise polytope closest to the origin
+fn closest_edge(polytope: &[SupportPoint]) -> (usize, Vector2, f64) {
+    use std::f64::INFINITY;
+
+    let mut normal = Vector2::new(1.0, 0.0);
+    let mut min_distance = INFINITY;
+    let mut index = 0;
+
+    for i in 0..polytope.len() {
+        let a = polytope[i].point;
+        let b = polytope[(i + 1) % polytope.len()].point;
+
+        let edge = b - a;
+        let length = edge.dot(edge).sqrt();
+
+        // Duplicate points have no edge between them
+        if length == 0.0 {
+            continue;
+        }
+
+        let n = Vector2::new(edge.y, -edge.x) / length;
+        let distance = n.dot(a);
+        if distance < min_distance {
+            normal = n;
+            min_distance = distance;
+            index = i;
+        }
+    }
+
+    (index, normal, min_distance)
 }
 
 
 /// Returns the final simplex between two convex objects, if there is one
-fn gjk_simplex(a: &Collide, b: &Collide) -> Option<Vec<Vector2>> {
+pub(crate) fn gjk_simplex(a: &Collide, b: &Collide) -> Option<Vec<SupportPoint>> {
     let mut simplex = Vec::new();
     let mut direction = Vector2{ x: 1.0, y: 0.0 };
 
@@ -87,51 +263,69 @@ fn gjk_simplex(a: &Collide, b: &Collide) -> Option<Vec<Vector2>> {
 
     direction = -direction;
 
-    loop {
+    for _ in 0..MAX_ITERATIONS {
         let last = support(a, b, direction);
-        simplex.push(last);
 
-        if direction.dot(last) <= 0.0 {
+        if direction.dot(last.point) <= 0.0 {
             return None;
-        } else {
-            // Determine if the simplex contains the origin
-            if {
-                let a = simplex[0];
-                let b = simplex[1];
-
-                if simplex.len() == 3 {
-                    let a_perp = toward_origin(a, last);
-                    let b_perp = toward_origin(b, last);
-
-                    if a_perp.dot(b) < 0.0 {
-                        simplex.remove(1);
-                        direction = a_perp;
-                        false
-                    } else if b_perp.dot(a) < 0.0 {
-                        simplex.remove(0);
-                        direction = b_perp;
-                        false
-                    } else {
-                        true
-                    }
-                } else {
-                    direction = toward_origin(a, b);
+        }
+
+        simplex.push(last);
+
+        // Determine if the simplex contains the origin
+        if {
+            let a = simplex[0].point;
+            let b = simplex[1].point;
+            let last = last.point;
+
+            if simplex.len() == 3 {
+                let a_perp = toward_origin(a, last);
+                let b_perp = toward_origin(b, last);
+
+                if a_perp.dot(b) < 0.0 {
+                    simplex.remove(1);
+                    direction = a_perp;
                     false
+                } else if b_perp.dot(a) < 0.0 {
+                    simplex.remove(0);
+                    direction = b_perp;
+                    false
+                } else {
+                    true
                 }
-            } {
-                return Some(simplex);
+            } else {
+                direction = toward_origin(a, b);
+                false
+            }
+        } {
+            return Some(simplex);
+        }
+
+        // Two identical points give no direction, search toward the origin from one of them instead
+        if direction.dot(direction) == 0.0 {
+            simplex.pop();
+            direction = -simplex[0].point;
+
+            if direction.dot(direction) == 0.0 {
+                return None;
             }
         }
     }
+
+    None
 }
 
 
 /// Return the point farthest along an axis on the minkowski difference
-fn support(a: &Collide, b: &Collide, direction: Vector2) -> Vector2 {
+pub(crate) fn support(a: &Collide, b: &Collide, direction: Vector2) -> SupportPoint {
     let p1 = a.get_farthest_point(direction);
     let p2 = b.get_farthest_point(-direction);
 
-    p1 - p2
+    SupportPoint {
+        point: p1 - p2,
+        a: p1,
+        b: p2,
+    }
 }
 
 
@@ -144,4 +338,195 @@ fn toward_origin(a: Vector2, b: Vector2) -> Vector2 {
     } else {
         direction
     }
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+
+
+    /// A small deterministic generator, so failures can be reproduced
+    struct Random(u64);
+
+    impl Random {
+        fn next(&mut self) -> f64 {
+            // xorshift64*
+            self.0 ^= self.0 >> 12;
+            self.0 ^= self.0 << 25;
+            self.0 ^= self.0 >> 27;
+            (self.0.wrapping_mul(0x2545F4914F6CDD1D) >> 11) as f64 / (1u64 << 53) as f64
+        }
+
+        fn range(&mut self, min: f64, max: f64) -> f64 {
+            min + (max - min) * self.next()
+        }
+    }
+
+
+    fn length(v: Vector2) -> f64 {
+        v.dot(v).sqrt()
+    }
+
+
+    #[test]
+    fn circles_match_the_analytic_overlap() {
+        let mut random = Random(0x5EED);
+        let mut tested = 0;
+
+        for _ in 0..2000 {
+            let a = Circle::new(Vector2::new(random.range(-5.0, 5.0), random.range(-5.0, 5.0)), random.range(0.1, 3.0));
+            let b = Circle::new(Vector2::new(random.range(-5.0, 5.0), random.range(-5.0, 5.0)), random.range(0.1, 3.0));
+
+            let offset = b.center - a.center;
+            let distance = length(offset);
+            let depth = a.radius + b.radius - distance;
+
+            // Touching and concentric circles are covered separately
+            if depth.abs() < 1e-3 || distance < 1e-3 {
+                continue;
+            }
+
+            match overlap(&a, &b) {
+                Some(translation) => {
+                    assert!(depth > 0.0, "separated circles overlap by {}", length(translation));
+
+                    let found = length(translation);
+                    assert!((found - depth).abs() < 1e-4 * (1.0 + depth), "depth {} instead of {}", found, depth);
+
+                    let normal = translation / found;
+                    let expected = offset / distance;
+                    assert!(length(normal - expected) < 1e-3, "normal off by {}", length(normal - expected));
+                },
+
+                None => assert!(depth < 0.0, "overlapping circles missed, depth {}", depth),
+            }
+
+            assert_eq!(intersect(&a, &b), depth > 0.0);
+            tested += 1;
+        }
+
+        assert!(tested > 1000);
+    }
+
+
+    #[test]
+    fn boxes_match_the_analytic_overlap() {
+        let mut random = Random(0xB0B);
+        let mut tested = 0;
+
+        for _ in 0..2000 {
+            let make = |random: &mut Random| {
+                let (x, y) = (random.range(-5.0, 5.0), random.range(-5.0, 5.0));
+                let (w, h) = (random.range(0.1, 4.0), random.range(0.1, 4.0));
+                Rectangle::new(x, x + w, y + h, y)
+            };
+            let a = make(&mut random);
+            let b = make(&mut random);
+
+            // The distance the first box has to move along each direction to get out
+            let candidates = [
+                (a.right - b.left, Vector2::new(1.0, 0.0)),
+                (b.right - a.left, Vector2::new(-1.0, 0.0)),
+                (a.top - b.bottom, Vector2::new(0.0, 1.0)),
+                (b.top - a.bottom, Vector2::new(0.0, -1.0)),
+            ];
+
+            let mut sorted = candidates.to_vec();
+            sorted.sort_by(|x, y| x.0.partial_cmp(&y.0).unwrap());
+            let (depth, normal) = sorted[0];
+
+            // Skip near ties, where either normal is correct, and near touching boxes
+            if sorted[1].0 - depth < 1e-6 || depth.abs() < 1e-6 {
+                continue;
+            }
+
+            match overlap(&a, &b) {
+                Some(translation) => {
+                    assert!(depth > 0.0, "separated boxes overlap by {}", length(translation));
+
+                    let found = length(translation);
+                    assert!((found - depth).abs() < 1e-9 * (1.0 + depth), "depth {} instead of {}", found, depth);
+                    assert!(length(translation / found - normal) < 1e-9, "normal off by {}", length(translation / found - normal));
+                },
+
+                None => assert!(depth < 0.0, "overlapping boxes missed, depth {}", depth),
+            }
+
+            tested += 1;
+        }
+
+        assert!(tested > 1000);
+    }
+
+
+    #[test]
+    fn touching_shapes_do_not_intersect() {
+        let a = Circle::new(Vector2::new(0.0, 0.0), 1.0);
+        let b = Circle::new(Vector2::new(2.0, 0.0), 1.0);
+        assert!(!intersect(&a, &b));
+
+        let a = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+        let b = Rectangle::new(1.0, 2.0, 1.0, 0.0);
+        assert!(!intersect(&a, &b));
+        assert!(overlap(&a, &b).map_or(true, |translation| length(translation) < 1e-12));
+
+        // Sharing only a corner
+        let c = Rectangle::new(1.0, 2.0, 2.0, 1.0);
+        assert!(!intersect(&a, &c));
+    }
+
+
+    #[test]
+    fn coincident_centers_push_out_by_the_full_depth() {
+        let a = Circle::new(Vector2::new(1.0, 1.0), 1.0);
+        let b = Circle::new(Vector2::new(1.0, 1.0), 2.0);
+
+        let translation = overlap(&a, &b).unwrap();
+        assert!((length(translation) - 3.0).abs() < 1e-4);
+
+        let a = Rectangle::new(-1.0, 1.0, 1.0, -1.0);
+        let b = Rectangle::new(-2.0, 2.0, 1.0, -1.0);
+
+        let translation = overlap(&a, &b).unwrap();
+        assert!((length(translation) - 2.0).abs() < 1e-9);
+        assert!(translation.x.abs() < 1e-9);
+
+        let translation = overlap(&a, &a).unwrap();
+        assert!((length(translation) - 2.0).abs() < 1e-9);
+    }
+
+
+    #[test]
+    fn zero_size_rectangles() {
+        let container = Rectangle::new(0.0, 2.0, 2.0, 0.0);
+
+        // A point inside is pushed out through the closest side
+        let point = Rectangle::new(0.5, 0.5, 1.0, 1.0);
+        let translation = overlap(&point, &container).unwrap();
+        assert!(length(translation - Vector2::new(0.5, 0.0)) < 1e-9);
+
+        let outside = Rectangle::new(3.0, 3.0, 1.0, 1.0);
+        assert!(!intersect(&outside, &container));
+        assert!(overlap(&outside, &container).is_none());
+
+        // A vertical line through the box
+        let line = Rectangle::new(0.5, 0.5, 3.0, -1.0);
+        let translation = overlap(&line, &container).unwrap();
+        assert!(length(translation - Vector2::new(0.5, 0.0)) < 1e-9);
+
+        // Two collinear lines have a flat minkowski difference, any result has to be finite
+        let a = Rectangle::new(0.0, 2.0, 0.0, 0.0);
+        let b = Rectangle::new(1.0, 3.0, 0.0, 0.0);
+        if let Some(translation) = overlap(&a, &b) {
+            assert!(length(translation) < 1e-9);
+        }
+
+        // Two points in the same place
+        let a = Rectangle::new(1.0, 1.0, 1.0, 1.0);
+        if let Some(translation) = overlap(&a, &a) {
+            assert!(translation.x.is_finite() && translation.y.is_finite());
+        }
+    }
+}
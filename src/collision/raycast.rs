@@ -0,0 +1,212 @@
+use Vector2;
+use Rectangle;
+use collision::Collide;
+use collision::shape_cast;
+use collision::shape_cast::Point;
+
+use std::cmp::Ordering;
+
+
+/// A half-line starting at a point
+#[derive(Copy, Clone, Debug)]
+pub struct Ray {
+    pub origin: Vector2,
+
+    /// The direction of the ray, its length is the distance covered by a fraction of 1
+    pub direction: Vector2,
+}
+
+impl Ray {
+    pub fn new(origin: Vector2, direction: Vector2) -> Ray {
+        Ray { origin, direction }
+    }
+
+
+    /// Creates a ray from one point to another, the end is reached at a fraction of 1
+    pub fn between(start: Vector2, end: Vector2) -> Ray {
+        Ray { origin: start, direction: end - start }
+    }
+
+
+    /// Returns the point a fraction along the ray
+    pub fn point_at(&self, fraction: f64) -> Vector2 {
+        self.origin + self.direction * fraction
+    }
+}
+
+
+/// Where a ray hit an object
+#[derive(Copy, Clone, Debug)]
+pub struct RayHit {
+    pub point: Vector2,
+
+    /// The normal of the surface that was hit
+    pub normal: Vector2,
+
+    /// How far along the ray's direction the hit is
+    pub fraction: f64,
+}
+
+
+/// Casts a ray against a convex object, hitting it no further than `max_fraction` along the ray.
+/// Rays starting inside an object hit it immediately.
+pub fn raycast(ray: &Ray, shape: &Collide, max_fraction: f64) -> Option<RayHit> {
+    if max_fraction <= 0.0 || ray.direction.dot(ray.direction) == 0.0 {
+        return None;
+    }
+
+    // A ray is a point moving along the direction
+    shape_cast(&Point(ray.origin), ray.direction * max_fraction, shape).map(|impact| {
+        let fraction = impact.time * max_fraction;
+
+        RayHit {
+            point: ray.point_at(fraction),
+            normal: impact.normal,
+            fraction,
+        }
+    })
+}
+
+
+/// Casts a ray against multiple objects, returning the index of every object hit along with where, closest first
+pub fn raycast_all<'a, I>(ray: &Ray, shapes: I, max_fraction: f64) -> Vec<(usize, RayHit)>
+    where I: IntoIterator<Item=&'a Collide>
+{
+    let mut hits = Vec::new();
+
+    for (index, shape) in shapes.into_iter().enumerate() {
+        if !ray_hits_box(ray, &shape.get_bounding_box(), max_fraction) {
+            continue;
+        }
+
+        if let Some(hit) = raycast(ray, shape, max_fraction) {
+            hits.push((index, hit));
+        }
+    }
+
+    hits.sort_by(|a, b| a.1.fraction.partial_cmp(&b.1.fraction).unwrap_or(Ordering::Equal));
+
+    hits
+}
+
+
+/// Casts a ray against multiple objects, returning the index of the closest object hit along with where
+pub fn raycast_first<'a, I>(ray: &Ray, shapes: I, max_fraction: f64) -> Option<(usize, RayHit)>
+    where I: IntoIterator<Item=&'a Collide>
+{
+    let mut closest: Option<(usize, RayHit)> = None;
+    let mut max_fraction = max_fraction;
+
+    for (index, shape) in shapes.into_iter().enumerate() {
+        if !ray_hits_box(ray, &shape.get_bounding_box(), max_fraction) {
+            continue;
+        }
+
+        if let Some(hit) = raycast(ray, shape, max_fraction) {
+            max_fraction = hit.fraction;
+            closest = Some((index, hit));
+        }
+    }
+
+    closest
+}
+
+
+/// Returns true if a ray passes through a rectangle before reaching a fraction
+pub(crate) fn ray_hits_box(ray: &Ray, rectangle: &Rectangle, max_fraction: f64) -> bool {
+    let mut enter = 0.0f64;
+    let mut exit = max_fraction;
+
+    let slabs = [
+        (ray.origin.x, ray.direction.x, rectangle.left, rectangle.right),
+        (ray.origin.y, ray.direction.y, rectangle.bottom, rectangle.top),
+    ];
+
+    for &(origin, direction, min, max) in slabs.iter() {
+        if direction == 0.0 {
+            if origin < min || origin > max {
+                return false;
+            }
+        } else {
+            let t0 = (min - origin) / direction;
+            let t1 = (max - origin) / direction;
+
+            enter = enter.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+
+            if enter > exit {
+                return false;
+            }
+        }
+    }
+
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+
+    const EPSILON: f64 = 1e-6;
+
+
+    fn close(a: Vector2, b: Vector2) -> bool {
+        (a - b).dot(a - b).sqrt() < EPSILON
+    }
+
+
+    #[test]
+    fn rays_hit_box_faces() {
+        let square = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+
+        let hit = raycast(&Ray::new(Vector2::new(-2.0, 0.5), Vector2::new(4.0, 0.0)), &square, 1.0).unwrap();
+        assert!((hit.fraction - 0.5).abs() < EPSILON);
+        assert!(close(hit.normal, Vector2::new(-1.0, 0.0)));
+        assert!(close(hit.point, Vector2::new(0.0, 0.5)));
+
+        let hit = raycast(&Ray::new(Vector2::new(0.25, 3.0), Vector2::new(0.0, -1.0)), &square, 10.0).unwrap();
+        assert!((hit.fraction - 2.0).abs() < EPSILON);
+        assert!(close(hit.normal, Vector2::new(0.0, 1.0)));
+        assert!(close(hit.point, Vector2::new(0.25, 1.0)));
+    }
+
+
+    #[test]
+    fn rays_hit_circles() {
+        let circle = Circle::new(Vector2::new(0.0, 0.0), 1.0);
+
+        let hit = raycast(&Ray::between(Vector2::new(-5.0, 0.0), Vector2::new(5.0, 0.0)), &circle, 1.0).unwrap();
+        assert!((hit.fraction - 0.4).abs() < 1e-4);
+        assert!((hit.normal.x + 1.0).abs() < 1e-3);
+    }
+
+
+    #[test]
+    fn rays_miss_behind_and_beyond() {
+        let square = Rectangle::new(0.0, 1.0, 1.0, 0.0);
+        let ray = Ray::new(Vector2::new(-2.0, 0.5), Vector2::new(1.0, 0.0));
+
+        assert!(raycast(&ray, &square, 1.5).is_none());
+        assert!(raycast(&Ray::new(ray.origin, -ray.direction), &square, 10.0).is_none());
+        assert!(raycast(&Ray::new(Vector2::new(-2.0, 1.5), ray.direction), &square, 10.0).is_none());
+    }
+
+
+    #[test]
+    fn the_first_hit_is_the_closest() {
+        let far = Rectangle::new(5.0, 6.0, 1.0, 0.0);
+        let near = Rectangle::new(2.0, 3.0, 1.0, 0.0);
+        let shapes: Vec<&Collide> = vec![&far, &near];
+
+        let ray = Ray::new(Vector2::new(0.0, 0.5), Vector2::new(1.0, 0.0));
+
+        let (index, hit) = raycast_first(&ray, shapes.iter().cloned(), 10.0).unwrap();
+        assert_eq!(index, 1);
+        assert!((hit.fraction - 2.0).abs() < EPSILON);
+
+        let all = raycast_all(&ray, shapes.iter().cloned(), 10.0);
+        assert_eq!(all.iter().map(|&(index, _)| index).collect::<Vec<_>>(), vec![1, 0]);
+    }
+}
@@ -0,0 +1,235 @@
+use collision::Collide;
+use collision::CollisionFilter;
+use collision::BroadPhase;
+use collision::ProxyId;
+use collision::intersect;
+
+use std::collections::HashSet;
+use std::hash::Hash;
+use std::mem;
+
+
+/// A change in what a sensor overlaps
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SensorEvent<Id> {
+    /// An object started overlapping a sensor
+    Enter { sensor: Id, other: Id },
+
+    /// An object is still overlapping a sensor
+    Stay { sensor: Id, other: Id },
+
+    /// An object stopped overlapping a sensor, or one of them was removed
+    Exit { sensor: Id, other: Id },
+}
+
+
+struct Object<S> {
+    shape: S,
+    sensor: bool,
+}
+
+
+/// Detects which objects overlap a set of sensors, without pushing them apart
+pub struct Sensors<S> {
+    broad_phase: BroadPhase<Object<S>>,
+
+    // Pairs of sensors and the objects overlapping them
+    overlapping: HashSet<(ProxyId, ProxyId)>,
+
+    events: Vec<SensorEvent<ProxyId>>,
+}
+
+
+impl<S: Collide> Sensors<S> {
+    /// Creates an empty set, cells work best when slightly larger than a typical object
+    pub fn new(cell_size: f64) -> Sensors<S> {
+        Sensors {
+            broad_phase: BroadPhase::new(cell_size),
+            overlapping: HashSet::new(),
+            events: Vec::new(),
+        }
+    }
+
+
+    /// Adds a sensor, detecting the objects its filter collides with
+    pub fn add_sensor(&mut self, shape: S, filter: CollisionFilter) -> ProxyId {
+        self.insert(shape, filter, true)
+    }
+
+    /// Adds an object that sensors can detect
+    pub fn add_object(&mut self, shape: S, filter: CollisionFilter) -> ProxyId {
+        self.insert(shape, filter, false)
+    }
+
+
+    /// Removes a sensor or an object, returning its shape.
+    /// Its overlaps end during the next update.
+    pub fn remove(&mut self, id: ProxyId) -> Option<S> {
+        self.broad_phase.remove(id).map(|object| object.shape)
+    }
+
+
+    pub fn shape(&self, id: ProxyId) -> Option<&S> {
+        self.broad_phase.get(id).map(|object| &object.shape)
+    }
+
+    /// Returns a shape to move it, the change is picked up by the next update
+    pub fn shape_mut(&mut self, id: ProxyId) -> Option<&mut S> {
+        self.broad_phase.get_mut(id).map(|object| &mut object.shape)
+    }
+
+
+    pub fn is_sensor(&self, id: ProxyId) -> bool {
+        self.broad_phase.get(id).map_or(false, |object| object.sensor)
+    }
+
+
+    /// Returns the objects overlapping a sensor during the last update
+    pub fn overlapping(&self, sensor: ProxyId) -> Vec<ProxyId> {
+        let mut others: Vec<ProxyId> = self.overlapping.iter()
+            .filter(|&&(s, _)| s == sensor)
+            .map(|&(_, other)| other)
+            .collect();
+
+        others.sort();
+        others
+    }
+
+
+    /// Finds the objects overlapping every sensor and adds the changes to the events
+    pub fn update(&mut self) {
+        for id in self.broad_phase.ids() {
+            let bounds = match self.broad_phase.get(id) {
+                Some(object) => object.shape.get_bounding_box(),
+                None => continue
+            };
+
+            self.broad_phase.update_bounds(id, bounds);
+        }
+
+        let mut current = HashSet::new();
+
+        for (a, b) in self.broad_phase.pairs() {
+            let (object_a, object_b) = match (self.broad_phase.get(a), self.broad_phase.get(b)) {
+                (Some(object_a), Some(object_b)) => (object_a, object_b),
+                _ => continue
+            };
+
+            // Sensors don't detect each other
+            let pair = match (object_a.sensor, object_b.sensor) {
+                (true, false) => (a, b),
+                (false, true) => (b, a),
+                _ => continue
+            };
+
+            if intersect(&object_a.shape, &object_b.shape) {
+                current.insert(pair);
+            }
+        }
+
+        let previous = mem::replace(&mut self.overlapping, current);
+        sensor_events(&previous, &self.overlapping, &mut self.events);
+    }
+
+
+    /// Removes and returns the events since the last time they were drained
+    pub fn drain_events(&mut self) -> Vec<SensorEvent<ProxyId>> {
+        mem::replace(&mut self.events, Vec::new())
+    }
+
+
+    fn insert(&mut self, shape: S, filter: CollisionFilter, sensor: bool) -> ProxyId {
+        let bounds = shape.get_bounding_box();
+
+        let id = self.broad_phase.insert_bounds(bounds, Object { shape, sensor });
+        self.broad_phase.set_filter(id, filter);
+
+        id
+    }
+}
+
+
+/// Adds the events between two sets of overlapping sensors and objects, in a consistent order
+pub(crate) fn sensor_events<Id>(previous: &HashSet<(Id, Id)>, current: &HashSet<(Id, Id)>,
+                                events: &mut Vec<SensorEvent<Id>>)
+    where Id: Copy + Eq + Hash + Ord
+{
+    let mut pairs: Vec<(Id, Id)> = current.iter().cloned().collect();
+    pairs.sort();
+
+    for (sensor, other) in pairs {
+        if previous.contains(&(sensor, other)) {
+            events.push(SensorEvent::Stay { sensor, other });
+        } else {
+            events.push(SensorEvent::Enter { sensor, other });
+        }
+    }
+
+    let mut ended: Vec<(Id, Id)> = previous.difference(current).cloned().collect();
+    ended.sort();
+
+    for (sensor, other) in ended {
+        events.push(SensorEvent::Exit { sensor, other });
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Vector2;
+    use shapes::Circle;
+
+
+    #[test]
+    fn objects_enter_stay_and_exit() {
+        let mut sensors = Sensors::new(4.0);
+
+        let sensor = sensors.add_sensor(Circle::new(Vector2::new(0.0, 0.0), 1.0), CollisionFilter::default());
+        let object = sensors.add_object(Circle::new(Vector2::new(5.0, 0.0), 0.5), CollisionFilter::default());
+
+        sensors.update();
+        assert!(sensors.drain_events().is_empty());
+
+        sensors.shape_mut(object).unwrap().center = Vector2::new(1.0, 0.0);
+        sensors.update();
+        assert_eq!(sensors.drain_events(), vec![SensorEvent::Enter { sensor, other: object }]);
+        assert_eq!(sensors.overlapping(sensor), vec![object]);
+
+        sensors.update();
+        sensors.update();
+        assert_eq!(sensors.drain_events(), vec![
+            SensorEvent::Stay { sensor, other: object },
+            SensorEvent::Stay { sensor, other: object },
+        ]);
+
+        sensors.shape_mut(object).unwrap().center = Vector2::new(-5.0, 0.0);
+        sensors.update();
+        sensors.update();
+        assert_eq!(sensors.drain_events(), vec![SensorEvent::Exit { sensor, other: object }]);
+        assert!(sensors.overlapping(sensor).is_empty());
+    }
+
+
+    #[test]
+    fn removing_an_object_ends_its_overlap() {
+        let mut sensors = Sensors::new(4.0);
+
+        let sensor = sensors.add_sensor(Circle::new(Vector2::new(0.0, 0.0), 1.0), CollisionFilter::default());
+        let object = sensors.add_object(Circle::new(Vector2::new(0.5, 0.0), 0.5), CollisionFilter::default());
+        let other_sensor = sensors.add_sensor(Circle::new(Vector2::new(0.0, 0.5), 1.0), CollisionFilter::default());
+
+        sensors.update();
+        assert_eq!(sensors.drain_events(), vec![
+            SensorEvent::Enter { sensor, other: object },
+            SensorEvent::Enter { sensor: other_sensor, other: object },
+        ]);
+
+        sensors.remove(object);
+        sensors.update();
+        assert_eq!(sensors.drain_events(), vec![
+            SensorEvent::Exit { sensor, other: object },
+            SensorEvent::Exit { sensor: other_sensor, other: object },
+        ]);
+    }
+}
@@ -0,0 +1,165 @@
+use Vector2;
+use Rectangle;
+use collision::Collide;
+use collision::distance;
+use collision::overlap;
+
+
+/// When and where a moving object first touches another
+#[derive(Copy, Clone, Debug)]
+pub struct TimeOfImpact {
+    /// The fraction of the velocity the object can move before touching, between 0 and 1
+    pub time: f64,
+
+    /// The point where the objects touch
+    pub point: Vector2,
+
+    /// The normal of the other object's surface at the point of impact, pointing toward the moving object
+    pub normal: Vector2,
+}
+
+
+/// Moves an object by a velocity and returns when it first touches another object, if it does.
+/// Objects that already overlap touch at time 0.
+pub fn shape_cast(shape: &Collide, velocity: Vector2, other: &Collide) -> Option<TimeOfImpact> {
+    const MAX_ITERATIONS: usize = 64;
+    const TOLERANCE: f64 = 1e-9;
+
+    let speed = velocity.dot(velocity).sqrt();
+
+    // Advance by the distance between the objects, divided by how fast they approach each other.
+    // Since both objects are convex this never moves past the point of impact.
+    let mut time = 0.0;
+    let mut normal = if speed > 0.0 { -velocity / speed } else { Vector2::new(0.0, 0.0) };
+
+    for _ in 0..MAX_ITERATIONS {
+        let moved = Translated { shape, offset: velocity * time };
+
+        let separation = match distance(&moved, other) {
+            Some(separation) => separation,
+
+            None => {
+                // Only objects that overlap from the start end up here
+                if time == 0.0 {
+                    if let Some(translation) = overlap(shape, other) {
+                        let depth = translation.dot(translation).sqrt();
+                        if depth > 0.0 {
+                            normal = -translation / depth;
+                        }
+                    }
+                }
+
+                let point = moved.get_farthest_point(-normal);
+                return Some(TimeOfImpact { time, point, normal });
+            }
+        };
+
+        if separation.distance > 0.0 {
+            normal = -separation.normal;
+        }
+
+        if separation.distance <= TOLERANCE * (1.0 + speed) {
+            return Some(TimeOfImpact { time, point: separation.point_b, normal });
+        }
+
+        let approach = separation.normal.dot(velocity);
+        if approach <= 0.0 {
+            return None;
+        }
+
+        time += separation.distance / approach;
+        if time > 1.0 {
+            return None;
+        }
+    }
+
+    None
+}
+
+
+/// An object moved by an offset
+pub(crate) struct Translated<'a> {
+    pub shape: &'a Collide,
+    pub offset: Vector2,
+}
+
+impl<'a> Collide for Translated<'a> {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        self.shape.get_farthest_point(axis) + self.offset
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        let mut bounds = self.shape.get_bounding_box();
+        bounds.left += self.offset.x;
+        bounds.right += self.offset.x;
+        bounds.top += self.offset.y;
+        bounds.bottom += self.offset.y;
+        bounds
+    }
+}
+
+
+/// A single point
+pub(crate) struct Point(pub Vector2);
+
+impl Collide for Point {
+    fn get_farthest_point(&self, _axis: Vector2) -> Vector2 {
+        self.0
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle {
+            left: self.0.x,
+            right: self.0.x,
+            top: self.0.y,
+            bottom: self.0.y,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+
+
+    fn length(v: Vector2) -> f64 {
+        v.dot(v).sqrt()
+    }
+
+
+    #[test]
+    fn approaching_circles_touch_at_the_analytic_time() {
+        let moving = Circle::new(Vector2::new(0.0, 0.0), 1.0);
+        let velocity = Vector2::new(10.0, 0.0);
+
+        let head_on = Circle::new(Vector2::new(5.0, 0.0), 1.0);
+        let impact = shape_cast(&moving, velocity, &head_on).unwrap();
+        assert!((impact.time - 0.3).abs() < 1e-6);
+        assert!(length(impact.normal - Vector2::new(-1.0, 0.0)) < 1e-3);
+        assert!(length(impact.point - Vector2::new(4.0, 0.0)) < 1e-3);
+
+        // The centers end up two radii apart: (5 - x)^2 + 1 = 4
+        let glancing = Circle::new(Vector2::new(5.0, 1.0), 1.0);
+        let impact = shape_cast(&moving, velocity, &glancing).unwrap();
+        let x = 5.0 - 3.0f64.sqrt();
+        assert!((impact.time - x / 10.0).abs() < 1e-6, "{} instead of {}", impact.time, x / 10.0);
+        assert!(length(impact.normal - Vector2::new(-0.5 * 3.0f64.sqrt(), -0.5)) < 1e-3);
+    }
+
+
+    #[test]
+    fn misses_and_overlaps() {
+        let moving = Circle::new(Vector2::new(0.0, 0.0), 1.0);
+        let other = Circle::new(Vector2::new(5.0, 0.0), 1.0);
+
+        // Too short, moving away and passing by
+        assert!(shape_cast(&moving, Vector2::new(2.0, 0.0), &other).is_none());
+        assert!(shape_cast(&moving, Vector2::new(-10.0, 0.0), &other).is_none());
+        assert!(shape_cast(&moving, Vector2::new(10.0, 0.0), &Circle::new(Vector2::new(5.0, 2.5), 1.0)).is_none());
+
+        let touching = Circle::new(Vector2::new(1.5, 0.0), 1.0);
+        assert_eq!(shape_cast(&moving, Vector2::new(1.0, 0.0), &touching).unwrap().time, 0.0);
+    }
+}
@@ -0,0 +1,500 @@
+use Vector2;
+use shapes::Shape;
+use shapes::Rectangle;
+use shapes::ConvexHull;
+use collision::Collide;
+use collision::overlap;
+
+
+/// The contents of a single cell in a tile map
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tile {
+    /// Nothing to collide with
+    Empty,
+
+    /// A completely solid tile
+    Solid,
+
+    /// A solid tile with a sloped top, `left` and `right` are the heights
+    /// of the surface at the tile's edges as fractions of the tile size
+    Slope { left: f64, right: f64 },
+
+    /// A tile that can only be landed on from above
+    OneWay,
+}
+
+
+/// A contact between a shape and a tile
+#[derive(Copy, Clone, Debug)]
+pub struct TileContact {
+    /// The column of the tile
+    pub column: usize,
+
+    /// The row of the tile
+    pub row: usize,
+
+    /// The normal of the tile's surface, pointing toward the shape
+    pub normal: Vector2,
+
+    /// How far the shape was pushed out of the tile
+    pub depth: f64,
+}
+
+
+/// A grid of tiles, row 0 is at the bottom
+#[derive(Clone)]
+pub struct TileMap {
+    columns: usize,
+    rows: usize,
+    tile_size: f64,
+
+    // The position of the bottom left corner of the map
+    origin: Vector2,
+
+    tiles: Vec<Tile>,
+}
+
+
+impl TileMap {
+    pub fn new(columns: usize, rows: usize, tile_size: f64, origin: Vector2) -> TileMap {
+        assert!(tile_size > 0.0 && tile_size.is_finite(), "TileMap: invalid tile size {}", tile_size);
+
+        TileMap {
+            columns,
+            rows,
+            tile_size,
+            origin,
+            tiles: vec![Tile::Empty; columns * rows],
+        }
+    }
+
+
+    pub fn columns(&self) -> usize {
+        self.columns
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn tile_size(&self) -> f64 {
+        self.tile_size
+    }
+
+
+    /// Returns the tile at a position, tiles outside the map are empty
+    pub fn get(&self, column: usize, row: usize) -> Tile {
+        if column < self.columns && row < self.rows {
+            self.tiles[row * self.columns + column]
+        } else {
+            Tile::Empty
+        }
+    }
+
+
+    /// Changes the tile at a position, returns false if the position is outside the map
+    pub fn set(&mut self, column: usize, row: usize, tile: Tile) -> bool {
+        if column < self.columns && row < self.rows {
+            self.tiles[row * self.columns + column] = tile;
+            true
+        } else {
+            false
+        }
+    }
+
+
+    /// Returns the area covered by a tile
+    pub fn tile_bounds(&self, column: usize, row: usize) -> Rectangle {
+        let left = self.origin.x + column as f64 * self.tile_size;
+        let bottom = self.origin.y + row as f64 * self.tile_size;
+
+        Rectangle::new(left, left + self.tile_size, bottom + self.tile_size, bottom)
+    }
+
+
+    /// Returns the collision shape of a tile, if it has one
+    pub fn tile_shape(&self, column: usize, row: usize) -> Option<ConvexHull> {
+        let bounds = self.tile_bounds(column, row);
+
+        match self.get(column, row) {
+            Tile::Empty => None,
+
+            Tile::Solid | Tile::OneWay => Some(ConvexHull::new(vec![
+                Vector2::new(bounds.left, bounds.bottom),
+                Vector2::new(bounds.right, bounds.bottom),
+                Vector2::new(bounds.right, bounds.top),
+                Vector2::new(bounds.left, bounds.top),
+            ])),
+
+            Tile::Slope { left, right } => {
+                let mut points = vec![
+                    Vector2::new(bounds.left, bounds.bottom),
+                    Vector2::new(bounds.right, bounds.bottom),
+                ];
+
+                // Skip corners with zero height to keep the hull free of duplicate points
+                if right > 0.0 {
+                    points.push(Vector2::new(bounds.right, bounds.bottom + right * self.tile_size));
+                }
+                if left > 0.0 {
+                    points.push(Vector2::new(bounds.left, bounds.bottom + left * self.tile_size));
+                }
+
+                if points.len() < 3 {
+                    None
+                } else {
+                    Some(ConvexHull::new(points))
+                }
+            }
+        }
+    }
+
+
+    /// Returns the positions of all non-empty tiles touching an area
+    pub fn tiles_in(&self, area: &Rectangle) -> Vec<(usize, usize)> {
+        let mut tiles = Vec::new();
+
+        if self.columns == 0 || self.rows == 0 {
+            return tiles;
+        }
+
+        let to_cell = |value: f64, origin: f64, count: usize| -> usize {
+            let cell = ((value - origin) / self.tile_size).floor();
+            if cell < 0.0 {
+                0
+            } else if cell >= count as f64 {
+                count - 1
+            } else {
+                cell as usize
+            }
+        };
+
+        // Areas entirely outside the map touch no tiles
+        let right = self.origin.x + self.columns as f64 * self.tile_size;
+        let top = self.origin.y + self.rows as f64 * self.tile_size;
+        if area.right < self.origin.x || area.left > right || area.top < self.origin.y || area.bottom > top {
+            return tiles;
+        }
+
+        let min_column = to_cell(area.left, self.origin.x, self.columns);
+        let max_column = to_cell(area.right, self.origin.x, self.columns);
+        let min_row = to_cell(area.bottom, self.origin.y, self.rows);
+        let max_row = to_cell(area.top, self.origin.y, self.rows);
+
+        for row in min_row..max_row + 1 {
+            for column in min_column..max_column + 1 {
+                if self.get(column, row) != Tile::Empty {
+                    tiles.push((column, row));
+                }
+            }
+        }
+
+        tiles
+    }
+
+
+    /// Returns the contacts between a shape and all solid tiles it overlaps.
+    /// One-way tiles are ignored, since they only block shapes moving downward.
+    pub fn contacts(&self, shape: &Collide) -> Vec<TileContact> {
+        let mut contacts = Vec::new();
+
+        for (column, row) in self.tiles_in(&shape.get_bounding_box()) {
+            if self.get(column, row) == Tile::OneWay {
+                continue;
+            }
+
+            if let Some(contact) = self.solid_contact(shape, column, row) {
+                contacts.push(contact);
+            }
+        }
+
+        contacts
+    }
+
+
+    /// Pushes a shape out of all solid tiles it overlaps
+    pub fn resolve<S: Shape + ?Sized>(&self, shape: &mut S) -> Vec<TileContact> {
+        let mut contacts = Vec::new();
+        let mut motion = Vector2::new(0.0, 0.0);
+        let bottom = shape.get_farthest_point(Vector2::new(0.0, -1.0)).y;
+
+        self.resolve_step(shape, &mut motion, bottom, &mut contacts);
+
+        contacts
+    }
+
+
+    /// Moves a shape through the map, sliding along any tiles it hits.
+    ///
+    /// The movement is split into steps no longer than half the size of the shape or a tile,
+    /// so fast moving shapes can't pass through thin walls. Steps are at least a twentieth of a tile,
+    /// so very thin shapes don't take too many. Displacements that aren't finite only resolve the shape.
+    pub fn move_shape<S: Shape + ?Sized>(&self, shape: &mut S, displacement: Vector2) -> Vec<TileContact> {
+        let mut contacts = Vec::new();
+
+        let length = displacement.dot(displacement).sqrt();
+        if length == 0.0 || !length.is_finite() {
+            return self.resolve(shape);
+        }
+
+        let bounds = shape.get_bounding_box();
+        let extent = 0.5 * (bounds.right - bounds.left).min(bounds.top - bounds.bottom);
+        let max_step = extent.max(0.05 * self.tile_size).min(0.5 * self.tile_size);
+
+        let steps = (length / max_step).ceil().max(1.0) as usize;
+        let mut motion = displacement / steps as f64;
+
+        for _ in 0..steps {
+            let bottom = shape.get_farthest_point(Vector2::new(0.0, -1.0)).y;
+            shape.translate(motion);
+
+            self.resolve_step(shape, &mut motion, bottom, &mut contacts);
+        }
+
+        contacts
+    }
+
+
+    /// Pushes a shape out of the tiles it overlaps, removing the part of the motion that goes into them
+    fn resolve_step<S: Shape + ?Sized>(&self, shape: &mut S, motion: &mut Vector2,
+                                        previous_bottom: f64, contacts: &mut Vec<TileContact>) {
+        const MAX_PASSES: usize = 4;
+
+        for _ in 0..MAX_PASSES {
+            let deepest = self.deepest_contact(shape.as_collide(), *motion, previous_bottom);
+
+            match deepest {
+                Some(contact) => {
+                    shape.translate(contact.normal * contact.depth);
+
+                    let into = motion.dot(contact.normal);
+                    if into < 0.0 {
+                        *motion = *motion - contact.normal * into;
+                    }
+
+                    contacts.push(contact);
+                }
+
+                None => break
+            }
+        }
+    }
+
+
+    /// Returns the contact with the deepest penetration
+    fn deepest_contact(&self, shape: &Collide, motion: Vector2, previous_bottom: f64) -> Option<TileContact> {
+        let mut deepest: Option<TileContact> = None;
+
+        for (column, row) in self.tiles_in(&shape.get_bounding_box()) {
+            let contact = if self.get(column, row) == Tile::OneWay {
+                self.one_way_contact(shape, column, row, motion, previous_bottom)
+            } else {
+                self.solid_contact(shape, column, row)
+            };
+
+            if let Some(contact) = contact {
+                let deeper = match deepest {
+                    Some(ref current) => contact.depth > current.depth,
+                    None => true
+                };
+
+                if deeper {
+                    deepest = Some(contact);
+                }
+            }
+        }
+
+        deepest
+    }
+
+
+    /// Returns the contact between a shape and a solid tile
+    fn solid_contact(&self, shape: &Collide, column: usize, row: usize) -> Option<TileContact> {
+        const TOLERANCE: f64 = 1e-9;
+
+        let tile = self.tile_shape(column, row)?;
+        let translation = overlap(shape, &tile)?;
+        let depth = translation.dot(translation).sqrt();
+
+        if depth > TOLERANCE {
+            Some(TileContact {
+                column,
+                row,
+                normal: -translation / depth,
+                depth,
+            })
+        } else {
+            None
+        }
+    }
+
+
+    /// Returns the contact between a shape and a one-way tile, only blocking shapes that
+    /// were above the tile and are moving down
+    fn one_way_contact(&self, shape: &Collide, column: usize, row: usize,
+                       motion: Vector2, previous_bottom: f64) -> Option<TileContact> {
+        const TOLERANCE: f64 = 1e-6;
+
+        let top = self.tile_bounds(column, row).top;
+        if motion.y > 0.0 || previous_bottom < top - TOLERANCE {
+            return None;
+        }
+
+        let tile = self.tile_shape(column, row)?;
+        if overlap(shape, &tile).is_none() {
+            return None;
+        }
+
+        let bottom = shape.get_farthest_point(Vector2::new(0.0, -1.0)).y;
+        let depth = top - bottom;
+
+        if depth > 0.0 {
+            Some(TileContact {
+                column,
+                row,
+                normal: Vector2::new(0.0, 1.0),
+                depth,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f64 = 1e-6;
+
+
+    #[test]
+    fn set_outside_the_map() {
+        let mut map = TileMap::new(2, 2, 1.0, Vector2::new(0.0, 0.0));
+
+        assert!(map.set(1, 1, Tile::Solid));
+        assert!(!map.set(2, 0, Tile::Solid));
+        assert!(!map.set(0, 2, Tile::Solid));
+
+        assert_eq!(map.get(1, 1), Tile::Solid);
+        assert_eq!(map.get(2, 0), Tile::Empty);
+    }
+
+
+    #[test]
+    fn fast_shapes_stop_at_thin_walls() {
+        let mut map = TileMap::new(10, 3, 1.0, Vector2::new(0.0, 0.0));
+        map.set(3, 0, Tile::Solid);
+
+        // Moving a whole wall's width in one frame, without sub-steps it would tunnel through
+        let mut shape = Rectangle::new(0.25, 0.75, 0.75, 0.25);
+        let contacts = map.move_shape(&mut shape, Vector2::new(10.0, 0.0));
+
+        assert!(!contacts.is_empty());
+        assert!(shape.right <= 3.0 + EPSILON, "passed through the wall: {}", shape.right);
+        assert!(shape.right >= 3.0 - EPSILON, "stopped early: {}", shape.right);
+        assert!((shape.bottom - 0.25).abs() < EPSILON);
+    }
+
+
+    #[test]
+    fn sliding_keeps_the_motion_along_the_wall() {
+        let mut map = TileMap::new(10, 10, 1.0, Vector2::new(0.0, 0.0));
+        for row in 0..10 {
+            map.set(3, row, Tile::Solid);
+        }
+
+        let mut shape = Rectangle::new(2.0, 2.5, 1.5, 1.0);
+        map.move_shape(&mut shape, Vector2::new(2.0, 2.0));
+
+        assert!(shape.right <= 3.0 + EPSILON);
+        assert!((shape.bottom - 3.0).abs() < EPSILON, "lost vertical motion: {}", shape.bottom);
+    }
+
+
+    #[test]
+    fn one_way_tiles_catch_falling_shapes() {
+        let mut map = TileMap::new(4, 4, 1.0, Vector2::new(0.0, 0.0));
+        map.set(0, 1, Tile::OneWay);
+
+        let mut shape = Rectangle::new(0.25, 0.75, 3.0, 2.5);
+        let contacts = map.move_shape(&mut shape, Vector2::new(0.0, -2.0));
+
+        assert!(!contacts.is_empty());
+        assert!(contacts.iter().all(|contact| (contact.normal.y - 1.0).abs() < EPSILON));
+        assert!((shape.bottom - 2.0).abs() < EPSILON, "not on top of the tile: {}", shape.bottom);
+    }
+
+
+    #[test]
+    fn one_way_tiles_let_shapes_through_from_below() {
+        let mut map = TileMap::new(4, 4, 1.0, Vector2::new(0.0, 0.0));
+        map.set(0, 1, Tile::OneWay);
+
+        let mut shape = Rectangle::new(0.25, 0.75, 0.7, 0.2);
+        let contacts = map.move_shape(&mut shape, Vector2::new(0.0, 1.5));
+
+        assert!(contacts.is_empty());
+        assert!((shape.bottom - 1.7).abs() < EPSILON);
+    }
+
+
+    #[test]
+    fn one_way_tiles_are_not_solid_from_the_side() {
+        let mut map = TileMap::new(4, 4, 1.0, Vector2::new(0.0, 0.0));
+        map.set(1, 1, Tile::OneWay);
+
+        let mut shape = Rectangle::new(0.2, 0.6, 1.8, 1.2);
+        let contacts = map.move_shape(&mut shape, Vector2::new(2.0, 0.0));
+
+        assert!(contacts.is_empty());
+        assert!((shape.left - 2.2).abs() < EPSILON);
+        assert!(map.contacts(&shape).is_empty());
+    }
+
+
+    #[test]
+    fn shapes_walk_up_slopes() {
+        let mut map = TileMap::new(10, 3, 1.0, Vector2::new(0.0, 0.0));
+        for column in 0..10 {
+            map.set(column, 0, Tile::Solid);
+        }
+        map.set(3, 1, Tile::Slope { left: 0.0, right: 1.0 });
+
+        let mut shape = Rectangle::new(1.0, 1.5, 1.5, 1.0);
+        let contacts = map.move_shape(&mut shape, Vector2::new(2.5, 0.0));
+
+        assert!(shape.right > 3.0 + EPSILON, "didn't climb the slope: {}", shape.right);
+        assert!(contacts.iter().any(|contact| contact.normal.x < 0.0 && contact.normal.y > 0.0));
+
+        // The bottom right corner rests on the surface, which rises one tile over the tile's width
+        let surface = 1.0 + (shape.right - 3.0);
+        assert!((shape.bottom - surface).abs() < EPSILON, "{} instead of {}", shape.bottom, surface);
+        assert!(map.contacts(&shape).is_empty());
+    }
+
+
+    #[test]
+    fn degenerate_motion_finishes() {
+        let mut map = TileMap::new(4, 4, 1.0, Vector2::new(0.0, 0.0));
+        map.set(3, 0, Tile::Solid);
+
+        let mut shape = Rectangle::new(0.0, 0.5, 0.5, 0.5);
+        map.move_shape(&mut shape, Vector2::new(::std::f64::NAN, 0.0));
+        map.move_shape(&mut shape, Vector2::new(::std::f64::INFINITY, 0.0));
+        assert_eq!(shape.left, 0.0);
+
+        // A shape with no height moving far, which would take millions of steps of its own size
+        let mut thin = Rectangle::new(0.0, 0.5, 0.500001, 0.5);
+        map.move_shape(&mut thin, Vector2::new(100.0, 0.0));
+        assert!(thin.right <= 3.0 + EPSILON);
+    }
+
+
+    #[test]
+    #[should_panic]
+    fn zero_tile_size_is_rejected() {
+        TileMap::new(4, 4, 0.0, Vector2::new(0.0, 0.0));
+    }
+}
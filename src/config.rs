@@ -0,0 +1,143 @@
+/// Settings for running an app
+#[derive(Clone, Debug)]
+pub struct AppConfig {
+    pub title: String,
+
+    /// The size of the window's drawable area
+    pub width: u32,
+    pub height: u32,
+
+    /// Cover the primary monitor
+    pub fullscreen: bool,
+
+    /// Show the window without a title bar and borders
+    pub borderless: bool,
+
+    /// Let the user resize the window
+    pub resizable: bool,
+
+    pub min_size: Option<(u32, u32)>,
+    pub max_size: Option<(u32, u32)>,
+
+    /// The number of samples per pixel used for anti-aliasing
+    pub samples: u16,
+
+    /// The icon of the window as RGBA pixels, along with its width and height
+    pub icon: Option<(Vec<u8>, u32, u32)>,
+
+    pub cursor_visible: bool,
+
+    /// Wait for the display to refresh before showing each frame
+    pub vsync: bool,
+
+    /// The most frames per second to render, or None to render as fast as possible
+    pub max_fps: Option<f64>,
+
+    /// The most frames per second to render while the window is not focused, to save power
+    pub unfocused_fps: Option<f64>,
+}
+
+
+impl AppConfig {
+    /// A resizable 800x600 window with 8x anti-aliasing, rendering as fast as possible without vsync
+    pub fn new() -> AppConfig {
+        AppConfig {
+            title: String::from("Aurora"),
+
+            width: 800,
+            height: 600,
+
+            fullscreen: false,
+            borderless: false,
+            resizable: true,
+
+            min_size: None,
+            max_size: None,
+
+            samples: 8,
+
+            icon: None,
+
+            cursor_visible: true,
+
+            vsync: false,
+            max_fps: None,
+            unfocused_fps: None,
+        }
+    }
+
+
+    pub fn with_title(self, title: &str) -> AppConfig {
+        AppConfig { title: String::from(title), ..self }
+    }
+
+    pub fn with_size(self, width: u32, height: u32) -> AppConfig {
+        AppConfig { width, height, ..self }
+    }
+
+    pub fn with_fullscreen(self, fullscreen: bool) -> AppConfig {
+        AppConfig { fullscreen, ..self }
+    }
+
+    pub fn with_borderless(self, borderless: bool) -> AppConfig {
+        AppConfig { borderless, ..self }
+    }
+
+    pub fn with_resizable(self, resizable: bool) -> AppConfig {
+        AppConfig { resizable, ..self }
+    }
+
+    pub fn with_min_size(self, width: u32, height: u32) -> AppConfig {
+        AppConfig { min_size: Some((width, height)), ..self }
+    }
+
+    pub fn with_max_size(self, width: u32, height: u32) -> AppConfig {
+        AppConfig { max_size: Some((width, height)), ..self }
+    }
+
+    /// Sets the number of samples per pixel, 0 turns anti-aliasing off
+    pub fn with_samples(self, samples: u16) -> AppConfig {
+        AppConfig { samples, ..self }
+    }
+
+    /// Sets the icon from RGBA pixels, row by row
+    pub fn with_icon(self, rgba: Vec<u8>, width: u32, height: u32) -> AppConfig {
+        AppConfig { icon: Some((rgba, width, height)), ..self }
+    }
+
+    pub fn with_cursor_visible(self, cursor_visible: bool) -> AppConfig {
+        AppConfig { cursor_visible, ..self }
+    }
+
+
+    pub fn with_vsync(self, vsync: bool) -> AppConfig {
+        AppConfig { vsync, ..self }
+    }
+
+    /// Limits the frame rate, sleeping for the rest of each frame
+    pub fn with_max_fps(self, max_fps: f64) -> AppConfig {
+        AppConfig { max_fps: Some(max_fps), ..self }
+    }
+
+    /// Limits the frame rate further while the window is not focused
+    pub fn with_unfocused_fps(self, unfocused_fps: f64) -> AppConfig {
+        AppConfig { unfocused_fps: Some(unfocused_fps), ..self }
+    }
+
+
+    /// Returns the frame rate limit for a focused or unfocused window
+    pub(crate) fn fps_limit(&self, focused: bool) -> Option<f64> {
+        match (focused, self.max_fps, self.unfocused_fps) {
+            (false, Some(max), Some(unfocused)) => Some(max.min(unfocused)),
+            (false, None, Some(unfocused)) => Some(unfocused),
+            _ => self.max_fps
+        }
+    }
+}
+
+
+impl Default for AppConfig {
+    fn default() -> AppConfig {
+        AppConfig::new()
+    }
+}
@@ -1,15 +1,163 @@
 use glium::Display;
+use glium::glutin::MonitorId;
+use glium::glutin::CursorState;
+use input::Input;
+use input::GamepadBackend;
+use timestep::FixedTimestep;
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::cell::RefCell;
+use std::cell::Ref;
+use std::cell::RefMut;
+
+
+struct WindowState {
+    // The monitor to cover in fullscreen
+    monitor: Option<MonitorId>,
+    fullscreen: bool,
+}
+
 
 #[derive(Clone)]
 pub struct Context {
-    pub(crate) display: Display
+    pub(crate) display: Display,
+    window: Rc<RefCell<WindowState>>,
+    input: Rc<RefCell<Input>>,
+    // The clock calling App::fixed_update
+    clock: Rc<Cell<Option<FixedTimestep>>>,
 }
 
 
 impl Context {
     pub fn new(display: Display) -> Context {
+        let input = Input::new(display.get_framebuffer_dimensions());
+
         Context {
-            display
+            display,
+            window: Rc::new(RefCell::new(WindowState {
+                monitor: None,
+                fullscreen: false,
+            })),
+            input: Rc::new(RefCell::new(input)),
+            clock: Rc::new(Cell::new(None)),
+        }
+    }
+
+
+    /// Creates a context for a window that can be made fullscreen on a monitor
+    pub(crate) fn with_monitor(display: Display, monitor: MonitorId, fullscreen: bool) -> Context {
+        let context = Context::new(display);
+        {
+            let mut window = context.window.borrow_mut();
+            window.monitor = Some(monitor);
+            window.fullscreen = fullscreen;
+        }
+        context
+    }
+
+
+    pub fn set_title(&self, title: &str) {
+        self.display.gl_window().set_title(title);
+    }
+
+
+    /// Changes the size of the window's drawable area
+    pub fn set_size(&self, width: u32, height: u32) {
+        self.display.gl_window().set_inner_size(width, height);
+    }
+
+    /// Returns the size of the window's drawable area
+    pub fn size(&self) -> (u32, u32) {
+        self.display.gl_window().get_inner_size().unwrap_or((0, 0))
+    }
+
+
+    /// Makes the window cover the primary monitor, or returns it to its normal size.
+    /// Does nothing for contexts not created by `run_app`.
+    pub fn set_fullscreen(&self, fullscreen: bool) {
+        let mut window = self.window.borrow_mut();
+
+        if let Some(ref monitor) = window.monitor {
+            self.display.gl_window().set_fullscreen(if fullscreen { Some(monitor.clone()) } else { None });
+        } else {
+            return;
+        }
+
+        window.fullscreen = fullscreen;
+    }
+
+    pub fn is_fullscreen(&self) -> bool {
+        self.window.borrow().fullscreen
+    }
+
+    pub fn toggle_fullscreen(&self) {
+        let fullscreen = self.is_fullscreen();
+        self.set_fullscreen(!fullscreen);
+    }
+
+
+    pub fn set_cursor_visible(&self, visible: bool) {
+        let state = if visible { CursorState::Normal } else { CursorState::Hide };
+        let _ = self.display.gl_window().set_cursor_state(state);
+    }
+
+
+    /// Changes where controllers are read from, such as a `VirtualBackend` for testing without hardware
+    pub fn set_gamepad_backend<B: GamepadBackend + 'static>(&self, backend: B) {
+        self.input.borrow_mut().gamepads_mut().set_backend(Box::new(backend));
+    }
+
+    /// Adds controller mappings in the format of the SDL game controller database,
+    /// returns the number of mappings added
+    pub fn add_gamepad_mappings(&self, text: &str) -> usize {
+        self.input.borrow_mut().gamepads_mut().add_mappings(text)
+    }
+
+    /// Sets the stick and trigger dead zones as fractions of their full range
+    pub fn set_gamepad_dead_zones(&self, stick: f64, trigger: f64) {
+        self.input.borrow_mut().gamepads_mut().set_dead_zones(stick, trigger);
+    }
+
+
+    /// Returns the state of the keyboard, mouse and controllers
+    pub fn input(&self) -> Ref<Input> {
+        self.input.borrow()
+    }
+
+    pub(crate) fn input_mut(&self) -> RefMut<Input> {
+        self.input.borrow_mut()
+    }
+
+
+    /// Replaces the clock calling `App::fixed_update`, None only calls `App::update`
+    pub fn set_fixed_timestep(&self, clock: Option<FixedTimestep>) {
+        self.clock.set(clock);
+    }
+
+    /// Returns the clock calling `App::fixed_update`
+    pub fn fixed_timestep(&self) -> Option<FixedTimestep> {
+        self.clock.get()
+    }
+
+    /// Changes the length of a fixed step in seconds, keeping the time already accumulated.
+    /// Does nothing without a fixed timestep
+    pub fn set_timestep(&self, timestep: f64) {
+        if let Some(mut clock) = self.clock.get() {
+            clock.set_timestep(timestep);
+            self.clock.set(Some(clock));
+        }
+    }
+
+    // Advances the clock, returning the number of fixed steps to run
+    pub(crate) fn advance_clock(&self, delta_time: f64) -> u32 {
+        match self.clock.get() {
+            Some(mut clock) => {
+                let steps = clock.advance(delta_time);
+                self.clock.set(Some(clock));
+                steps
+            }
+            None => 0,
         }
     }
 }
@@ -0,0 +1,56 @@
+use std::thread;
+use std::time::Duration;
+use std::time::Instant;
+
+
+/// Waits between frames to keep a maximum frame rate
+pub(crate) struct FrameLimiter {
+    next_frame: Instant,
+}
+
+
+impl FrameLimiter {
+    pub fn new() -> FrameLimiter {
+        FrameLimiter {
+            next_frame: Instant::now(),
+        }
+    }
+
+
+    /// Waits until the next frame is due, or returns immediately without a limit
+    pub fn wait(&mut self, fps: Option<f64>) {
+        // Sleeping is only accurate to about a millisecond, the rest is spent yielding
+        const SPIN: u32 = 2_000_000;
+
+        let now = Instant::now();
+
+        let fps = match fps {
+            Some(fps) if fps > 0.0 => fps,
+            _ => {
+                self.next_frame = now;
+                return;
+            }
+        };
+
+        let seconds = 1.0 / fps;
+        let frame_time = Duration::new(seconds.trunc() as u64, (seconds.fract() * 1e9) as u32);
+
+        if self.next_frame > now {
+            let remaining = self.next_frame - now;
+            let spin = Duration::new(0, SPIN);
+
+            if remaining > spin {
+                thread::sleep(remaining - spin);
+            }
+
+            while Instant::now() < self.next_frame {
+                thread::yield_now();
+            }
+
+            self.next_frame += frame_time;
+        } else {
+            // Don't try to catch up after a slow frame
+            self.next_frame = now + frame_time;
+        }
+    }
+}
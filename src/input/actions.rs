@@ -0,0 +1,864 @@
+use Vector2;
+use app::KeyCode;
+use app::MouseButton;
+use input::Input;
+use input::gamepad::Gamepad;
+use input::gamepad::GamepadId;
+use input::gamepad::GamepadButton;
+use input::gamepad::GamepadAxis;
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+
+
+/// How far a control has to be pushed to count as pressed
+const PRESS_THRESHOLD: f64 = 0.5;
+
+
+/// A single key, button or half of a controller axis, with a value from 0 to 1
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Control {
+    Key(KeyCode),
+    Mouse(MouseButton),
+    Button(GamepadButton),
+    /// The positive half of a controller axis
+    AxisPositive(GamepadAxis),
+    /// The negative half of a controller axis
+    AxisNegative(GamepadAxis),
+}
+
+
+/// Why bindings couldn't be loaded from a file
+#[derive(Debug)]
+pub enum ActionMapError {
+    Io(io::Error),
+    /// A line that isn't a valid binding, counting from 1
+    Parse { line: usize },
+}
+
+
+/// An input bound to an action
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Binding {
+    Control(Control),
+    /// Two controls combined into a value from -1 to 1
+    Axis { negative: Control, positive: Control },
+    /// Four controls combined into a vector no longer than 1, with y pointing up
+    Vector { left: Control, right: Control, down: Control, up: Control },
+}
+
+
+impl Control {
+    /// Returns how far the control is pushed, from 0 to 1
+    pub fn value(&self, input: &Input, gamepad: Option<GamepadId>) -> f64 {
+        match *self {
+            Control::Key(key) => if input.is_key_down(key) { 1.0 } else { 0.0 },
+            Control::Mouse(button) => if input.is_mouse_down(button) { 1.0 } else { 0.0 },
+
+            Control::Button(button) => {
+                let down = gamepads(input, gamepad).iter().any(|pad| pad.is_button_down(button));
+                if down { 1.0 } else { 0.0 }
+            },
+
+            Control::AxisPositive(axis) => {
+                gamepads(input, gamepad).iter().fold(0.0, |max, pad| pad.axis(axis).max(max))
+            },
+            Control::AxisNegative(axis) => {
+                gamepads(input, gamepad).iter().fold(0.0, |max, pad| (-pad.axis(axis)).max(max))
+            },
+        }
+    }
+
+
+    /// Returns true if the control went down since the last frame
+    pub fn was_pressed_this_frame(&self, input: &Input, gamepad: Option<GamepadId>) -> bool {
+        match *self {
+            Control::Key(key) => input.was_key_pressed_this_frame(key),
+            Control::Mouse(button) => input.was_mouse_pressed_this_frame(button),
+            Control::Button(button) => {
+                gamepads(input, gamepad).iter().any(|pad| pad.was_button_pressed_this_frame(button))
+            },
+            Control::AxisPositive(_) | Control::AxisNegative(_) => false,
+        }
+    }
+
+
+    /// Returns the first control pressed this frame, for letting the player choose a binding.
+    /// Keys that can't be saved are ignored.
+    pub fn pressed_this_frame(input: &Input, gamepad: Option<GamepadId>) -> Option<Control> {
+        if let Some(key) = input.keys_pressed_this_frame().into_iter().find(|key| KEYS.contains(key)) {
+            return Some(Control::Key(key));
+        }
+
+        if let Some(button) = input.mouse_pressed_this_frame().into_iter().next() {
+            return Some(Control::Mouse(button));
+        }
+
+        for pad in gamepads(input, gamepad) {
+            for button in GamepadButton::ALL.iter() {
+                if pad.was_button_pressed_this_frame(*button) {
+                    return Some(Control::Button(*button));
+                }
+            }
+
+            for axis in GamepadAxis::ALL.iter() {
+                let value = pad.axis(*axis);
+                if value > PRESS_THRESHOLD {
+                    return Some(Control::AxisPositive(*axis));
+                } else if value < -PRESS_THRESHOLD {
+                    return Some(Control::AxisNegative(*axis));
+                }
+            }
+        }
+
+        None
+    }
+}
+
+
+impl Binding {
+    /// The W, A, S and D keys as a vector
+    pub fn wasd() -> Binding {
+        Binding::Vector {
+            left: Control::Key(KeyCode::A),
+            right: Control::Key(KeyCode::D),
+            down: Control::Key(KeyCode::S),
+            up: Control::Key(KeyCode::W),
+        }
+    }
+
+    /// The arrow keys as a vector
+    pub fn arrows() -> Binding {
+        Binding::Vector {
+            left: Control::Key(KeyCode::Left),
+            right: Control::Key(KeyCode::Right),
+            down: Control::Key(KeyCode::Down),
+            up: Control::Key(KeyCode::Up),
+        }
+    }
+
+    /// A whole controller axis, from -1 to 1
+    pub fn gamepad_axis(axis: GamepadAxis) -> Binding {
+        Binding::Axis {
+            negative: Control::AxisNegative(axis),
+            positive: Control::AxisPositive(axis),
+        }
+    }
+
+    /// The left controller stick as a vector
+    pub fn left_stick() -> Binding {
+        Binding::Vector {
+            left: Control::AxisNegative(GamepadAxis::LeftX),
+            right: Control::AxisPositive(GamepadAxis::LeftX),
+            down: Control::AxisPositive(GamepadAxis::LeftY),
+            up: Control::AxisNegative(GamepadAxis::LeftY),
+        }
+    }
+
+    /// The right controller stick as a vector
+    pub fn right_stick() -> Binding {
+        Binding::Vector {
+            left: Control::AxisNegative(GamepadAxis::RightX),
+            right: Control::AxisPositive(GamepadAxis::RightX),
+            down: Control::AxisPositive(GamepadAxis::RightY),
+            up: Control::AxisNegative(GamepadAxis::RightY),
+        }
+    }
+
+
+    /// Returns the value of the binding as a vector, scalar bindings only have an x component
+    pub fn vector(&self, input: &Input, gamepad: Option<GamepadId>) -> Vector2 {
+        match *self {
+            Binding::Control(control) => Vector2::new(control.value(input, gamepad), 0.0),
+
+            Binding::Axis { negative, positive } => {
+                Vector2::new(positive.value(input, gamepad) - negative.value(input, gamepad), 0.0)
+            },
+
+            Binding::Vector { left, right, down, up } => {
+                let vector = Vector2::new(
+                    right.value(input, gamepad) - left.value(input, gamepad),
+                    up.value(input, gamepad) - down.value(input, gamepad)
+                );
+
+                clamp_length(vector)
+            },
+        }
+    }
+
+
+    /// Returns the controls the binding is made of
+    pub fn controls(&self) -> Vec<Control> {
+        match *self {
+            Binding::Control(control) => vec![control],
+            Binding::Axis { negative, positive } => vec![negative, positive],
+            Binding::Vector { left, right, down, up } => vec![left, right, down, up],
+        }
+    }
+}
+
+
+impl From<Control> for Binding {
+    fn from(control: Control) -> Binding {
+        Binding::Control(control)
+    }
+}
+
+impl From<KeyCode> for Binding {
+    fn from(key: KeyCode) -> Binding {
+        Binding::Control(Control::Key(key))
+    }
+}
+
+impl From<MouseButton> for Binding {
+    fn from(button: MouseButton) -> Binding {
+        Binding::Control(Control::Mouse(button))
+    }
+}
+
+impl From<GamepadButton> for Binding {
+    fn from(button: GamepadButton) -> Binding {
+        Binding::Control(Control::Button(button))
+    }
+}
+
+
+#[derive(Clone, Debug)]
+struct Action {
+    bindings: Vec<Binding>,
+
+    down: bool,
+    pressed: bool,
+    released: bool,
+    value: Vector2,
+}
+
+
+impl Action {
+    fn new() -> Action {
+        Action {
+            bindings: Vec::new(),
+
+            down: false,
+            pressed: false,
+            released: false,
+            value: Vector2::new(0.0, 0.0),
+        }
+    }
+}
+
+
+/// Named actions, such as "jump" or "move", bound to keys, mouse buttons and controllers.
+/// Call `update` once per frame before reading the actions.
+#[derive(Clone, Debug)]
+pub struct ActionMap {
+    actions: BTreeMap<String, Action>,
+
+    // Only read this controller, or all of them if None
+    gamepad: Option<GamepadId>,
+}
+
+
+impl ActionMap {
+    pub fn new() -> ActionMap {
+        ActionMap {
+            actions: BTreeMap::new(),
+            gamepad: None,
+        }
+    }
+
+
+    /// Only reads the given controller, for when several players share a computer
+    pub fn with_gamepad(self, gamepad: GamepadId) -> ActionMap {
+        ActionMap { gamepad: Some(gamepad), ..self }
+    }
+
+    pub fn set_gamepad(&mut self, gamepad: Option<GamepadId>) {
+        self.gamepad = gamepad;
+    }
+
+    pub fn gamepad(&self) -> Option<GamepadId> {
+        self.gamepad
+    }
+
+
+    /// Adds a binding to an action, creating the action if needed
+    pub fn bind<B: Into<Binding>>(&mut self, action: &str, binding: B) {
+        let binding = binding.into();
+        let bindings = &mut self.action_mut(action).bindings;
+
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+    }
+
+    /// Removes a binding from an action
+    pub fn unbind(&mut self, action: &str, binding: &Binding) {
+        if let Some(action) = self.actions.get_mut(action) {
+            action.bindings.retain(|other| other != binding);
+        }
+    }
+
+    /// Replaces the bindings of an action
+    pub fn rebind(&mut self, action: &str, bindings: Vec<Binding>) {
+        self.action_mut(action).bindings = bindings;
+    }
+
+    /// Replaces a binding of an action at an index, as listed by `bindings`.
+    /// Returns false if there is no such binding.
+    pub fn rebind_at<B: Into<Binding>>(&mut self, action: &str, index: usize, binding: B) -> bool {
+        match self.actions.get_mut(action).and_then(|action| action.bindings.get_mut(index)) {
+            Some(slot) => {
+                *slot = binding.into();
+                true
+            },
+            None => false
+        }
+    }
+
+    /// Removes every binding of an action, but keeps the action
+    pub fn clear(&mut self, action: &str) {
+        self.action_mut(action).bindings.clear();
+    }
+
+    /// Removes an action and its bindings
+    pub fn remove(&mut self, action: &str) {
+        self.actions.remove(action);
+    }
+
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        match self.actions.get(action) {
+            Some(action) => &action.bindings[..],
+            None => &[]
+        }
+    }
+
+    /// Returns the names of every action, in alphabetical order
+    pub fn actions(&self) -> Vec<&str> {
+        self.actions.keys().map(|name| name.as_str()).collect()
+    }
+
+
+    /// Reads the bindings of every action from the current input
+    pub fn update(&mut self, input: &Input) {
+        let gamepad = self.gamepad;
+
+        for action in self.actions.values_mut() {
+            let mut value = Vector2::new(0.0, 0.0);
+            let mut down = false;
+            let mut tapped = false;
+
+            for binding in action.bindings.iter() {
+                let vector = binding.vector(input, gamepad);
+                value.x += vector.x;
+                value.y += vector.y;
+
+                down = down || vector.dot(vector).sqrt() > PRESS_THRESHOLD;
+
+                // Catch presses that were released again within the frame
+                tapped = tapped || binding.controls().iter().any(|control| control.was_pressed_this_frame(input, gamepad));
+            }
+
+            action.pressed = !action.down && (down || tapped);
+            action.released = action.down && !down;
+            action.down = down;
+            action.value = clamp_length(value);
+        }
+    }
+
+
+    /// Returns true if any binding of an action is pushed far enough to count as pressed
+    pub fn is_down(&self, action: &str) -> bool {
+        self.actions.get(action).map_or(false, |action| action.down)
+    }
+
+    /// Returns true if an action went down since the last frame
+    pub fn was_pressed_this_frame(&self, action: &str) -> bool {
+        self.actions.get(action).map_or(false, |action| action.pressed)
+    }
+
+    /// Returns true if an action was let go since the last frame
+    pub fn was_released(&self, action: &str) -> bool {
+        self.actions.get(action).map_or(false, |action| action.released)
+    }
+
+    /// Returns the value of an action from -1 to 1, the x component of vector bindings
+    pub fn value(&self, action: &str) -> f64 {
+        self.vector(action).x
+    }
+
+    /// Returns the combined value of an action's bindings, no longer than 1
+    pub fn vector(&self, action: &str) -> Vector2 {
+        self.actions.get(action).map_or(Vector2::new(0.0, 0.0), |action| action.value)
+    }
+
+
+    /// Reads bindings in the format written by `Display`, ignoring empty lines and lines starting with '#'.
+    /// Returns the number of the first invalid line on failure, counting from 1.
+    pub fn parse(text: &str) -> Result<ActionMap, usize> {
+        let mut map = ActionMap::new();
+
+        for (index, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.splitn(2, '=');
+            let name = parts.next().map(str::trim).unwrap_or("");
+            let binding = parts.next().map(str::trim).ok_or(index + 1)?;
+
+            if name.is_empty() {
+                return Err(index + 1);
+            }
+
+            if binding.is_empty() {
+                map.action_mut(name);
+            } else {
+                let binding = parse_binding(binding).ok_or(index + 1)?;
+                map.bind(name, binding);
+            }
+        }
+
+        Ok(map)
+    }
+
+
+    /// Writes the bindings to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.to_string().as_bytes())
+    }
+
+    /// Reads bindings from a file written by `save`
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<ActionMap, ActionMapError> {
+        let mut text = String::new();
+        File::open(path).and_then(|mut file| file.read_to_string(&mut text))?;
+        ActionMap::parse(&text).map_err(|line| ActionMapError::Parse { line })
+    }
+
+
+    /// Replaces the bindings of the actions in another map, keeping actions it doesn't mention.
+    /// Useful for applying a player's saved bindings on top of the defaults.
+    pub fn merge(&mut self, other: &ActionMap) {
+        for (name, action) in other.actions.iter() {
+            self.rebind(name, action.bindings.clone());
+        }
+    }
+
+
+    fn action_mut(&mut self, name: &str) -> &mut Action {
+        self.actions.entry(name.to_string()).or_insert_with(Action::new)
+    }
+}
+
+
+/// Writes the bindings one per line, in the format read by `parse`.
+/// Bindings using keys that can't be read back are left out, actions left without bindings are still written:
+///
+/// ```text
+/// jump = key Space
+/// jump = button a
+/// move = vector key A, key D, key S, key W
+/// steer = axis axis- leftx, axis+ leftx
+/// ```
+impl fmt::Display for ActionMap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, action) in self.actions.iter() {
+            let bindings: Vec<&Binding> = action.bindings.iter().filter(|binding| can_save(binding)).collect();
+
+            if bindings.is_empty() {
+                writeln!(f, "{} =", name)?;
+            }
+
+            for binding in bindings {
+                writeln!(f, "{} = {}", name, format_binding(binding))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+
+impl Default for ActionMap {
+    fn default() -> ActionMap {
+        ActionMap::new()
+    }
+}
+
+
+impl From<io::Error> for ActionMapError {
+    fn from(error: io::Error) -> ActionMapError {
+        ActionMapError::Io(error)
+    }
+}
+
+
+impl fmt::Display for ActionMapError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ActionMapError::Io(ref error) => write!(f, "could not read bindings: {}", error),
+            ActionMapError::Parse { line } => write!(f, "invalid binding on line {}", line),
+        }
+    }
+}
+
+
+impl Error for ActionMapError {
+    fn description(&self) -> &str {
+        match *self {
+            ActionMapError::Io(_) => "could not read bindings",
+            ActionMapError::Parse { .. } => "invalid binding",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            ActionMapError::Io(ref error) => Some(error),
+            ActionMapError::Parse { .. } => None,
+        }
+    }
+}
+
+
+// Returns the controllers to read, all of them unless one is chosen
+fn gamepads(input: &Input, gamepad: Option<GamepadId>) -> Vec<&Gamepad> {
+    match gamepad {
+        Some(id) => input.gamepad(id).into_iter().collect(),
+        None => input.gamepads().gamepads(),
+    }
+}
+
+
+fn clamp_length(vector: Vector2) -> Vector2 {
+    let length = vector.dot(vector).sqrt();
+    if length > 1.0 {
+        Vector2::new(vector.x / length, vector.y / length)
+    } else {
+        vector
+    }
+}
+
+
+fn format_binding(binding: &Binding) -> String {
+    match *binding {
+        Binding::Control(control) => format_control(&control),
+        Binding::Axis { negative, positive } => {
+            format!("axis {}, {}", format_control(&negative), format_control(&positive))
+        },
+        Binding::Vector { left, right, down, up } => {
+            format!("vector {}, {}, {}, {}",
+                    format_control(&left), format_control(&right), format_control(&down), format_control(&up))
+        },
+    }
+}
+
+
+// Returns false for bindings using keys `key_from_name` doesn't know
+fn can_save(binding: &Binding) -> bool {
+    binding.controls().iter().all(|control| match *control {
+        Control::Key(key) => KEYS.contains(&key),
+        _ => true
+    })
+}
+
+
+fn parse_binding(text: &str) -> Option<Binding> {
+    if text.starts_with("axis ") {
+        let controls = parse_controls(&text[5..])?;
+        if controls.len() == 2 {
+            return Some(Binding::Axis { negative: controls[0], positive: controls[1] });
+        }
+        None
+    } else if text.starts_with("vector ") {
+        let controls = parse_controls(&text[7..])?;
+        if controls.len() == 4 {
+            return Some(Binding::Vector { left: controls[0], right: controls[1], down: controls[2], up: controls[3] });
+        }
+        None
+    } else {
+        parse_control(text).map(Binding::Control)
+    }
+}
+
+
+fn parse_controls(text: &str) -> Option<Vec<Control>> {
+    text.split(',').map(|control| parse_control(control.trim())).collect()
+}
+
+
+fn format_control(control: &Control) -> String {
+    match *control {
+        Control::Key(key) => format!("key {:?}", key),
+        Control::Mouse(MouseButton::Other(button)) => format!("mouse {}", button),
+        Control::Mouse(button) => format!("mouse {:?}", button),
+        Control::Button(button) => format!("button {}", button.name()),
+        Control::AxisPositive(axis) => format!("axis+ {}", axis.name()),
+        Control::AxisNegative(axis) => format!("axis- {}", axis.name()),
+    }
+}
+
+
+fn parse_control(text: &str) -> Option<Control> {
+    let mut parts = text.split_whitespace();
+    let kind = parts.next()?;
+    let name = parts.next()?;
+
+    if parts.next().is_some() {
+        return None;
+    }
+
+    match kind {
+        "key" => key_from_name(name).map(Control::Key),
+        "mouse" => mouse_from_name(name).map(Control::Mouse),
+        "button" => GamepadButton::from_name(name).map(Control::Button),
+        "axis+" => GamepadAxis::from_name(name).map(Control::AxisPositive),
+        "axis-" => GamepadAxis::from_name(name).map(Control::AxisNegative),
+        _ => None
+    }
+}
+
+
+fn mouse_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        _ => name.parse().ok().map(MouseButton::Other)
+    }
+}
+
+
+/// Finds a key by the name it is written with
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    KEYS.iter().cloned().find(|key| format!("{:?}", key) == name)
+}
+
+
+/// The keys that can be read back from a file, other keys can't be bound by the player or saved
+const KEYS: &[KeyCode] = &[
+    KeyCode::A, KeyCode::B, KeyCode::C, KeyCode::D, KeyCode::E, KeyCode::F, KeyCode::G,
+    KeyCode::H, KeyCode::I, KeyCode::J, KeyCode::K, KeyCode::L, KeyCode::M, KeyCode::N,
+    KeyCode::O, KeyCode::P, KeyCode::Q, KeyCode::R, KeyCode::S, KeyCode::T, KeyCode::U,
+    KeyCode::V, KeyCode::W, KeyCode::X, KeyCode::Y, KeyCode::Z,
+
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+
+    KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4, KeyCode::F5, KeyCode::F6,
+    KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12,
+
+    KeyCode::Escape, KeyCode::Space, KeyCode::Return, KeyCode::Tab, KeyCode::Back,
+    KeyCode::Insert, KeyCode::Delete, KeyCode::Home, KeyCode::End, KeyCode::PageUp, KeyCode::PageDown,
+    KeyCode::Left, KeyCode::Right, KeyCode::Up, KeyCode::Down,
+
+    KeyCode::LShift, KeyCode::RShift, KeyCode::LControl, KeyCode::RControl, KeyCode::LAlt, KeyCode::RAlt,
+    KeyCode::Capital, KeyCode::Pause,
+
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::Add, KeyCode::Subtract, KeyCode::Multiply, KeyCode::Divide, KeyCode::Decimal, KeyCode::NumpadEnter,
+
+    KeyCode::Comma, KeyCode::Period, KeyCode::Minus, KeyCode::Equals, KeyCode::Semicolon, KeyCode::Apostrophe,
+    KeyCode::Slash, KeyCode::Backslash, KeyCode::LBracket, KeyCode::RBracket, KeyCode::Grave,
+];
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::gamepad::VirtualBackend;
+
+
+    fn example() -> ActionMap {
+        let mut map = ActionMap::new();
+
+        map.bind("jump", Control::Key(KeyCode::Space));
+        map.bind("jump", Control::Button(GamepadButton::A));
+        map.bind("fire", Control::Mouse(MouseButton::Left));
+        map.bind("fire", Control::Mouse(MouseButton::Other(4)));
+        map.bind("move", Binding::wasd());
+        map.bind("move", Binding::left_stick());
+        map.bind("steer", Binding::gamepad_axis(GamepadAxis::RightX));
+        map.bind("brake", Control::AxisPositive(GamepadAxis::LeftTrigger));
+        map.rebind("unused", Vec::new());
+
+        map
+    }
+
+
+    #[test]
+    fn display_round_trips_through_parse() {
+        let map = example();
+        let text = map.to_string();
+        let parsed = ActionMap::parse(&text).unwrap();
+
+        assert_eq!(parsed.to_string(), text);
+        for name in ["jump", "fire", "move", "steer", "brake", "unused"].iter() {
+            assert_eq!(parsed.bindings(name), map.bindings(name), "{}", name);
+        }
+    }
+
+
+    #[test]
+    fn actions_with_only_unsaved_keys_are_kept() {
+        let mut map = ActionMap::new();
+        map.bind("screenshot", KeyCode::Snapshot);
+        map.bind("jump", KeyCode::Snapshot);
+        map.bind("jump", KeyCode::Space);
+
+        assert_eq!(map.to_string(), "jump = key Space\nscreenshot =\n");
+
+        let parsed = ActionMap::parse(&map.to_string()).unwrap();
+        assert_eq!(parsed.actions(), vec!["jump", "screenshot"]);
+    }
+
+
+    #[test]
+    fn every_known_key_round_trips() {
+        for key in KEYS.iter() {
+            let control = Control::Key(*key);
+            assert_eq!(parse_control(&format_control(&control)), Some(control));
+        }
+    }
+
+
+    #[test]
+    fn reports_the_first_invalid_line() {
+        assert_eq!(ActionMap::parse("# Comment\n\njump = key Space\njump = key Nonsense\n").err(), Some(4));
+        assert_eq!(ActionMap::parse("= key Space").err(), Some(1));
+        assert_eq!(ActionMap::parse("move = vector key A, key D").err(), Some(1));
+    }
+
+
+    fn input() -> Input {
+        Input::new((800, 600))
+    }
+
+
+    #[test]
+    fn actions_go_down_and_come_back_up() {
+        let mut map = ActionMap::new();
+        map.bind("jump", KeyCode::Space);
+        map.bind("jump", KeyCode::Up);
+
+        let mut input = input();
+        map.update(&input);
+        assert!(!map.is_down("jump") && !map.was_pressed_this_frame("jump") && !map.was_released("jump"));
+
+        input.press_key(KeyCode::Space);
+        map.update(&input);
+        assert!(map.is_down("jump") && map.was_pressed_this_frame("jump"));
+        input.end_frame();
+
+        // Holding a second binding doesn't press the action again
+        input.press_key(KeyCode::Up);
+        map.update(&input);
+        assert!(map.is_down("jump") && !map.was_pressed_this_frame("jump"));
+        input.end_frame();
+
+        input.release_key(KeyCode::Space);
+        map.update(&input);
+        assert!(map.is_down("jump") && !map.was_released("jump"));
+        input.end_frame();
+
+        input.release_key(KeyCode::Up);
+        map.update(&input);
+        assert!(!map.is_down("jump") && map.was_released("jump"));
+        input.end_frame();
+
+        map.update(&input);
+        assert!(!map.is_down("jump") && !map.was_pressed_this_frame("jump") && !map.was_released("jump"));
+        assert!(!map.is_down("unknown"));
+    }
+
+
+    #[test]
+    fn taps_within_a_frame_still_press() {
+        let mut map = ActionMap::new();
+        map.bind("fire", MouseButton::Left);
+
+        let mut input = input();
+        input.press_button(MouseButton::Left);
+        input.release_button(MouseButton::Left);
+
+        map.update(&input);
+        assert!(map.was_pressed_this_frame("fire"));
+        assert!(!map.is_down("fire"));
+        input.end_frame();
+
+        map.update(&input);
+        assert!(!map.was_pressed_this_frame("fire") && !map.was_released("fire"));
+    }
+
+
+    #[test]
+    fn diagonal_vectors_are_no_longer_than_one() {
+        let mut map = ActionMap::new();
+        map.bind("move", Binding::wasd());
+        map.bind("move", Binding::arrows());
+
+        let mut input = input();
+        input.press_key(KeyCode::W);
+        input.press_key(KeyCode::D);
+        map.update(&input);
+
+        let vector = map.vector("move");
+        let half = 0.5f64.sqrt();
+        assert!((vector.x - half).abs() < 1e-9 && (vector.y - half).abs() < 1e-9);
+
+        // Both bindings pushing the same way still add up to at most 1
+        input.press_key(KeyCode::Right);
+        map.update(&input);
+        let vector = map.vector("move");
+        assert!((vector.dot(vector).sqrt() - 1.0).abs() < 1e-9);
+        assert!(vector.x > half);
+    }
+
+
+    #[test]
+    fn bindings_add_up() {
+        let backend = VirtualBackend::new();
+        let mut input = input();
+        input.gamepads_mut().set_backend(Box::new(backend.clone()));
+
+        let pad = GamepadId(0);
+        backend.connect(pad, "Virtual Pad", "0123456789abcdef0123456789abcdef");
+        backend.set_axis(pad, 0, 0.6);
+        input.gamepads_mut().update();
+
+        let mut map = ActionMap::new();
+        map.bind("steer", Binding::Axis { negative: Control::Key(KeyCode::A), positive: Control::Key(KeyCode::D) });
+        map.bind("steer", Binding::gamepad_axis(GamepadAxis::LeftX));
+
+        // The stick is halfway between the dead zone of 0.2 and the end of its range
+        map.update(&input);
+        assert!((map.value("steer") - 0.5).abs() < 1e-9);
+
+        input.press_key(KeyCode::A);
+        map.update(&input);
+        assert!((map.value("steer") + 0.5).abs() < 1e-9);
+
+        input.release_key(KeyCode::A);
+        input.press_key(KeyCode::D);
+        map.update(&input);
+        assert_eq!(map.value("steer"), 1.0);
+        assert!(map.is_down("steer"));
+
+        // Only the chosen controller is read
+        map.set_gamepad(Some(GamepadId(1)));
+        map.update(&input);
+        assert_eq!(map.value("steer"), 1.0);
+        input.release_key(KeyCode::D);
+        map.update(&input);
+        assert_eq!(map.value("steer"), 0.0);
+    }
+}
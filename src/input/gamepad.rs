@@ -0,0 +1,883 @@
+use Vector2;
+use input::mapping::GamepadMapping;
+use input::mapping::MappingDatabase;
+use input::mapping::Range;
+use input::mapping::Source;
+use input::mapping::Target;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+
+/// Identifies a connected controller, chosen by the backend
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub u32);
+
+
+/// The buttons of a standard controller, laid out like an Xbox controller
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GamepadButton {
+    A,
+    B,
+    X,
+    Y,
+    Back,
+    Guide,
+    Start,
+    LeftStick,
+    RightStick,
+    LeftShoulder,
+    RightShoulder,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+
+/// The axes of a standard controller.
+/// Sticks range from -1 to 1 with positive y pointing down, triggers range from 0 to 1.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum GamepadAxis {
+    LeftX,
+    LeftY,
+    RightX,
+    RightY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 15] = [
+        GamepadButton::A, GamepadButton::B, GamepadButton::X, GamepadButton::Y,
+        GamepadButton::Back, GamepadButton::Guide, GamepadButton::Start,
+        GamepadButton::LeftStick, GamepadButton::RightStick,
+        GamepadButton::LeftShoulder, GamepadButton::RightShoulder,
+        GamepadButton::DPadUp, GamepadButton::DPadDown, GamepadButton::DPadLeft, GamepadButton::DPadRight,
+    ];
+
+
+    /// Returns the name of the button in the SDL game controller database
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GamepadButton::A => "a",
+            GamepadButton::B => "b",
+            GamepadButton::X => "x",
+            GamepadButton::Y => "y",
+            GamepadButton::Back => "back",
+            GamepadButton::Guide => "guide",
+            GamepadButton::Start => "start",
+            GamepadButton::LeftStick => "leftstick",
+            GamepadButton::RightStick => "rightstick",
+            GamepadButton::LeftShoulder => "leftshoulder",
+            GamepadButton::RightShoulder => "rightshoulder",
+            GamepadButton::DPadUp => "dpup",
+            GamepadButton::DPadDown => "dpdown",
+            GamepadButton::DPadLeft => "dpleft",
+            GamepadButton::DPadRight => "dpright",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GamepadButton> {
+        GamepadButton::ALL.iter().cloned().find(|button| button.name() == name)
+    }
+}
+
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftX, GamepadAxis::LeftY,
+        GamepadAxis::RightX, GamepadAxis::RightY,
+        GamepadAxis::LeftTrigger, GamepadAxis::RightTrigger,
+    ];
+
+
+    /// Returns the name of the axis in the SDL game controller database
+    pub fn name(&self) -> &'static str {
+        match *self {
+            GamepadAxis::LeftX => "leftx",
+            GamepadAxis::LeftY => "lefty",
+            GamepadAxis::RightX => "rightx",
+            GamepadAxis::RightY => "righty",
+            GamepadAxis::LeftTrigger => "lefttrigger",
+            GamepadAxis::RightTrigger => "righttrigger",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GamepadAxis> {
+        GamepadAxis::ALL.iter().cloned().find(|axis| axis.name() == name)
+    }
+
+    pub fn is_trigger(&self) -> bool {
+        match *self {
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => true,
+            _ => false
+        }
+    }
+}
+
+
+/// Something that happened to a controller, after mapping and dead zones
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected(GamepadId),
+    Disconnected(GamepadId),
+    ButtonPressed(GamepadId, GamepadButton),
+    ButtonReleased(GamepadId, GamepadButton),
+    AxisMoved(GamepadId, GamepadAxis, f64),
+}
+
+
+/// What a backend reports about a device.
+/// Raw buttons, axes and hats are translated through the device's mapping,
+/// standard buttons and axes are for backends that already map devices themselves and are used as they are.
+#[derive(Clone, Debug, PartialEq)]
+pub enum RawGamepadEvent {
+    Connected { id: GamepadId, name: String, guid: String },
+    Disconnected { id: GamepadId },
+
+    Button { id: GamepadId, index: u32, pressed: bool },
+    /// An axis from -1 to 1
+    Axis { id: GamepadId, index: u32, value: f64 },
+    /// A hat switch as a bitmask of up (1), right (2), down (4) and left (8)
+    Hat { id: GamepadId, index: u32, mask: u8 },
+
+    StandardButton { id: GamepadId, button: GamepadButton, pressed: bool },
+    StandardAxis { id: GamepadId, axis: GamepadAxis, value: f64 },
+}
+
+
+impl RawGamepadEvent {
+    pub fn id(&self) -> GamepadId {
+        match *self {
+            RawGamepadEvent::Connected { id, .. } |
+            RawGamepadEvent::Disconnected { id } |
+            RawGamepadEvent::Button { id, .. } |
+            RawGamepadEvent::Axis { id, .. } |
+            RawGamepadEvent::Hat { id, .. } |
+            RawGamepadEvent::StandardButton { id, .. } |
+            RawGamepadEvent::StandardAxis { id, .. } => id
+        }
+    }
+}
+
+
+/// A source of controller events
+pub trait GamepadBackend {
+    /// Appends the events that happened since the last poll
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>);
+
+    /// Returns true if the backend only reports standard buttons and axes,
+    /// in which case its devices get no mapping from the database
+    fn maps_devices(&self) -> bool {
+        false
+    }
+}
+
+
+/// A backend without hardware, driven by sending it events.
+/// Clones share their events, so one can be kept to control the devices of another.
+#[derive(Clone)]
+pub struct VirtualBackend {
+    events: Rc<RefCell<Vec<RawGamepadEvent>>>,
+}
+
+
+impl VirtualBackend {
+    pub fn new() -> VirtualBackend {
+        VirtualBackend {
+            events: Rc::new(RefCell::new(Vec::new())),
+        }
+    }
+
+
+    /// Queues an event for the next poll
+    pub fn send(&self, event: RawGamepadEvent) {
+        self.events.borrow_mut().push(event);
+    }
+
+
+    pub fn connect(&self, id: GamepadId, name: &str, guid: &str) {
+        self.send(RawGamepadEvent::Connected { id, name: name.to_string(), guid: guid.to_string() });
+    }
+
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(RawGamepadEvent::Disconnected { id });
+    }
+
+
+    pub fn set_button(&self, id: GamepadId, index: u32, pressed: bool) {
+        self.send(RawGamepadEvent::Button { id, index, pressed });
+    }
+
+    pub fn set_axis(&self, id: GamepadId, index: u32, value: f64) {
+        self.send(RawGamepadEvent::Axis { id, index, value });
+    }
+
+    pub fn set_hat(&self, id: GamepadId, index: u32, mask: u8) {
+        self.send(RawGamepadEvent::Hat { id, index, mask });
+    }
+}
+
+
+impl GamepadBackend for VirtualBackend {
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>) {
+        events.extend(self.events.borrow_mut().drain(..));
+    }
+}
+
+
+/// The state of a connected controller
+pub struct Gamepad {
+    id: GamepadId,
+    name: String,
+    guid: String,
+    mapping: Option<GamepadMapping>,
+
+    raw_buttons: HashMap<u32, bool>,
+    raw_axes: HashMap<u32, f64>,
+    hats: HashMap<u32, u8>,
+
+    // Translated through the mapping and reported as standard by the backend, kept apart
+    // so remapping the device doesn't undo what the backend reported
+    mapped_buttons: HashSet<GamepadButton>,
+    standard_buttons: HashSet<GamepadButton>,
+
+    buttons_down: HashSet<GamepadButton>,
+    buttons_pressed: HashSet<GamepadButton>,
+    buttons_released: HashSet<GamepadButton>,
+
+    // Axes before dead zones
+    mapped_axes: HashMap<GamepadAxis, f64>,
+    standard_axes: HashMap<GamepadAxis, f64>,
+
+    dead_zone: f64,
+    trigger_dead_zone: f64,
+}
+
+
+impl Gamepad {
+    fn new(id: GamepadId, name: String, guid: String, mapping: Option<GamepadMapping>) -> Gamepad {
+        Gamepad {
+            id,
+            name,
+            guid,
+            mapping,
+
+            raw_buttons: HashMap::new(),
+            raw_axes: HashMap::new(),
+            hats: HashMap::new(),
+
+            mapped_buttons: HashSet::new(),
+            standard_buttons: HashSet::new(),
+
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+
+            mapped_axes: HashMap::new(),
+            standard_axes: HashMap::new(),
+
+            dead_zone: 0.0,
+            trigger_dead_zone: 0.0,
+        }
+    }
+
+
+    pub fn id(&self) -> GamepadId {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn guid(&self) -> &str {
+        &self.guid
+    }
+
+    /// Returns the mapping used to translate the device's buttons and axes,
+    /// or None if the backend maps the device itself
+    pub fn mapping(&self) -> Option<&GamepadMapping> {
+        self.mapping.as_ref()
+    }
+
+
+    /// Returns true if a button is held down
+    pub fn is_button_down(&self, button: GamepadButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// Returns true if a button went down since the last frame
+    pub fn was_button_pressed_this_frame(&self, button: GamepadButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Returns true if a button was let go since the last frame
+    pub fn was_button_released(&self, button: GamepadButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+
+    /// Returns the value of an axis with dead zones applied
+    pub fn axis(&self, axis: GamepadAxis) -> f64 {
+        match axis {
+            GamepadAxis::LeftX => self.left_stick().x,
+            GamepadAxis::LeftY => self.left_stick().y,
+            GamepadAxis::RightX => self.right_stick().x,
+            GamepadAxis::RightY => self.right_stick().y,
+            GamepadAxis::LeftTrigger | GamepadAxis::RightTrigger => {
+                let value = self.raw_axis(axis);
+                if value <= self.trigger_dead_zone {
+                    0.0
+                } else {
+                    ((value - self.trigger_dead_zone) / (1.0 - self.trigger_dead_zone)).min(1.0)
+                }
+            }
+        }
+    }
+
+    /// Returns the value of an axis as mapped, without dead zones
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f64 {
+        self.standard_axes.get(&axis)
+            .or_else(|| self.mapped_axes.get(&axis))
+            .cloned()
+            .unwrap_or(0.0)
+    }
+
+
+    /// Returns the left stick with its dead zone applied
+    pub fn left_stick(&self) -> Vector2 {
+        self.stick(GamepadAxis::LeftX, GamepadAxis::LeftY)
+    }
+
+    /// Returns the right stick with its dead zone applied
+    pub fn right_stick(&self) -> Vector2 {
+        self.stick(GamepadAxis::RightX, GamepadAxis::RightY)
+    }
+
+
+    /// Returns true if a button of the device, as numbered by the backend, is held down
+    pub fn is_raw_button_down(&self, index: u32) -> bool {
+        self.raw_buttons.get(&index).cloned().unwrap_or(false)
+    }
+
+    /// Returns an axis of the device, as numbered by the backend
+    pub fn raw_device_axis(&self, index: u32) -> f64 {
+        self.raw_axes.get(&index).cloned().unwrap_or(0.0)
+    }
+
+
+    // Applies a radial dead zone, rescaling the rest of the range to start at zero
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> Vector2 {
+        let value = Vector2::new(self.raw_axis(x), self.raw_axis(y));
+        let length = value.dot(value).sqrt();
+
+        if length <= self.dead_zone || length == 0.0 {
+            Vector2::new(0.0, 0.0)
+        } else {
+            let scaled = ((length - self.dead_zone) / (1.0 - self.dead_zone)).min(1.0);
+            Vector2::new(value.x * scaled / length, value.y * scaled / length)
+        }
+    }
+
+
+    fn apply(&mut self, event: RawGamepadEvent) {
+        match event {
+            RawGamepadEvent::Button { index, pressed, .. } => {
+                self.raw_buttons.insert(index, pressed);
+                self.remap();
+            },
+            RawGamepadEvent::Axis { index, value, .. } => {
+                self.raw_axes.insert(index, value.max(-1.0).min(1.0));
+                self.remap();
+            },
+            RawGamepadEvent::Hat { index, mask, .. } => {
+                self.hats.insert(index, mask);
+                self.remap();
+            },
+
+            RawGamepadEvent::StandardButton { button, pressed, .. } => {
+                if pressed {
+                    self.standard_buttons.insert(button);
+                } else {
+                    self.standard_buttons.remove(&button);
+                }
+                self.update_buttons();
+            },
+            RawGamepadEvent::StandardAxis { axis, value, .. } => {
+                self.standard_axes.insert(axis, clamp_axis(axis, value));
+            },
+
+            RawGamepadEvent::Connected { .. } | RawGamepadEvent::Disconnected { .. } => {}
+        }
+    }
+
+
+    // Recomputes the mapped buttons and axes from the device's state
+    fn remap(&mut self) {
+        let mut buttons = HashSet::new();
+        let mut axes = HashMap::new();
+
+        for &(source, target) in self.mapping.iter().flat_map(|mapping| mapping.bindings.iter()) {
+            let value = match self.source_value(source) {
+                Some(value) => value,
+                None => continue
+            };
+
+            match target {
+                Target::Button(button) => {
+                    if value > 0.5 {
+                        buttons.insert(button);
+                    }
+                },
+
+                Target::Axis { axis, range } => {
+                    let (full_source, half_source) = match source {
+                        Source::Axis { range: Range::Full, .. } => (true, false),
+                        Source::Axis { .. } => (false, true),
+                        _ => (false, false)
+                    };
+
+                    let value = match range {
+                        Range::Positive => value,
+                        Range::Negative => -value,
+
+                        // Triggers rest at -1 on a full axis
+                        Range::Full if axis.is_trigger() && full_source => (value + 1.0) / 2.0,
+                        Range::Full if !axis.is_trigger() && half_source => value * 2.0 - 1.0,
+                        Range::Full => value,
+                    };
+
+                    *axes.entry(axis).or_insert(0.0) += value;
+                }
+            }
+        }
+
+        self.mapped_buttons = buttons;
+        self.mapped_axes = axes.into_iter()
+            .map(|(axis, value)| (axis, clamp_axis(axis, value)))
+            .collect();
+
+        self.update_buttons();
+    }
+
+
+    // A button is down while either the mapping or the backend says so
+    fn update_buttons(&mut self) {
+        for button in GamepadButton::ALL.iter() {
+            if self.mapped_buttons.contains(button) || self.standard_buttons.contains(button) {
+                self.press(*button);
+            } else {
+                self.release(*button);
+            }
+        }
+    }
+
+
+    // Returns None for axes the device hasn't reported yet, since their resting value is unknown
+    fn source_value(&self, source: Source) -> Option<f64> {
+        match source {
+            Source::Button(index) => {
+                Some(if self.is_raw_button_down(index) { 1.0 } else { 0.0 })
+            },
+
+            Source::Axis { index, range, inverted } => {
+                let value = *self.raw_axes.get(&index)?;
+                let value = if inverted { -value } else { value };
+
+                Some(match range {
+                    Range::Full => value,
+                    Range::Positive => value.max(0.0),
+                    Range::Negative => (-value).max(0.0),
+                })
+            },
+
+            Source::Hat { index, mask } => {
+                let state = self.hats.get(&index).cloned().unwrap_or(0);
+                Some(if state & mask != 0 { 1.0 } else { 0.0 })
+            }
+        }
+    }
+
+
+    fn press(&mut self, button: GamepadButton) {
+        if self.buttons_down.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+    }
+
+    fn release(&mut self, button: GamepadButton) {
+        if self.buttons_down.remove(&button) {
+            self.buttons_released.insert(button);
+        }
+    }
+
+
+    fn end_frame(&mut self) {
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+    }
+}
+
+
+/// All connected controllers, updated from a backend
+pub struct Gamepads {
+    backend: Option<Box<GamepadBackend>>,
+    // Events to handle before polling, such as disconnects after changing backends
+    pending: Vec<RawGamepadEvent>,
+    mappings: MappingDatabase,
+    gamepads: BTreeMap<GamepadId, Gamepad>,
+
+    dead_zone: f64,
+    trigger_dead_zone: f64,
+}
+
+
+impl Gamepads {
+    /// Creates a set of controllers without a backend, so none ever connect
+    pub fn new() -> Gamepads {
+        Gamepads {
+            backend: None,
+            pending: Vec::new(),
+            mappings: MappingDatabase::new(),
+            gamepads: BTreeMap::new(),
+
+            dead_zone: 0.2,
+            trigger_dead_zone: 0.05,
+        }
+    }
+
+    pub fn with_backend<B: GamepadBackend + 'static>(backend: B) -> Gamepads {
+        let mut gamepads = Gamepads::new();
+        gamepads.set_backend(Box::new(backend));
+        gamepads
+    }
+
+
+    /// Replaces the backend, disconnecting the controllers of the previous one on the next update
+    pub fn set_backend(&mut self, backend: Box<GamepadBackend>) {
+        for id in self.gamepads.keys() {
+            self.pending.push(RawGamepadEvent::Disconnected { id: *id });
+        }
+
+        self.backend = Some(backend);
+    }
+
+
+    /// Adds mappings in the format of the SDL game controller database, see `MappingDatabase::add_mappings`.
+    /// Only controllers connected afterwards use them, and only if the backend reports raw buttons and axes.
+    pub fn add_mappings(&mut self, text: &str) -> usize {
+        self.mappings.add_mappings(text)
+    }
+
+    pub fn mappings(&self) -> &MappingDatabase {
+        &self.mappings
+    }
+
+    pub fn mappings_mut(&mut self) -> &mut MappingDatabase {
+        &mut self.mappings
+    }
+
+
+    /// Sets the radius of the stick dead zone and the trigger dead zone, both as fractions of the full range
+    pub fn set_dead_zones(&mut self, stick: f64, trigger: f64) {
+        self.dead_zone = stick.max(0.0).min(0.99);
+        self.trigger_dead_zone = trigger.max(0.0).min(0.99);
+
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.dead_zone = self.dead_zone;
+            gamepad.trigger_dead_zone = self.trigger_dead_zone;
+        }
+    }
+
+    pub fn dead_zones(&self) -> (f64, f64) {
+        (self.dead_zone, self.trigger_dead_zone)
+    }
+
+
+    pub fn gamepad(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gamepads.get(&id)
+    }
+
+    /// Returns every connected controller, ordered by id
+    pub fn gamepads(&self) -> Vec<&Gamepad> {
+        self.gamepads.values().collect()
+    }
+
+    /// Returns the connected controller with the lowest id
+    pub fn first(&self) -> Option<&Gamepad> {
+        self.gamepads.values().next()
+    }
+
+
+    /// Polls the backend and returns what happened since the last update
+    pub fn update(&mut self) -> Vec<GamepadEvent> {
+        let mut raw: Vec<RawGamepadEvent> = self.pending.drain(..).collect();
+        if let Some(ref mut backend) = self.backend {
+            backend.poll(&mut raw);
+        }
+
+        let mut events = Vec::new();
+        for event in raw {
+            self.handle(event, &mut events);
+        }
+
+        events
+    }
+
+
+    /// Forgets which buttons were pressed and released during the frame
+    pub fn end_frame(&mut self) {
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.end_frame();
+        }
+    }
+
+
+    fn handle(&mut self, event: RawGamepadEvent, events: &mut Vec<GamepadEvent>) {
+        match event {
+            RawGamepadEvent::Connected { id, name, guid } => {
+                self.disconnect(id, events);
+
+                let mapped = self.backend.as_ref().map_or(false, |backend| backend.maps_devices());
+                let mapping = if mapped { None } else { Some(self.mappings.get_or_default(&guid).clone()) };
+
+                let mut gamepad = Gamepad::new(id, name, guid, mapping);
+                gamepad.dead_zone = self.dead_zone;
+                gamepad.trigger_dead_zone = self.trigger_dead_zone;
+
+                self.gamepads.insert(id, gamepad);
+                events.push(GamepadEvent::Connected(id));
+            },
+
+            RawGamepadEvent::Disconnected { id } => {
+                self.disconnect(id, events);
+            },
+
+            event => {
+                let id = event.id();
+                let gamepad = match self.gamepads.get_mut(&id) {
+                    Some(gamepad) => gamepad,
+                    None => return
+                };
+
+                let buttons: HashSet<GamepadButton> = gamepad.buttons_down.clone();
+                let axes: Vec<f64> = GamepadAxis::ALL.iter().map(|axis| gamepad.axis(*axis)).collect();
+
+                gamepad.apply(event);
+
+                for button in GamepadButton::ALL.iter() {
+                    match (buttons.contains(button), gamepad.is_button_down(*button)) {
+                        (false, true) => events.push(GamepadEvent::ButtonPressed(id, *button)),
+                        (true, false) => events.push(GamepadEvent::ButtonReleased(id, *button)),
+                        _ => ()
+                    }
+                }
+
+                for (axis, previous) in GamepadAxis::ALL.iter().zip(axes) {
+                    let value = gamepad.axis(*axis);
+                    if value != previous {
+                        events.push(GamepadEvent::AxisMoved(id, *axis, value));
+                    }
+                }
+            }
+        }
+    }
+
+
+    // Releases everything held on a controller and removes it
+    fn disconnect(&mut self, id: GamepadId, events: &mut Vec<GamepadEvent>) {
+        if let Some(gamepad) = self.gamepads.remove(&id) {
+            for button in GamepadButton::ALL.iter() {
+                if gamepad.is_button_down(*button) {
+                    events.push(GamepadEvent::ButtonReleased(id, *button));
+                }
+            }
+
+            events.push(GamepadEvent::Disconnected(id));
+        }
+    }
+}
+
+
+fn clamp_axis(axis: GamepadAxis, value: f64) -> f64 {
+    let min = if axis.is_trigger() { 0.0 } else { -1.0 };
+    value.max(min).min(1.0)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    const PAD: GamepadId = GamepadId(3);
+
+
+    fn connected() -> (VirtualBackend, Gamepads) {
+        let backend = VirtualBackend::new();
+        let mut gamepads = Gamepads::with_backend(backend.clone());
+
+        backend.connect(PAD, "Virtual Pad", "0123456789abcdef0123456789abcdef");
+        assert_eq!(gamepads.update(), vec![GamepadEvent::Connected(PAD)]);
+
+        (backend, gamepads)
+    }
+
+
+    #[test]
+    fn disconnecting_releases_held_buttons() {
+        let (backend, mut gamepads) = connected();
+
+        backend.set_button(PAD, 0, true);
+        backend.set_button(PAD, 4, true);
+        assert_eq!(gamepads.update(), vec![
+            GamepadEvent::ButtonPressed(PAD, GamepadButton::A),
+            GamepadEvent::ButtonPressed(PAD, GamepadButton::LeftShoulder),
+        ]);
+        assert!(gamepads.gamepad(PAD).unwrap().is_button_down(GamepadButton::A));
+
+        backend.disconnect(PAD);
+        assert_eq!(gamepads.update(), vec![
+            GamepadEvent::ButtonReleased(PAD, GamepadButton::A),
+            GamepadEvent::ButtonReleased(PAD, GamepadButton::LeftShoulder),
+            GamepadEvent::Disconnected(PAD),
+        ]);
+        assert!(gamepads.gamepad(PAD).is_none());
+
+        // Events for controllers that aren't connected are ignored
+        backend.set_button(PAD, 0, true);
+        assert!(gamepads.update().is_empty());
+    }
+
+
+    #[test]
+    fn dead_zones_rescale_the_remaining_range() {
+        let (backend, mut gamepads) = connected();
+        gamepads.set_dead_zones(0.2, 0.1);
+
+        backend.set_axis(PAD, 0, 0.15);
+        assert!(gamepads.update().is_empty());
+        assert_eq!(gamepads.gamepad(PAD).unwrap().axis(GamepadAxis::LeftX), 0.0);
+        assert_eq!(gamepads.gamepad(PAD).unwrap().raw_axis(GamepadAxis::LeftX), 0.15);
+
+        backend.set_axis(PAD, 0, 0.6);
+        match &gamepads.update()[..] {
+            &[GamepadEvent::AxisMoved(PAD, GamepadAxis::LeftX, value)] => assert!((value - 0.5).abs() < 1e-9),
+            events => panic!("unexpected events {:?}", events)
+        }
+
+        backend.set_axis(PAD, 0, 1.0);
+        gamepads.update();
+        assert!((gamepads.gamepad(PAD).unwrap().axis(GamepadAxis::LeftX) - 1.0).abs() < 1e-9);
+
+        // The default mapping reads the trigger from a full axis resting at -1
+        backend.set_axis(PAD, 2, -0.6);
+        gamepads.update();
+        let gamepad = gamepads.gamepad(PAD).unwrap();
+        assert!((gamepad.raw_axis(GamepadAxis::LeftTrigger) - 0.2).abs() < 1e-9);
+        assert!((gamepad.axis(GamepadAxis::LeftTrigger) - 0.1 / 0.9).abs() < 1e-9);
+    }
+
+
+    #[test]
+    fn raw_inputs_go_through_the_mapping() {
+        let backend = VirtualBackend::new();
+        let mut gamepads = Gamepads::with_backend(backend.clone());
+
+        let added = gamepads.add_mappings("\
+            # A controller with unusual numbering\n\
+            ABCDEF00000000000000000000000000,Odd Pad,a:b3,leftx:a1~,+lefty:+a2,dpup:h0.1,\n");
+        assert_eq!(added, 1);
+
+        backend.connect(PAD, "Odd Pad", "abcdef00000000000000000000000000");
+        gamepads.update();
+        assert_eq!(gamepads.gamepad(PAD).unwrap().mapping().unwrap().name, "Odd Pad");
+
+        backend.set_button(PAD, 0, true);
+        assert!(gamepads.update().is_empty());
+
+        backend.set_button(PAD, 3, true);
+        assert_eq!(gamepads.update(), vec![GamepadEvent::ButtonPressed(PAD, GamepadButton::A)]);
+
+        backend.set_hat(PAD, 0, 1 | 2);
+        assert_eq!(gamepads.update(), vec![GamepadEvent::ButtonPressed(PAD, GamepadButton::DPadUp)]);
+
+        backend.set_axis(PAD, 1, 0.5);
+        backend.set_axis(PAD, 2, -0.5);
+        gamepads.update();
+
+        let gamepad = gamepads.gamepad(PAD).unwrap();
+        assert_eq!(gamepad.raw_axis(GamepadAxis::LeftX), -0.5);
+        // Only the positive half of the device's axis is bound
+        assert_eq!(gamepad.raw_axis(GamepadAxis::LeftY), 0.0);
+        assert!(gamepad.is_raw_button_down(0));
+    }
+
+
+    #[test]
+    fn remapping_keeps_standard_buttons() {
+        let (backend, mut gamepads) = connected();
+
+        backend.send(RawGamepadEvent::StandardButton { id: PAD, button: GamepadButton::Start, pressed: true });
+        backend.send(RawGamepadEvent::StandardAxis { id: PAD, axis: GamepadAxis::RightX, value: 0.9 });
+        backend.set_button(PAD, 5, true);
+        backend.set_axis(PAD, 3, -0.9);
+
+        // The standard axis takes precedence over the mapped one
+        match &gamepads.update()[..] {
+            &[
+                GamepadEvent::ButtonPressed(PAD, GamepadButton::Start),
+                GamepadEvent::AxisMoved(PAD, GamepadAxis::RightX, value),
+                GamepadEvent::ButtonPressed(PAD, GamepadButton::RightShoulder),
+            ] => assert!((value - 0.875).abs() < 1e-9),
+            events => panic!("unexpected events {:?}", events)
+        }
+
+        let gamepad = gamepads.gamepad(PAD).unwrap();
+        assert!(gamepad.is_button_down(GamepadButton::Start));
+        assert!(gamepad.is_button_down(GamepadButton::RightShoulder));
+        assert_eq!(gamepad.raw_axis(GamepadAxis::RightX), 0.9);
+
+        // A button held through both stays down until both let go
+        backend.set_button(PAD, 7, true);
+        backend.send(RawGamepadEvent::StandardButton { id: PAD, button: GamepadButton::Start, pressed: false });
+        assert!(gamepads.update().is_empty());
+
+        backend.set_button(PAD, 7, false);
+        assert_eq!(gamepads.update(), vec![GamepadEvent::ButtonReleased(PAD, GamepadButton::Start)]);
+    }
+
+
+    #[test]
+    fn backends_that_map_devices_get_no_mapping() {
+        struct Mapped(VirtualBackend);
+
+        impl GamepadBackend for Mapped {
+            fn poll(&mut self, events: &mut Vec<RawGamepadEvent>) {
+                self.0.poll(events);
+            }
+
+            fn maps_devices(&self) -> bool {
+                true
+            }
+        }
+
+        let backend = VirtualBackend::new();
+        let mut gamepads = Gamepads::with_backend(Mapped(backend.clone()));
+
+        backend.connect(PAD, "Mapped Pad", "0123456789abcdef0123456789abcdef");
+        backend.set_button(PAD, 0, true);
+        assert_eq!(gamepads.update(), vec![GamepadEvent::Connected(PAD)]);
+
+        let gamepad = gamepads.gamepad(PAD).unwrap();
+        assert!(gamepad.mapping().is_none());
+        assert!(gamepad.is_raw_button_down(0));
+        assert!(!gamepad.is_button_down(GamepadButton::A));
+    }
+}
@@ -0,0 +1,129 @@
+use input::gamepad::GamepadBackend;
+use input::gamepad::RawGamepadEvent;
+use input::gamepad::GamepadId;
+use input::gamepad::GamepadButton;
+use input::gamepad::GamepadAxis;
+
+use gilrs::Gilrs;
+use gilrs::Button;
+use gilrs::Axis;
+use gilrs::EventType;
+
+
+/// Reads connected controllers through gilrs, which maps them with its own copy of the SDL database.
+/// Only standard buttons and axes are reported, so mappings added to `Gamepads` don't apply to these controllers.
+pub struct GilrsBackend {
+    gilrs: Gilrs,
+    // Controllers connected before the first poll are reported then
+    reported: bool,
+}
+
+
+impl GilrsBackend {
+    pub fn new() -> Option<GilrsBackend> {
+        Gilrs::new().ok().map(|gilrs| GilrsBackend { gilrs, reported: false })
+    }
+
+
+    fn connected(&self, id: usize) -> RawGamepadEvent {
+        let gamepad = self.gilrs.gamepad(id);
+
+        let guid: String = gamepad.uuid().iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect();
+
+        RawGamepadEvent::Connected { id: GamepadId(id as u32), name: gamepad.name().to_string(), guid }
+    }
+}
+
+
+impl GamepadBackend for GilrsBackend {
+    fn poll(&mut self, events: &mut Vec<RawGamepadEvent>) {
+        if !self.reported {
+            self.reported = true;
+
+            let ids: Vec<usize> = self.gilrs.gamepads().map(|(id, _)| id).collect();
+            for id in ids {
+                let event = self.connected(id);
+                events.push(event);
+            }
+        }
+
+        while let Some(event) = self.gilrs.next_event() {
+            let id = GamepadId(event.id as u32);
+
+            match event.event {
+                EventType::Connected => {
+                    let event = self.connected(event.id);
+                    events.push(event);
+                },
+                EventType::Disconnected => {
+                    events.push(RawGamepadEvent::Disconnected { id });
+                },
+
+                EventType::ButtonPressed(button, _) => {
+                    if let Some(button) = standard_button(button) {
+                        events.push(RawGamepadEvent::StandardButton { id, button, pressed: true });
+                    }
+                },
+                EventType::ButtonReleased(button, _) => {
+                    if let Some(button) = standard_button(button) {
+                        events.push(RawGamepadEvent::StandardButton { id, button, pressed: false });
+                    }
+                },
+
+                // The analog triggers are reported as buttons with a value
+                EventType::ButtonChanged(Button::LeftTrigger2, value, _) => {
+                    events.push(RawGamepadEvent::StandardAxis { id, axis: GamepadAxis::LeftTrigger, value: value as f64 });
+                },
+                EventType::ButtonChanged(Button::RightTrigger2, value, _) => {
+                    events.push(RawGamepadEvent::StandardAxis { id, axis: GamepadAxis::RightTrigger, value: value as f64 });
+                },
+
+                EventType::AxisChanged(axis, value, _) => {
+                    // gilrs points y up, the standard layout points it down
+                    let standard = match axis {
+                        Axis::LeftStickX => Some((GamepadAxis::LeftX, value)),
+                        Axis::LeftStickY => Some((GamepadAxis::LeftY, -value)),
+                        Axis::RightStickX => Some((GamepadAxis::RightX, value)),
+                        Axis::RightStickY => Some((GamepadAxis::RightY, -value)),
+                        _ => None
+                    };
+
+                    if let Some((axis, value)) = standard {
+                        events.push(RawGamepadEvent::StandardAxis { id, axis, value: value as f64 });
+                    }
+                },
+
+                _ => ()
+            }
+        }
+    }
+
+
+    fn maps_devices(&self) -> bool {
+        true
+    }
+}
+
+
+fn standard_button(button: Button) -> Option<GamepadButton> {
+    match button {
+        Button::South => Some(GamepadButton::A),
+        Button::East => Some(GamepadButton::B),
+        Button::West => Some(GamepadButton::X),
+        Button::North => Some(GamepadButton::Y),
+        Button::Select => Some(GamepadButton::Back),
+        Button::Mode => Some(GamepadButton::Guide),
+        Button::Start => Some(GamepadButton::Start),
+        Button::LeftThumb => Some(GamepadButton::LeftStick),
+        Button::RightThumb => Some(GamepadButton::RightStick),
+        Button::LeftTrigger => Some(GamepadButton::LeftShoulder),
+        Button::RightTrigger => Some(GamepadButton::RightShoulder),
+        Button::DPadUp => Some(GamepadButton::DPadUp),
+        Button::DPadDown => Some(GamepadButton::DPadDown),
+        Button::DPadLeft => Some(GamepadButton::DPadLeft),
+        Button::DPadRight => Some(GamepadButton::DPadRight),
+        _ => None
+    }
+}
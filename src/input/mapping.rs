@@ -0,0 +1,304 @@
+use input::gamepad::GamepadButton;
+use input::gamepad::GamepadAxis;
+
+use std::collections::HashMap;
+
+
+/// The layout of most controllers, used for controllers without a mapping
+pub const DEFAULT_MAPPING: &str = "00000000000000000000000000000000,Default Controller,\
+    a:b0,b:b1,x:b2,y:b3,leftshoulder:b4,rightshoulder:b5,back:b6,start:b7,guide:b8,\
+    leftstick:b9,rightstick:b10,leftx:a0,lefty:a1,lefttrigger:a2,rightx:a3,righty:a4,righttrigger:a5,\
+    dpup:h0.1,dpright:h0.2,dpdown:h0.4,dpleft:h0.8,";
+
+
+/// Which part of an axis is used
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Range {
+    Full,
+    Positive,
+    Negative,
+}
+
+
+/// A button, axis or hat of a controller, as reported by the device
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Source {
+    Button(u32),
+    Axis { index: u32, range: Range, inverted: bool },
+    Hat { index: u32, mask: u8 },
+}
+
+
+/// A standard button or axis
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub(crate) enum Target {
+    Button(GamepadButton),
+    Axis { axis: GamepadAxis, range: Range },
+}
+
+
+/// Describes which of a controller's buttons and axes are the standard ones,
+/// in the format of the SDL game controller database
+#[derive(Clone, Debug)]
+pub struct GamepadMapping {
+    pub guid: String,
+    pub name: String,
+
+    pub(crate) bindings: Vec<(Source, Target)>,
+}
+
+
+impl GamepadMapping {
+    /// Parses a single line of the SDL game controller database, such as
+    /// `030000005e0400008e02000014010000,Xbox 360 Controller,a:b0,b:b1,leftx:a0,dpup:h0.1,platform:Linux,`
+    pub fn parse(line: &str) -> Result<GamepadMapping, ()> {
+        let mut fields = line.trim().split(',');
+
+        let guid = fields.next().ok_or(())?.trim().to_lowercase();
+        let name = fields.next().ok_or(())?.trim().to_string();
+
+        if guid.is_empty() {
+            return Err(());
+        }
+
+        let mut bindings = Vec::new();
+
+        for field in fields {
+            let field = field.trim();
+            if field.is_empty() {
+                continue;
+            }
+
+            let mut parts = field.splitn(2, ':');
+            let key = parts.next().ok_or(())?;
+            let value = parts.next().ok_or(())?;
+
+            if key == "platform" {
+                continue;
+            }
+
+            // Skip outputs this library doesn't know about, such as extra paddles
+            let target = match parse_target(key) {
+                Some(target) => target,
+                None => continue
+            };
+
+            bindings.push((parse_source(value)?, target));
+        }
+
+        Ok(GamepadMapping { guid, name, bindings })
+    }
+
+
+    /// Returns the platform a line of the database is for, if it names one
+    pub fn platform(line: &str) -> Option<&str> {
+        line.split(',')
+            .filter_map(|field| {
+                let mut parts = field.trim().splitn(2, ':');
+                match (parts.next(), parts.next()) {
+                    (Some("platform"), Some(platform)) => Some(platform),
+                    _ => None
+                }
+            })
+            .next()
+    }
+}
+
+
+/// A collection of controller mappings by GUID
+pub struct MappingDatabase {
+    mappings: HashMap<String, GamepadMapping>,
+    default: GamepadMapping,
+}
+
+
+impl MappingDatabase {
+    /// Creates a database with no mappings besides the default
+    pub fn new() -> MappingDatabase {
+        MappingDatabase {
+            mappings: HashMap::new(),
+            default: GamepadMapping::parse(DEFAULT_MAPPING).unwrap(),
+        }
+    }
+
+
+    /// Adds the mappings for this platform from the contents of a database file, replacing mappings with the same GUID.
+    /// Comments, empty lines, invalid lines and lines for other platforms are skipped.
+    /// Returns the number of mappings added.
+    pub fn add_mappings(&mut self, text: &str) -> usize {
+        let mut added = 0;
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(platform) = GamepadMapping::platform(line) {
+                if platform != current_platform() {
+                    continue;
+                }
+            }
+
+            if let Ok(mapping) = GamepadMapping::parse(line) {
+                self.add(mapping);
+                added += 1;
+            }
+        }
+
+        added
+    }
+
+
+    /// Adds a mapping, replacing any mapping with the same GUID
+    pub fn add(&mut self, mapping: GamepadMapping) {
+        self.mappings.insert(mapping.guid.clone(), mapping);
+    }
+
+
+    pub fn get(&self, guid: &str) -> Option<&GamepadMapping> {
+        self.mappings.get(&guid.to_lowercase())
+    }
+
+
+    /// Returns the mapping for a GUID, or the default one if there is none
+    pub fn get_or_default(&self, guid: &str) -> &GamepadMapping {
+        self.get(guid).unwrap_or(&self.default)
+    }
+
+
+    pub fn len(&self) -> usize {
+        self.mappings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mappings.is_empty()
+    }
+}
+
+
+/// The name of the current platform in the SDL game controller database
+fn current_platform() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "Windows"
+    } else if cfg!(target_os = "macos") {
+        "Mac OS X"
+    } else if cfg!(target_os = "android") {
+        "Android"
+    } else if cfg!(target_os = "ios") {
+        "iOS"
+    } else {
+        "Linux"
+    }
+}
+
+
+/// Parses an output such as `a`, `leftx` or `+lefty`
+fn parse_target(key: &str) -> Option<Target> {
+    let (range, name) = if key.starts_with('+') {
+        (Range::Positive, &key[1..])
+    } else if key.starts_with('-') {
+        (Range::Negative, &key[1..])
+    } else {
+        (Range::Full, key)
+    };
+
+    if let Some(button) = GamepadButton::from_name(name) {
+        return Some(Target::Button(button));
+    }
+
+    GamepadAxis::from_name(name).map(|axis| Target::Axis { axis, range })
+}
+
+
+/// Parses an input such as `b3`, `a2`, `-a1`, `a5~` or `h0.4`
+fn parse_source(value: &str) -> Result<Source, ()> {
+    let (range, value) = if value.starts_with('+') {
+        (Range::Positive, &value[1..])
+    } else if value.starts_with('-') {
+        (Range::Negative, &value[1..])
+    } else {
+        (Range::Full, value)
+    };
+
+    let (inverted, value) = if value.ends_with('~') {
+        (true, &value[..value.len() - 1])
+    } else {
+        (false, value)
+    };
+
+    if value.starts_with('b') {
+        let index = value[1..].parse().map_err(|_| ())?;
+        Ok(Source::Button(index))
+    } else if value.starts_with('a') {
+        let index = value[1..].parse().map_err(|_| ())?;
+        Ok(Source::Axis { index, range, inverted })
+    } else if value.starts_with('h') {
+        let mut parts = value[1..].splitn(2, '.');
+        let index = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        let mask = parts.next().ok_or(())?.parse().map_err(|_| ())?;
+        Ok(Source::Hat { index, mask })
+    } else {
+        Err(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn parses_a_database_line() {
+        let mapping = GamepadMapping::parse(
+            "030000005E0400008E02000014010000,Xbox 360 Controller,a:b0,-leftx:-a0,lefttrigger:a2~,dpup:h0.1,paddle1:b20,platform:Linux,"
+        ).unwrap();
+
+        assert_eq!(mapping.guid, "030000005e0400008e02000014010000");
+        assert_eq!(mapping.name, "Xbox 360 Controller");
+        assert_eq!(mapping.bindings, vec![
+            (Source::Button(0), Target::Button(GamepadButton::A)),
+            (Source::Axis { index: 0, range: Range::Negative, inverted: false }, Target::Axis { axis: GamepadAxis::LeftX, range: Range::Negative }),
+            (Source::Axis { index: 2, range: Range::Full, inverted: true }, Target::Axis { axis: GamepadAxis::LeftTrigger, range: Range::Full }),
+            (Source::Hat { index: 0, mask: 1 }, Target::Button(GamepadButton::DPadUp)),
+        ]);
+
+        assert_eq!(GamepadMapping::platform("guid,name,a:b0,platform:Mac OS X,"), Some("Mac OS X"));
+    }
+
+
+    #[test]
+    fn rejects_malformed_lines() {
+        let lines = [
+            "",
+            "030000005e0400008e02000014010000",
+            ",Missing Guid,a:b0,",
+            "030000005e0400008e02000014010000,Bad Source,a:q3,",
+            "030000005e0400008e02000014010000,Bad Index,a:bx,",
+            "030000005e0400008e02000014010000,Hat Without Mask,dpup:h0,",
+            "030000005e0400008e02000014010000,Missing Value,a,",
+        ];
+
+        for line in lines.iter() {
+            assert!(GamepadMapping::parse(line).is_err(), "accepted {:?}", line);
+        }
+    }
+
+
+    #[test]
+    fn skips_comments_and_invalid_lines() {
+        let mut database = MappingDatabase::new();
+
+        let added = database.add_mappings("\
+            # Comment\n\
+            \n\
+            030000005e0400008e02000014010000,Broken,a:q3,\n\
+            030000005e0400008e02000014010000,Working,a:b1,\n");
+
+        assert_eq!(added, 1);
+        assert_eq!(database.len(), 1);
+        assert_eq!(database.get("030000005E0400008E02000014010000").unwrap().name, "Working");
+        assert_eq!(database.get_or_default("ffffffffffffffffffffffffffffffff").name, "Default Controller");
+    }
+}
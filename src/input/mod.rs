@@ -0,0 +1,324 @@
+use Vector2;
+use Rectangle;
+use app::KeyCode;
+use app::MouseButton;
+use app::ScrollDelta;
+
+pub use glium::glutin::ModifiersState as Modifiers;
+
+mod gamepad;
+pub use self::gamepad::Gamepads;
+pub use self::gamepad::Gamepad;
+pub use self::gamepad::GamepadId;
+pub use self::gamepad::GamepadButton;
+pub use self::gamepad::GamepadAxis;
+pub use self::gamepad::GamepadEvent;
+pub use self::gamepad::GamepadBackend;
+pub use self::gamepad::RawGamepadEvent;
+pub use self::gamepad::VirtualBackend;
+
+mod actions;
+pub use self::actions::ActionMap;
+pub use self::actions::ActionMapError;
+pub use self::actions::Binding;
+pub use self::actions::Control;
+
+mod mapping;
+pub use self::mapping::GamepadMapping;
+pub use self::mapping::MappingDatabase;
+
+#[cfg(feature = "gilrs")]
+mod gilrs_backend;
+#[cfg(feature = "gilrs")]
+pub use self::gilrs_backend::GilrsBackend;
+
+use std::collections::HashSet;
+
+
+/// The state of the keyboard, mouse and controllers, updated once per frame
+pub struct Input {
+    keys_down: HashSet<KeyCode>,
+    keys_pressed: HashSet<KeyCode>,
+    keys_released: HashSet<KeyCode>,
+
+    buttons_down: HashSet<MouseButton>,
+    buttons_pressed: HashSet<MouseButton>,
+    buttons_released: HashSet<MouseButton>,
+
+    modifiers: Modifiers,
+
+    // The cursor in pixels from the top left corner of the window
+    cursor: Vector2,
+
+    wheel_lines: Vector2,
+    wheel_pixels: Vector2,
+
+    // The size of the window in pixels and the area of the world it shows
+    window_size: (u32, u32),
+    view: Rectangle,
+
+    gamepads: Gamepads,
+}
+
+
+impl Input {
+    pub(crate) fn new(window_size: (u32, u32)) -> Input {
+        Input {
+            keys_down: HashSet::new(),
+            keys_pressed: HashSet::new(),
+            keys_released: HashSet::new(),
+
+            buttons_down: HashSet::new(),
+            buttons_pressed: HashSet::new(),
+            buttons_released: HashSet::new(),
+
+            modifiers: Modifiers::default(),
+
+            cursor: Vector2::new(0.0, 0.0),
+
+            wheel_lines: Vector2::new(0.0, 0.0),
+            wheel_pixels: Vector2::new(0.0, 0.0),
+
+            window_size,
+            view: Rectangle::new(-1.0, 1.0, 1.0, -1.0),
+
+            gamepads: Gamepads::new(),
+        }
+    }
+
+
+    /// Returns true if a key is held down
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
+    /// Returns true if a key went down since the last frame
+    pub fn was_key_pressed_this_frame(&self, key: KeyCode) -> bool {
+        self.keys_pressed.contains(&key)
+    }
+
+    /// Returns true if a key was let go since the last frame
+    pub fn was_key_released(&self, key: KeyCode) -> bool {
+        self.keys_released.contains(&key)
+    }
+
+    /// Returns every key held down
+    pub fn keys_down(&self) -> Vec<KeyCode> {
+        self.keys_down.iter().cloned().collect()
+    }
+
+    /// Returns every key that went down since the last frame
+    pub fn keys_pressed_this_frame(&self) -> Vec<KeyCode> {
+        self.keys_pressed.iter().cloned().collect()
+    }
+
+
+    /// Returns true if a mouse button is held down
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.buttons_down.contains(&button)
+    }
+
+    /// Returns true if a mouse button went down since the last frame
+    pub fn was_mouse_pressed_this_frame(&self, button: MouseButton) -> bool {
+        self.buttons_pressed.contains(&button)
+    }
+
+    /// Returns true if a mouse button was let go since the last frame
+    pub fn was_mouse_released(&self, button: MouseButton) -> bool {
+        self.buttons_released.contains(&button)
+    }
+
+    /// Returns every mouse button that went down since the last frame
+    pub fn mouse_pressed_this_frame(&self) -> Vec<MouseButton> {
+        self.buttons_pressed.iter().cloned().collect()
+    }
+
+
+    /// Returns which of shift, ctrl, alt and logo are held down
+    pub fn modifiers(&self) -> Modifiers {
+        self.modifiers
+    }
+
+    pub fn shift(&self) -> bool {
+        self.modifiers.shift
+    }
+
+    pub fn ctrl(&self) -> bool {
+        self.modifiers.ctrl
+    }
+
+    pub fn alt(&self) -> bool {
+        self.modifiers.alt
+    }
+
+
+    /// Returns the cursor position in pixels from the top left corner of the window
+    pub fn mouse_position(&self) -> Vector2 {
+        self.cursor
+    }
+
+    /// Returns the cursor position in the view the last frame was rendered with.
+    /// The view is picked up after rendering, so during `update` this lags one frame behind a moving camera,
+    /// use `mouse_position_in` with the new view instead.
+    pub fn mouse_world_position(&self) -> Vector2 {
+        self.mouse_position_in(self.view)
+    }
+
+    /// Returns the cursor position in a view covering the whole window
+    pub fn mouse_position_in(&self, view: Rectangle) -> Vector2 {
+        let (width, height) = self.window_size;
+        let x = self.cursor.x / width.max(1) as f64;
+        let y = self.cursor.y / height.max(1) as f64;
+
+        Vector2::new(
+            view.left + x * (view.right - view.left),
+            view.top + y * (view.bottom - view.top)
+        )
+    }
+
+
+    /// Returns how many lines the mouse wheel scrolled since the last frame
+    pub fn wheel_delta(&self) -> Vector2 {
+        self.wheel_lines
+    }
+
+    /// Returns how many pixels a touchpad scrolled since the last frame
+    pub fn wheel_pixel_delta(&self) -> Vector2 {
+        self.wheel_pixels
+    }
+
+
+    /// Returns the connected controllers
+    pub fn gamepads(&self) -> &Gamepads {
+        &self.gamepads
+    }
+
+    pub fn gamepad(&self, id: GamepadId) -> Option<&Gamepad> {
+        self.gamepads.gamepad(id)
+    }
+
+
+    /// Records a key going down, returns false if it was already down
+    pub(crate) fn press_key(&mut self, key: KeyCode) -> bool {
+        if self.keys_down.insert(key) {
+            self.keys_pressed.insert(key);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub(crate) fn release_key(&mut self, key: KeyCode) {
+        self.keys_down.remove(&key);
+        self.keys_released.insert(key);
+    }
+
+
+    pub(crate) fn press_button(&mut self, button: MouseButton) {
+        if self.buttons_down.insert(button) {
+            self.buttons_pressed.insert(button);
+        }
+    }
+
+    pub(crate) fn release_button(&mut self, button: MouseButton) {
+        self.buttons_down.remove(&button);
+        self.buttons_released.insert(button);
+    }
+
+
+    /// Releases everything held down, for when the window loses focus and stops receiving releases.
+    /// Returns the keys and buttons that were released.
+    pub(crate) fn release_all(&mut self) -> (Vec<KeyCode>, Vec<MouseButton>) {
+        let keys: Vec<KeyCode> = self.keys_down.drain().collect();
+        let buttons: Vec<MouseButton> = self.buttons_down.drain().collect();
+
+        self.keys_released.extend(keys.iter().cloned());
+        self.buttons_released.extend(buttons.iter().cloned());
+
+        self.modifiers = Modifiers::default();
+
+        (keys, buttons)
+    }
+
+
+    pub(crate) fn set_modifiers(&mut self, modifiers: Modifiers) {
+        self.modifiers = modifiers;
+    }
+
+    pub(crate) fn move_cursor(&mut self, x: f64, y: f64) {
+        self.cursor = Vector2::new(x, y);
+    }
+
+    pub(crate) fn scroll(&mut self, delta: ScrollDelta) {
+        match delta {
+            ScrollDelta::LineDelta(x, y) => self.wheel_lines += Vector2::new(x as f64, y as f64),
+            ScrollDelta::PixelDelta(x, y) => self.wheel_pixels += Vector2::new(x as f64, y as f64),
+        }
+    }
+
+    pub(crate) fn resize(&mut self, width: u32, height: u32) {
+        self.window_size = (width, height);
+    }
+
+    pub(crate) fn set_view(&mut self, view: Rectangle) {
+        self.view = view;
+    }
+
+    pub(crate) fn gamepads_mut(&mut self) -> &mut Gamepads {
+        &mut self.gamepads
+    }
+
+
+    /// Forgets what happened during the frame
+    pub(crate) fn end_frame(&mut self) {
+        self.keys_pressed.clear();
+        self.keys_released.clear();
+
+        self.buttons_pressed.clear();
+        self.buttons_released.clear();
+
+        self.wheel_lines = Vector2::new(0.0, 0.0);
+        self.wheel_pixels = Vector2::new(0.0, 0.0);
+
+        self.gamepads.end_frame();
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn losing_focus_releases_everything() {
+        let mut input = Input::new((800, 600));
+        input.press_key(KeyCode::Space);
+        input.press_button(MouseButton::Left);
+        input.end_frame();
+
+        let (keys, buttons) = input.release_all();
+        assert_eq!(keys, vec![KeyCode::Space]);
+        assert_eq!(buttons, vec![MouseButton::Left]);
+
+        assert!(!input.is_key_down(KeyCode::Space) && input.was_key_released(KeyCode::Space));
+        assert!(!input.is_mouse_down(MouseButton::Left) && input.was_mouse_released(MouseButton::Left));
+
+        // Nothing is left to release
+        assert_eq!(input.release_all(), (Vec::new(), Vec::new()));
+    }
+
+
+    #[test]
+    fn the_cursor_maps_to_the_view() {
+        let mut input = Input::new((800, 600));
+        input.move_cursor(200.0, 450.0);
+        input.set_view(Rectangle::new(0.0, 8.0, 6.0, 0.0));
+
+        let position = input.mouse_world_position();
+        assert!((position.x - 2.0).abs() < 1e-9 && (position.y - 1.5).abs() < 1e-9);
+
+        let position = input.mouse_position_in(Rectangle::new(-4.0, 4.0, 3.0, -3.0));
+        assert!((position.x + 2.0).abs() < 1e-9 && (position.y + 1.5).abs() < 1e-9);
+    }
+}
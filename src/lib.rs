@@ -6,6 +6,9 @@ extern crate trap;
 
 extern crate image;
 
+#[cfg(feature = "gilrs")]
+extern crate gilrs;
+
 
 
 pub use trap::*;
@@ -16,17 +19,56 @@ use std::time::Instant;
 mod context;
 pub use context::Context;
 
+mod input;
+pub use input::Input;
+pub use input::Modifiers;
+pub use input::Gamepads;
+pub use input::Gamepad;
+pub use input::GamepadId;
+pub use input::GamepadButton;
+pub use input::GamepadAxis;
+pub use input::GamepadEvent;
+pub use input::GamepadBackend;
+pub use input::RawGamepadEvent;
+pub use input::VirtualBackend;
+pub use input::GamepadMapping;
+pub use input::MappingDatabase;
+pub use input::ActionMap;
+pub use input::ActionMapError;
+pub use input::Binding;
+pub use input::Control;
+#[cfg(feature = "gilrs")]
+pub use input::GilrsBackend;
+
 mod app;
 pub use app::App;
 pub use app::KeyCode;
 pub use app::MouseButton;
+pub use app::ScrollDelta;
+pub use app::Touch;
+pub use app::TouchPhase;
 
 mod renderer;
 pub use renderer::Renderer;
 pub use renderer::Render;
 pub use renderer::Triangulate;
+pub use renderer::Tessellation;
 pub use renderer::PolygonMode;
 
+mod timestep;
+pub use timestep::FixedTimestep;
+
+mod transform;
+pub use transform::Affine2;
+
+mod path;
+pub use path::Path;
+pub use path::Polyline;
+pub use path::FillRule;
+pub use path::StrokeStyle;
+pub use path::LineJoin;
+pub use path::LineCap;
+
 mod color;
 pub use color::Color;
 
@@ -35,12 +77,45 @@ pub use shapes::Shape;
 pub use shapes::Rectangle;
 pub use shapes::Circle;
 pub use shapes::ConvexHull;
+pub use shapes::Polygon;
+pub use shapes::Ellipse;
+pub use shapes::Capsule;
+pub use shapes::RoundedRectangle;
+pub use shapes::Segment;
+pub use shapes::Triangle;
+pub use shapes::OrientedRectangle;
 
 
 mod collision;
 pub use collision::Collide;
 pub use collision::intersect;
 pub use collision::overlap;
+pub use collision::manifold;
+pub use collision::Manifold;
+pub use collision::ContactPoint;
+pub use collision::distance;
+pub use collision::Distance;
+pub use collision::shape_cast;
+pub use collision::TimeOfImpact;
+pub use collision::Ray;
+pub use collision::RayHit;
+pub use collision::raycast;
+pub use collision::raycast_all;
+pub use collision::raycast_first;
+pub use collision::BroadPhase;
+pub use collision::ProxyId;
+pub use collision::CollisionFilter;
+pub use collision::Sensors;
+pub use collision::SensorEvent;
+pub use collision::CharacterController;
+pub use collision::CharacterState;
+pub use collision::Obstacle;
+pub use collision::TileMap;
+pub use collision::Tile;
+pub use collision::TileContact;
+
+
+pub mod physics;
 
 
 mod texture;
@@ -50,36 +125,81 @@ pub use texture::Texture;
 mod frame_counter;
 pub use frame_counter::FrameCounter;
 
+mod frame_limiter;
+use frame_limiter::FrameLimiter;
+
+mod config;
+pub use config::AppConfig;
+
 
 
 use glium::glutin::KeyboardInput;
 use glium::glutin::ElementState;
-use std::collections::HashSet;
 
 
-pub fn run_app(mut app: Box<App>, width: u32, height: u32, title: &str) {
+pub fn run_app(mut app: Box<App>, config: AppConfig) {
     let mut events_loop = glium::glutin::EventsLoop::new();
+    let monitor = events_loop.get_primary_monitor();
+
+    let mut window = glium::glutin::WindowBuilder::new()
+        .with_dimensions(config.width, config.height)
+        .with_title(config.title.clone())
+        .with_decorations(!config.borderless);
+
+    if config.fullscreen {
+        window = window.with_fullscreen(Some(monitor.clone()));
+    }
+
+    // Windows that can't be resized are kept at their size by their limits
+    let (min_size, max_size) = if config.resizable {
+        (config.min_size, config.max_size)
+    } else {
+        (Some((config.width, config.height)), Some((config.width, config.height)))
+    };
+
+    if let Some((width, height)) = min_size {
+        window = window.with_min_dimensions(width, height);
+    }
+
+    if let Some((width, height)) = max_size {
+        window = window.with_max_dimensions(width, height);
+    }
 
-    let window = glium::glutin::WindowBuilder::new()
-        .with_dimensions(width, height)
-        //.with_fullscreen(Some(events_loop.get_primary_monitor()))
-        .with_title(title);
+    if let Some((ref rgba, width, height)) = config.icon {
+        window = window.with_window_icon(glium::glutin::Icon::from_rgba(rgba.clone(), width, height).ok());
+    }
 
     let context = glium::glutin::ContextBuilder::new()
-        .with_multisampling(8)
-        .with_vsync(false);
+        .with_multisampling(config.samples)
+        .with_stencil_buffer(8)
+        .with_vsync(config.vsync);
 
     let display = glium::Display::new(window, context, &events_loop).unwrap();
 
     let mut renderer = Renderer::new(display.clone());
 
 
-    app.init(Context::new(display.clone()));
+    let context = Context::with_monitor(display.clone(), monitor, config.fullscreen);
+    context.set_cursor_visible(config.cursor_visible);
+
+    #[cfg(feature = "gilrs")]
+    {
+        if let Some(backend) = GilrsBackend::new() {
+            context.set_gamepad_backend(backend);
+        }
+    }
+
+    app.init(context.clone());
+
+    if let Some(clock) = app.fixed_timestep() {
+        context.set_fixed_timestep(Some(clock));
+    }
 
 
     let mut running = true;
     let mut previous_instant = Instant::now();
-    let mut pressed_keys = HashSet::new();
+    let mut focused = true;
+    let mut frame_limiter = FrameLimiter::new();
 
     loop {
         events_loop.poll_events(|e|{
@@ -93,44 +213,82 @@ pub fn run_app(mut app: Box<App>, width: u32, height: u32, title: &str) {
                         },
 
                         WindowEvent::Resized(w, h) => {
+                            context.input_mut().resize(w, h);
                             app.size_changed(w, h);
                         },
-                        WindowEvent::Moved(_, _) => {},
+                        WindowEvent::Moved(x, y) => {
+                            app.window_moved(x, y);
+                        },
 
-                        WindowEvent::DroppedFile(_) => {},
-                        WindowEvent::HoveredFile(_) => {},
-                        WindowEvent::HoveredFileCancelled => {},
-                        WindowEvent::ReceivedCharacter(_) => {},
-                        WindowEvent::Focused(_) => {},
+                        WindowEvent::DroppedFile(path) => {
+                            app.file_dropped(path);
+                        },
+                        WindowEvent::HoveredFile(path) => {
+                            app.file_hovered(path);
+                        },
+                        WindowEvent::HoveredFileCancelled => {
+                            app.file_hover_cancelled();
+                        },
+                        WindowEvent::ReceivedCharacter(character) => {
+                            app.text_input(character);
+                        },
+                        WindowEvent::Focused(focus) => {
+                            focused = focus;
+                            if !focus {
+                                // Keys let go while unfocused are never reported, so release them now
+                                let (keys, buttons) = context.input_mut().release_all();
+                                for key in keys {
+                                    app.key_released(key);
+                                }
+                                for button in buttons {
+                                    app.mouse_released(button);
+                                }
+                            }
+                            app.focus_changed(focus);
+                        },
                         WindowEvent::KeyboardInput { input: KeyboardInput {
-                            state, virtual_keycode, ..
+                            state, virtual_keycode, modifiers, ..
                         }, .. } => {
+                            context.input_mut().set_modifiers(modifiers);
+
                             if let Some(key_code) = virtual_keycode {
                                 match state {
                                     ElementState::Pressed => {
-                                        if pressed_keys.insert(key_code) {
+                                        // Held keys repeat, but only the first press is reported
+                                        let first = context.input_mut().press_key(key_code);
+                                        if first {
                                             app.key_pressed(key_code);
                                         }
                                     },
                                     ElementState::Released => {
-                                        pressed_keys.remove(&key_code);
+                                        context.input_mut().release_key(key_code);
                                         app.key_released(key_code);
                                     },
                                 }
                             }
                         },
                         WindowEvent::CursorMoved { position: (x, y), .. } => {
+                            context.input_mut().move_cursor(x, y);
                             app.cursor_moved(x, y);
                         },
-                        WindowEvent::CursorEntered { .. } => {},
-                        WindowEvent::CursorLeft { .. } => {},
-                        WindowEvent::MouseWheel { .. } => {},
+                        WindowEvent::CursorEntered { .. } => {
+                            app.cursor_entered();
+                        },
+                        WindowEvent::CursorLeft { .. } => {
+                            app.cursor_left();
+                        },
+                        WindowEvent::MouseWheel { delta, .. } => {
+                            context.input_mut().scroll(delta);
+                            app.mouse_scrolled(delta);
+                        },
                         WindowEvent::MouseInput { state, button, .. } => {
                             match state {
                                 ElementState::Pressed => {
+                                    context.input_mut().press_button(button);
                                     app.mouse_pressed(button);
                                 },
                                 ElementState::Released => {
+                                    context.input_mut().release_button(button);
                                     app.mouse_released(button);
                                 },
                             }
@@ -138,8 +296,12 @@ pub fn run_app(mut app: Box<App>, width: u32, height: u32, title: &str) {
                         WindowEvent::TouchpadPressure { .. } => {},
                         WindowEvent::AxisMotion { .. } => {},
                         WindowEvent::Refresh => {},
-                        WindowEvent::Touch(_) => {},
-                        WindowEvent::HiDPIFactorChanged(_) => {},
+                        WindowEvent::Touch(touch) => {
+                            app.touch(touch);
+                        },
+                        WindowEvent::HiDPIFactorChanged(factor) => {
+                            app.dpi_changed(factor);
+                        },
                     }
                 },
 
@@ -148,18 +310,48 @@ pub fn run_app(mut app: Box<App>, width: u32, height: u32, title: &str) {
         });
 
         if running {
+            // Controllers are polled once per frame, after the window's events
+            let gamepad_events = context.input_mut().gamepads_mut().update();
+            for event in gamepad_events {
+                match event {
+                    GamepadEvent::Connected(id) => app.gamepad_connected(id),
+                    GamepadEvent::Disconnected(id) => app.gamepad_disconnected(id),
+                    GamepadEvent::ButtonPressed(id, button) => app.gamepad_button_pressed(id, button),
+                    GamepadEvent::ButtonReleased(id, button) => app.gamepad_button_released(id, button),
+                    GamepadEvent::AxisMoved(id, axis, value) => app.gamepad_axis_moved(id, axis, value),
+                }
+            }
+
             {
                 let current_instant = Instant::now();
                 let duration = current_instant - previous_instant;
                 let delta_time = duration.as_secs() as f64 + duration.subsec_nanos() as f64 * 1e-9;
+
+                // The clock is read every step as the app can change it during fixed_update
+                for _ in 0..context.advance_clock(delta_time) {
+                    if let Some(clock) = context.fixed_timestep() {
+                        app.fixed_update(clock.timestep());
+                    }
+                }
+
                 app.update(delta_time);
 
                 previous_instant = current_instant;
             }
 
+            let alpha = context.fixed_timestep().map_or(1.0, |clock| clock.alpha());
+
             renderer.begin();
-            app.render(&mut renderer);
+            app.render_interpolated(&mut renderer, alpha);
             renderer.end();
+
+            {
+                let mut input = context.input_mut();
+                input.set_view(renderer.view());
+                input.end_frame();
+            }
+
+            frame_limiter.wait(config.fps_limit(focused));
         } else {
             break;
         }
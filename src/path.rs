@@ -0,0 +1,556 @@
+use trap::Vector2;
+use renderer::Triangles;
+use renderer::Tessellation;
+
+use std::f64::consts::PI;
+
+
+/// Determines which parts of a path are inside when filling it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum FillRule {
+    /// Points with a non-zero winding number are inside
+    NonZero,
+
+    /// Points enclosed by an odd number of edges are inside
+    EvenOdd,
+}
+
+
+/// How two segments of a stroke are connected
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineJoin {
+    Miter,
+    Bevel,
+    Round,
+}
+
+
+/// How the ends of an open stroke are drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum LineCap {
+    Butt,
+    Square,
+    Round,
+}
+
+
+/// Describes the outline drawn when stroking a path
+#[derive(Copy, Clone, Debug)]
+pub struct StrokeStyle {
+    /// The width of the stroke in world units
+    pub width: f64,
+
+    pub join: LineJoin,
+    pub cap: LineCap,
+
+    /// The longest allowed miter, relative to the width, before falling back to a bevel
+    pub miter_limit: f64,
+}
+
+
+impl StrokeStyle {
+    pub fn new(width: f64) -> StrokeStyle {
+        StrokeStyle {
+            width,
+            join: LineJoin::Miter,
+            cap: LineCap::Butt,
+            miter_limit: 4.0,
+        }
+    }
+}
+
+
+/// A sequence of connected points
+#[derive(Clone, Debug)]
+pub struct Polyline {
+    pub points: Vec<Vector2>,
+
+    /// If the last point connects back to the first
+    pub closed: bool,
+}
+
+
+#[derive(Copy, Clone, Debug)]
+enum Command {
+    MoveTo(Vector2),
+    LineTo(Vector2),
+    Quadratic(Vector2, Vector2),
+    Cubic(Vector2, Vector2, Vector2),
+    Arc { center: Vector2, radius: f64, start: f64, end: f64 },
+    Close,
+}
+
+
+/// A shape made out of lines and curves
+#[derive(Clone, Debug)]
+pub struct Path {
+    commands: Vec<Command>,
+    fill_rule: FillRule,
+}
+
+
+impl Path {
+    pub fn new() -> Path {
+        Path {
+            commands: Vec::new(),
+            fill_rule: FillRule::NonZero,
+        }
+    }
+
+
+    /// Starts a new sub-path at a point
+    pub fn move_to(&mut self, point: Vector2) -> &mut Path {
+        self.commands.push(Command::MoveTo(point));
+        self
+    }
+
+
+    /// Adds a straight line from the current point
+    pub fn line_to(&mut self, point: Vector2) -> &mut Path {
+        self.commands.push(Command::LineTo(point));
+        self
+    }
+
+
+    /// Adds a quadratic Bezier curve from the current point
+    pub fn quadratic_to(&mut self, control: Vector2, point: Vector2) -> &mut Path {
+        self.commands.push(Command::Quadratic(control, point));
+        self
+    }
+
+
+    /// Adds a cubic Bezier curve from the current point
+    pub fn cubic_to(&mut self, first: Vector2, second: Vector2, point: Vector2) -> &mut Path {
+        self.commands.push(Command::Cubic(first, second, point));
+        self
+    }
+
+
+    /// Adds a circular arc, angles are in radians and counter-clockwise from the x-axis.
+    /// A line is added from the current point to the start of the arc.
+    pub fn arc(&mut self, center: Vector2, radius: f64, start_angle: f64, end_angle: f64) -> &mut Path {
+        self.commands.push(Command::Arc { center, radius, start: start_angle, end: end_angle });
+        self
+    }
+
+
+    /// Closes the current sub-path with a line back to its first point
+    pub fn close(&mut self) -> &mut Path {
+        self.commands.push(Command::Close);
+        self
+    }
+
+
+    /// Sets the rule used to fill the path
+    pub fn set_fill_rule(&mut self, rule: FillRule) -> &mut Path {
+        self.fill_rule = rule;
+        self
+    }
+
+    pub fn fill_rule(&self) -> FillRule {
+        self.fill_rule
+    }
+
+
+    /// Converts the path into polylines that deviate at most `tolerance` from the curves
+    pub fn flatten(&self, tolerance: f64) -> Vec<Polyline> {
+        let tolerance = tolerance.max(1e-9);
+
+        let mut polylines = Vec::new();
+        let mut current: Vec<Vector2> = Vec::new();
+
+        for command in self.commands.iter() {
+            match *command {
+                Command::MoveTo(point) => {
+                    finish_polyline(&mut polylines, &mut current, false);
+                    current.push(point);
+                },
+
+                Command::LineTo(point) => {
+                    current.push(point);
+                },
+
+                Command::Quadratic(control, end) => {
+                    let start = match current.last() {
+                        Some(&start) => start,
+                        None => { current.push(control); control }
+                    };
+
+                    let dd = start - 2.0 * control + end;
+                    let segments = (dd.dot(dd).sqrt() / (4.0 * tolerance)).sqrt().ceil().max(1.0) as u32;
+
+                    for i in 1..segments + 1 {
+                        let t = i as f64 / segments as f64;
+                        let u = 1.0 - t;
+                        current.push(u * u * start + 2.0 * u * t * control + t * t * end);
+                    }
+                },
+
+                Command::Cubic(first, second, end) => {
+                    let start = match current.last() {
+                        Some(&start) => start,
+                        None => { current.push(first); first }
+                    };
+
+                    let dd0 = start - 2.0 * first + second;
+                    let dd1 = first - 2.0 * second + end;
+                    let dd = dd0.dot(dd0).max(dd1.dot(dd1)).sqrt();
+                    let segments = (3.0 * dd / (4.0 * tolerance)).sqrt().ceil().max(1.0) as u32;
+
+                    for i in 1..segments + 1 {
+                        let t = i as f64 / segments as f64;
+                        let u = 1.0 - t;
+                        current.push(
+                            u * u * u * start + 3.0 * u * u * t * first +
+                                3.0 * u * t * t * second + t * t * t * end
+                        );
+                    }
+                },
+
+                Command::Arc { center, radius, start, end } => {
+                    let sweep = end - start;
+                    let segments = Tessellation::Tolerance(tolerance).arc_segments(radius, sweep);
+
+                    for i in 0..segments + 1 {
+                        let angle = start + sweep * i as f64 / segments as f64;
+                        let (dy, dx) = angle.sin_cos();
+                        current.push(center + radius * Vector2::new(dx, dy));
+                    }
+                },
+
+                Command::Close => {
+                    let first = current.first().cloned();
+                    finish_polyline(&mut polylines, &mut current, true);
+
+                    // Following commands continue from the start of the closed sub-path
+                    if let Some(first) = first {
+                        current.push(first);
+                    }
+                },
+            }
+        }
+
+        finish_polyline(&mut polylines, &mut current, false);
+
+        polylines
+    }
+
+
+    /// Returns triangles outlining the path
+    pub fn stroke(&self, style: &StrokeStyle, tolerance: f64) -> Triangles {
+        let mut triangles = Vec::new();
+        let half_width = 0.5 * style.width;
+
+        for polyline in self.flatten(tolerance) {
+            stroke_polyline(&polyline, style, half_width, tolerance, &mut triangles);
+        }
+
+        Triangles::TriangleList(triangles)
+    }
+}
+
+
+/// Moves the current points into a polyline, if there are enough of them
+fn finish_polyline(polylines: &mut Vec<Polyline>, current: &mut Vec<Vector2>, closed: bool) {
+    if current.len() >= 2 {
+        let mut points: Vec<Vector2> = Vec::with_capacity(current.len());
+
+        // Remove consecutive duplicates, they have no direction to stroke along
+        for point in current.drain(..) {
+            let duplicate = match points.last() {
+                Some(&last) => { let d = point - last; d.dot(d) == 0.0 },
+                None => false
+            };
+
+            if !duplicate {
+                points.push(point);
+            }
+        }
+
+        if closed && points.len() > 2 {
+            let d = points[0] - points[points.len() - 1];
+            if d.dot(d) == 0.0 {
+                points.pop();
+            }
+        }
+
+        if points.len() >= 2 {
+            polylines.push(Polyline { points, closed });
+        }
+    }
+
+    current.clear();
+}
+
+
+/// Returns the unit length direction from one point to another
+fn direction(from: Vector2, to: Vector2) -> Vector2 {
+    let d = to - from;
+    d / d.dot(d).sqrt()
+}
+
+
+/// Returns a vector rotated 90 degrees counter-clockwise
+fn perpendicular(v: Vector2) -> Vector2 {
+    Vector2::new(-v.y, v.x)
+}
+
+
+/// Adds a fan of triangles around a center, from one angle to another
+fn add_fan(triangles: &mut Vec<(Vector2, Vector2, Vector2)>, center: Vector2, radius: f64,
+           start: f64, sweep: f64, tolerance: f64) {
+    let segments = Tessellation::Tolerance(tolerance).arc_segments(radius, sweep);
+
+    let mut previous = center + radius * Vector2::new(start.cos(), start.sin());
+    for i in 1..segments + 1 {
+        let angle = start + sweep * i as f64 / segments as f64;
+        let next = center + radius * Vector2::new(angle.cos(), angle.sin());
+        triangles.push((center, previous, next));
+        previous = next;
+    }
+}
+
+
+fn stroke_polyline(polyline: &Polyline, style: &StrokeStyle, half_width: f64, tolerance: f64,
+                   triangles: &mut Vec<(Vector2, Vector2, Vector2)>) {
+    let points = &polyline.points;
+    let count = points.len();
+    let segments = if polyline.closed { count } else { count - 1 };
+
+    // The body of each segment
+    for i in 0..segments {
+        let a = points[i];
+        let b = points[(i + 1) % count];
+        let offset = half_width * perpendicular(direction(a, b));
+
+        triangles.push((a + offset, a - offset, b - offset));
+        triangles.push((b - offset, b + offset, a + offset));
+    }
+
+    // The joins between segments
+    let joins = if polyline.closed { 0..count } else { 1..count - 1 };
+    for i in joins {
+        let previous = points[(i + count - 1) % count];
+        let point = points[i];
+        let next = points[(i + 1) % count];
+
+        let d0 = direction(previous, point);
+        let d1 = direction(point, next);
+        let cross = d0.x * d1.y - d0.y * d1.x;
+
+        // The outside of the turn is on the right of a left turn, and vice versa
+        let side = if cross > 0.0 { -1.0 } else { 1.0 };
+        let n0 = side * perpendicular(d0);
+        let n1 = side * perpendicular(d1);
+        let outer0 = point + half_width * n0;
+        let outer1 = point + half_width * n1;
+
+        match style.join {
+            LineJoin::Bevel => triangles.push((point, outer0, outer1)),
+
+            LineJoin::Miter => {
+                let sum = n0 + n1;
+                let cos_half = (0.5 * sum.dot(sum)).sqrt();
+
+                if cos_half > 1e-9 && 1.0 / cos_half <= style.miter_limit {
+                    let miter = point + (half_width / cos_half) * (sum / sum.dot(sum).sqrt());
+                    triangles.push((point, outer0, miter));
+                    triangles.push((point, miter, outer1));
+                } else {
+                    triangles.push((point, outer0, outer1));
+                }
+            },
+
+            LineJoin::Round => {
+                let start = n0.y.atan2(n0.x);
+                let mut sweep = n1.y.atan2(n1.x) - start;
+                if sweep > PI { sweep -= 2.0 * PI; }
+                if sweep < -PI { sweep += 2.0 * PI; }
+
+                add_fan(triangles, point, half_width, start, sweep, tolerance);
+            },
+        }
+    }
+
+    // The caps at the ends of open polylines
+    if !polyline.closed {
+        let ends = [
+            (points[0], direction(points[1], points[0])),
+            (points[count - 1], direction(points[count - 2], points[count - 1])),
+        ];
+
+        for &(point, outward) in ends.iter() {
+            let offset = half_width * perpendicular(outward);
+
+            match style.cap {
+                LineCap::Butt => {},
+
+                LineCap::Square => {
+                    let extension = half_width * outward;
+                    triangles.push((point + offset, point - offset, point - offset + extension));
+                    triangles.push((point - offset + extension, point + offset + extension, point + offset));
+                },
+
+                LineCap::Round => {
+                    let start = offset.y.atan2(offset.x);
+                    add_fan(triangles, point, half_width, start, -PI, tolerance);
+                },
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::INFINITY;
+
+    const EPSILON: f64 = 1e-9;
+
+
+    fn vertices(triangles: Triangles) -> Vec<Vector2> {
+        match triangles {
+            Triangles::TriangleList(triangles) => {
+                triangles.into_iter().flat_map(|(a, b, c)| vec![a, b, c]).collect()
+            },
+            Triangles::IndexedTriangles(points, _) => points,
+        }
+    }
+
+
+    // Returns the left, right, bottom and top extents of some points
+    fn extents(points: &[Vector2]) -> (f64, f64, f64, f64) {
+        points.iter().fold(
+            (INFINITY, -INFINITY, INFINITY, -INFINITY),
+            |(left, right, bottom, top), p| (left.min(p.x), right.max(p.x), bottom.min(p.y), top.max(p.y))
+        )
+    }
+
+
+    fn has_vertex(points: &[Vector2], vertex: Vector2) -> bool {
+        points.iter().any(|&p| { let d = p - vertex; d.dot(d).sqrt() < 1e-6 })
+    }
+
+
+    fn distance_to_segment(point: Vector2, a: Vector2, b: Vector2) -> f64 {
+        let ab = b - a;
+        let t = ((point - a).dot(ab) / ab.dot(ab)).max(0.0).min(1.0);
+        let d = point - (a + t * ab);
+        d.dot(d).sqrt()
+    }
+
+
+    fn distance_to_polyline(point: Vector2, polyline: &Polyline) -> f64 {
+        polyline.points.windows(2)
+            .map(|pair| distance_to_segment(point, pair[0], pair[1]))
+            .fold(INFINITY, |min, distance| min.min(distance))
+    }
+
+
+    fn stroke_line(style: &StrokeStyle) -> Vec<Vector2> {
+        let mut path = Path::new();
+        path.move_to(Vector2::new(0.0, 0.0)).line_to(Vector2::new(2.0, 0.0));
+        vertices(path.stroke(style, 0.01))
+    }
+
+
+    #[test]
+    fn bezier_curves_stay_within_the_tolerance() {
+        let tolerance = 0.01;
+        let start = Vector2::new(0.0, 0.0);
+        let (first, second) = (Vector2::new(2.0, 8.0), Vector2::new(9.0, -6.0));
+        let end = Vector2::new(10.0, 0.0);
+
+        let mut quadratic = Path::new();
+        quadratic.move_to(start).quadratic_to(first, end);
+
+        let mut cubic = Path::new();
+        cubic.move_to(start).cubic_to(first, second, end);
+
+        let quadratic_lines = quadratic.flatten(tolerance);
+        let cubic_lines = cubic.flatten(tolerance);
+        assert_eq!(quadratic_lines.len(), 1);
+        assert_eq!(cubic_lines.len(), 1);
+
+        for i in 0..1001 {
+            let t = i as f64 / 1000.0;
+            let u = 1.0 - t;
+
+            let on_quadratic = u * u * start + 2.0 * u * t * first + t * t * end;
+            let on_cubic = u * u * u * start + 3.0 * u * u * t * first + 3.0 * u * t * t * second + t * t * t * end;
+
+            assert!(distance_to_polyline(on_quadratic, &quadratic_lines[0]) <= tolerance + EPSILON);
+            assert!(distance_to_polyline(on_cubic, &cubic_lines[0]) <= tolerance + EPSILON);
+        }
+    }
+
+
+    #[test]
+    fn closing_onto_the_start_drops_the_duplicate() {
+        let mut path = Path::new();
+        path.move_to(Vector2::new(0.0, 0.0))
+            .line_to(Vector2::new(1.0, 0.0))
+            .line_to(Vector2::new(1.0, 1.0))
+            .line_to(Vector2::new(0.0, 0.0))
+            .close();
+
+        let polylines = path.flatten(0.01);
+
+        assert_eq!(polylines.len(), 1);
+        assert!(polylines[0].closed);
+        assert_eq!(polylines[0].points.len(), 3);
+    }
+
+
+    #[test]
+    fn sharp_miters_fall_back_to_bevels() {
+        let mut style = StrokeStyle::new(0.2);
+
+        // A right angle needs a miter ratio of 1.41, within the default limit of 4
+        let mut corner = Path::new();
+        corner.move_to(Vector2::new(0.0, 0.0)).line_to(Vector2::new(1.0, 0.0)).line_to(Vector2::new(1.0, 1.0));
+
+        let mitered = vertices(corner.stroke(&style, 0.01));
+        assert_eq!(mitered.len(), 3 * 6);
+        assert!(has_vertex(&mitered, Vector2::new(1.1, -0.1)));
+
+        style.miter_limit = 1.2;
+        let beveled = vertices(corner.stroke(&style, 0.01));
+        assert_eq!(beveled.len(), 3 * 5);
+        assert!(!has_vertex(&beveled, Vector2::new(1.1, -0.1)));
+
+        // Turning almost all the way back would need a miter far longer than the limit
+        style.miter_limit = 4.0;
+        let mut spike = Path::new();
+        spike.move_to(Vector2::new(0.0, 0.0)).line_to(Vector2::new(10.0, 0.0)).line_to(Vector2::new(0.0, 1.0));
+
+        let points = vertices(spike.stroke(&style, 0.01));
+        let (_, right, _, _) = extents(&points);
+        assert_eq!(points.len(), 3 * 5);
+        assert!(right <= 10.1 + EPSILON, "the join reaches x = {}", right);
+    }
+
+
+    #[test]
+    fn caps_extend_the_expected_distance() {
+        let mut style = StrokeStyle::new(0.5);
+
+        style.cap = LineCap::Butt;
+        let (left, right, bottom, top) = extents(&stroke_line(&style));
+        assert!((left - 0.0).abs() < EPSILON && (right - 2.0).abs() < EPSILON);
+        assert!((bottom + 0.25).abs() < EPSILON && (top - 0.25).abs() < EPSILON);
+
+        style.cap = LineCap::Square;
+        let (left, right, bottom, top) = extents(&stroke_line(&style));
+        assert!((left + 0.25).abs() < EPSILON && (right - 2.25).abs() < EPSILON);
+        assert!((bottom + 0.25).abs() < EPSILON && (top - 0.25).abs() < EPSILON);
+
+        // Round caps stay on the circle, reaching its far side to within the tolerance
+        style.cap = LineCap::Round;
+        let (left, right, bottom, top) = extents(&stroke_line(&style));
+        assert!(left >= -0.25 - EPSILON && left <= -0.25 + 0.01, "left = {}", left);
+        assert!(right <= 2.25 + EPSILON && right >= 2.25 - 0.01, "right = {}", right);
+        assert!((bottom + 0.25).abs() < EPSILON && (top - 0.25).abs() < EPSILON);
+    }
+}
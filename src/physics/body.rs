@@ -0,0 +1,283 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use renderer::Tessellation;
+use collision::Collide;
+use collision::CollisionFilter;
+use shapes::Shape;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// Determines how a body moves
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum BodyType {
+    /// Never moves
+    Static,
+
+    /// Moves with its velocity but is not affected by forces or collisions
+    Kinematic,
+
+    /// Moves with its velocity, affected by gravity, forces and collisions
+    Dynamic,
+}
+
+
+/// Identifies a body in a world
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BodyHandle {
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+}
+
+
+/// A rigid object in a physics world
+pub struct Body {
+    // The shape relative to the center of mass, without rotation
+    shape: Box<Shape>,
+
+    body_type: BodyType,
+
+    /// The position of the center of mass
+    pub position: Vector2,
+
+    /// The counter-clockwise rotation in radians
+    pub angle: f64,
+
+    pub velocity: Vector2,
+
+    /// Counter-clockwise rotation speed in radians per second
+    pub angular_velocity: f64,
+
+    /// How bouncy the body is, 0 stops on impact and 1 bounces back with the same speed
+    pub restitution: f64,
+
+    /// How much the body resists sliding along other bodies
+    pub friction: f64,
+
+    /// How strongly gravity affects the body
+    pub gravity_scale: f64,
+
+    /// Which other bodies the body collides with
+    pub filter: CollisionFilter,
+
+    /// Sensors detect the bodies overlapping them without colliding with them
+    pub sensor: bool,
+
+    mass: f64,
+    inertia: f64,
+    pub(crate) inverse_mass: f64,
+    pub(crate) inverse_inertia: f64,
+
+    force: Vector2,
+    torque: f64,
+}
+
+
+impl Body {
+    /// Creates a body from a shape in world coordinates.
+    /// The position of the body becomes the shape's centroid.
+    pub fn new<S: Shape + 'static>(body_type: BodyType, mut shape: S, density: f64) -> Body {
+        let position = shape.centroid();
+        shape.translate(-position);
+
+        let mut body = Body {
+            shape: Box::new(shape),
+            body_type,
+
+            position,
+            angle: 0.0,
+
+            velocity: Vector2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+
+            restitution: 0.0,
+            friction: 0.5,
+            gravity_scale: 1.0,
+
+            filter: CollisionFilter::default(),
+            sensor: false,
+
+            mass: 0.0,
+            inertia: 0.0,
+            inverse_mass: 0.0,
+            inverse_inertia: 0.0,
+
+            force: Vector2::new(0.0, 0.0),
+            torque: 0.0,
+        };
+
+        body.set_density(density);
+        body
+    }
+
+
+    /// Recomputes the mass of the body from a density.
+    /// Shapes without area, such as segments, get the density as their mass.
+    pub fn set_density(&mut self, density: f64) {
+        let area = self.shape.area();
+        let mass = if area > 0.0 { area * density } else { density };
+
+        self.mass = mass;
+        self.inertia = self.shape.moment_of_inertia(mass);
+
+        if self.body_type == BodyType::Dynamic && mass > 0.0 {
+            self.inverse_mass = 1.0 / mass;
+            self.inverse_inertia = if self.inertia > 0.0 { 1.0 / self.inertia } else { 0.0 };
+        } else {
+            self.inverse_mass = 0.0;
+            self.inverse_inertia = 0.0;
+        }
+    }
+
+
+    pub fn body_type(&self) -> BodyType {
+        self.body_type
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    pub fn inertia(&self) -> f64 {
+        self.inertia
+    }
+
+
+    /// Returns the shape relative to the center of mass, before rotation
+    pub fn shape(&self) -> &Shape {
+        &*self.shape
+    }
+
+
+    /// Returns the transformation from the body's local space to world space
+    pub fn transform(&self) -> Affine2 {
+        Affine2::rigid(self.angle, self.position)
+    }
+
+
+    /// Converts a point relative to the body into world space
+    pub fn world_point(&self, local: Vector2) -> Vector2 {
+        self.transform().transform_point(local)
+    }
+
+    /// Converts a point in world space into one relative to the body
+    pub fn local_point(&self, world: Vector2) -> Vector2 {
+        self.transform().transpose_vector(world - self.position)
+    }
+
+
+    /// Rotates a direction relative to the body into world space
+    pub fn world_vector(&self, local: Vector2) -> Vector2 {
+        self.transform().transform_vector(local)
+    }
+
+    /// Rotates a direction in world space into one relative to the body
+    pub fn local_vector(&self, world: Vector2) -> Vector2 {
+        self.transform().transpose_vector(world)
+    }
+
+
+    /// Returns the velocity of a point attached to the body
+    pub fn velocity_at(&self, point: Vector2) -> Vector2 {
+        let r = point - self.position;
+        self.velocity + Vector2::new(-self.angular_velocity * r.y, self.angular_velocity * r.x)
+    }
+
+
+    /// Applies a force at the center of mass during the next step
+    pub fn apply_force(&mut self, force: Vector2) {
+        self.force += force;
+    }
+
+    /// Applies a force at a point in world space during the next step
+    pub fn apply_force_at(&mut self, force: Vector2, point: Vector2) {
+        let r = point - self.position;
+        self.force += force;
+        self.torque += r.x * force.y - r.y * force.x;
+    }
+
+    /// Applies a torque during the next step
+    pub fn apply_torque(&mut self, torque: f64) {
+        self.torque += torque;
+    }
+
+
+    /// Changes the velocity immediately by an impulse at a point in world space
+    pub fn apply_impulse(&mut self, impulse: Vector2, point: Vector2) {
+        let r = point - self.position;
+        self.velocity += impulse * self.inverse_mass;
+        self.angular_velocity += self.inverse_inertia * (r.x * impulse.y - r.y * impulse.x);
+    }
+
+
+    /// Applies gravity and forces to the velocity, then clears the forces
+    pub(crate) fn integrate_velocity(&mut self, gravity: Vector2, dt: f64) {
+        if self.body_type == BodyType::Dynamic {
+            self.velocity += (gravity * self.gravity_scale + self.force * self.inverse_mass) * dt;
+            self.angular_velocity += self.torque * self.inverse_inertia * dt;
+        }
+
+        self.force = Vector2::new(0.0, 0.0);
+        self.torque = 0.0;
+    }
+
+
+    /// Moves the body with its velocity
+    pub(crate) fn integrate_position(&mut self, dt: f64) {
+        if self.body_type != BodyType::Static {
+            self.position += self.velocity * dt;
+            self.angle += self.angular_velocity * dt;
+        }
+    }
+}
+
+
+/// The body's shape, in world space
+impl Triangulate for Body {
+    fn get_triangles(&self) -> Triangles {
+        transform_triangles(self.shape.get_triangles(), &self.transform())
+    }
+
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
+        transform_triangles(self.shape.tessellate(tessellation), &self.transform())
+    }
+}
+
+
+/// The body's shape, in world space
+impl Collide for Body {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        let transform = self.transform();
+        let local = self.shape.get_farthest_point(transform.transpose_vector(axis));
+
+        transform.transform_point(local)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle {
+            left: self.get_farthest_point(Vector2::new(-1.0, 0.0)).x,
+            right: self.get_farthest_point(Vector2::new(1.0, 0.0)).x,
+            top: self.get_farthest_point(Vector2::new(0.0, 1.0)).y,
+            bottom: self.get_farthest_point(Vector2::new(0.0, -1.0)).y,
+        }
+    }
+}
+
+
+fn transform_triangles(triangles: Triangles, transform: &Affine2) -> Triangles {
+    match triangles {
+        Triangles::TriangleList(list) => Triangles::TriangleList(
+            list.into_iter().map(|(a, b, c)| (
+                transform.transform_point(a),
+                transform.transform_point(b),
+                transform.transform_point(c)
+            )).collect()
+        ),
+
+        Triangles::IndexedTriangles(points, indices) => Triangles::IndexedTriangles(
+            points.into_iter().map(|p| transform.transform_point(p)).collect(),
+            indices
+        ),
+    }
+}
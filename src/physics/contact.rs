@@ -0,0 +1,151 @@
+use trap::Vector2;
+use collision::Manifold;
+use physics::Body;
+use physics::BodyHandle;
+use physics::BAUMGARTE;
+use physics::apply_impulses;
+use physics::relative_velocity;
+use physics::effective_mass;
+use physics::perpendicular;
+
+
+// How far bodies may overlap before they are pushed apart
+const SLOP: f64 = 0.005;
+
+// Slower impacts don't bounce, to let bodies come to rest
+const RESTITUTION_THRESHOLD: f64 = 1.0;
+
+
+/// The impulses applied at each point of a contact, kept between steps to warm start the solver
+#[derive(Clone, Debug, Default)]
+pub(crate) struct ContactImpulses {
+    pub normal: Vec<f64>,
+    pub tangent: Vec<f64>,
+}
+
+
+struct ConstraintPoint {
+    // From the centers of mass to the contact point
+    r_a: Vector2,
+    r_b: Vector2,
+
+    normal_mass: f64,
+    tangent_mass: f64,
+
+    // The separating velocity the solver aims for
+    bias: f64,
+
+    normal_impulse: f64,
+    tangent_impulse: f64,
+}
+
+
+/// Keeps two bodies from moving into each other
+pub(crate) struct ContactConstraint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    normal: Vector2,
+    friction: f64,
+    points: Vec<ConstraintPoint>,
+}
+
+
+impl ContactConstraint {
+    pub fn new(a: BodyHandle, b: BodyHandle, body_a: &Body, body_b: &Body,
+               manifold: &Manifold, impulses: Option<&ContactImpulses>, dt: f64) -> ContactConstraint {
+        let normal = manifold.normal;
+        let tangent = perpendicular(normal);
+        let restitution = body_a.restitution.max(body_b.restitution);
+
+        // Impulses can only be reused if the contact still has the same number of points
+        let impulses = impulses.and_then(|i| if i.normal.len() == manifold.points.len() { Some(i) } else { None });
+
+        let points = manifold.points.iter().enumerate().map(|(i, contact)| {
+            let point = (contact.on_a + contact.on_b) * 0.5;
+            let r_a = point - body_a.position;
+            let r_b = point - body_b.position;
+
+            let normal_mass = effective_mass(body_a, body_b, r_a, r_b, normal);
+            let tangent_mass = effective_mass(body_a, body_b, r_a, r_b, tangent);
+
+            let relative = body_b.velocity_at(point) - body_a.velocity_at(point);
+            let approach = relative.dot(normal);
+
+            let bounce = if approach < -RESTITUTION_THRESHOLD { -restitution * approach } else { 0.0 };
+            let correction = BAUMGARTE / dt * (contact.depth - SLOP).max(0.0);
+
+            let (normal_impulse, tangent_impulse) = match impulses {
+                Some(impulses) => (impulses.normal[i], impulses.tangent[i]),
+                None => (0.0, 0.0)
+            };
+
+            ConstraintPoint {
+                r_a,
+                r_b,
+                normal_mass,
+                tangent_mass,
+                bias: bounce.max(correction),
+                normal_impulse,
+                tangent_impulse,
+            }
+        }).collect();
+
+        ContactConstraint {
+            a,
+            b,
+            normal,
+            friction: (body_a.friction * body_b.friction).sqrt(),
+            points,
+        }
+    }
+
+
+    /// Applies the impulses from the previous step
+    pub fn warm_start(&self, body_a: &mut Body, body_b: &mut Body) {
+        let tangent = perpendicular(self.normal);
+
+        for point in self.points.iter() {
+            let impulse = self.normal * point.normal_impulse + tangent * point.tangent_impulse;
+            apply_impulses(body_a, body_b, point.r_a, point.r_b, impulse);
+        }
+    }
+
+
+    /// Moves the velocities of the bodies closer to satisfying the contact
+    pub fn solve(&mut self, body_a: &mut Body, body_b: &mut Body) {
+        let normal = self.normal;
+        let tangent = perpendicular(normal);
+
+        for point in self.points.iter_mut() {
+            // Friction is limited by the normal impulse, so it goes first using the previous iteration's
+            let relative = relative_velocity(body_a, body_b, point.r_a, point.r_b);
+            let mut lambda = -point.tangent_mass * relative.dot(tangent);
+
+            let limit = self.friction * point.normal_impulse;
+            let total = (point.tangent_impulse + lambda).max(-limit).min(limit);
+            lambda = total - point.tangent_impulse;
+            point.tangent_impulse = total;
+
+            apply_impulses(body_a, body_b, point.r_a, point.r_b, tangent * lambda);
+
+            // The bodies may only push each other apart
+            let relative = relative_velocity(body_a, body_b, point.r_a, point.r_b);
+            let mut lambda = point.normal_mass * (point.bias - relative.dot(normal));
+
+            let total = (point.normal_impulse + lambda).max(0.0);
+            lambda = total - point.normal_impulse;
+            point.normal_impulse = total;
+
+            apply_impulses(body_a, body_b, point.r_a, point.r_b, normal * lambda);
+        }
+    }
+
+
+    pub fn impulses(&self) -> ContactImpulses {
+        ContactImpulses {
+            normal: self.points.iter().map(|p| p.normal_impulse).collect(),
+            tangent: self.points.iter().map(|p| p.tangent_impulse).collect(),
+        }
+    }
+}
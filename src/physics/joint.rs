@@ -0,0 +1,1025 @@
+use trap::Vector2;
+use physics::Body;
+use physics::BodyHandle;
+use physics::World;
+use physics::BAUMGARTE;
+use physics::apply_impulses;
+use physics::relative_velocity;
+use physics::effective_mass;
+use physics::perpendicular;
+use shapes::cross;
+use physics::cross_scalar;
+
+use std::f64::consts::PI;
+
+
+/// Identifies a joint in a world
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct JointHandle {
+    pub(crate) index: usize,
+    pub(crate) generation: u32,
+}
+
+
+/// Drives a joint at a constant speed
+#[derive(Copy, Clone, Debug)]
+pub struct Motor {
+    /// Radians per second for rotating joints, units per second for sliding joints
+    pub speed: f64,
+
+    /// The largest torque for rotating joints, or force for sliding joints, the motor can use
+    pub max_force: f64,
+}
+
+
+/// Connects two bodies, or a body and a point
+pub enum Joint {
+    Distance(DistanceJoint),
+    Rope(RopeJoint),
+    Revolute(RevoluteJoint),
+    Prismatic(PrismaticJoint),
+    Weld(WeldJoint),
+    Mouse(MouseJoint),
+}
+
+
+impl Joint {
+    /// Returns the bodies connected by the joint, mouse joints only have one
+    pub fn bodies(&self) -> (BodyHandle, Option<BodyHandle>) {
+        match *self {
+            Joint::Distance(ref joint) => (joint.a, Some(joint.b)),
+            Joint::Rope(ref joint) => (joint.a, Some(joint.b)),
+            Joint::Revolute(ref joint) => (joint.a, Some(joint.b)),
+            Joint::Prismatic(ref joint) => (joint.a, Some(joint.b)),
+            Joint::Weld(ref joint) => (joint.a, Some(joint.b)),
+            Joint::Mouse(ref joint) => (joint.body, None),
+        }
+    }
+
+
+    /// Returns true if the connected bodies still collide with each other
+    pub fn collide_connected(&self) -> bool {
+        match *self {
+            Joint::Distance(ref joint) => joint.collide_connected,
+            Joint::Rope(ref joint) => joint.collide_connected,
+            Joint::Revolute(ref joint) => joint.collide_connected,
+            Joint::Prismatic(ref joint) => joint.collide_connected,
+            Joint::Weld(ref joint) => joint.collide_connected,
+            Joint::Mouse(_) => true,
+        }
+    }
+
+
+    fn constraint(&mut self) -> Option<&mut Constraint> {
+        match *self {
+            Joint::Distance(ref mut joint) => Some(joint),
+            Joint::Rope(ref mut joint) => Some(joint),
+            Joint::Revolute(ref mut joint) => Some(joint),
+            Joint::Prismatic(ref mut joint) => Some(joint),
+            Joint::Weld(ref mut joint) => Some(joint),
+            Joint::Mouse(_) => None,
+        }
+    }
+
+
+    pub(crate) fn prepare(&mut self, a: &Body, b: Option<&Body>, dt: f64) {
+        if let Joint::Mouse(ref mut joint) = *self {
+            return joint.prepare(a, dt);
+        }
+
+        if let (Some(constraint), Some(b)) = (self.constraint(), b) {
+            constraint.prepare(a, b, dt);
+        }
+    }
+
+
+    pub(crate) fn warm_start(&mut self, a: &mut Body, b: Option<&mut Body>) {
+        if let Joint::Mouse(ref mut joint) = *self {
+            return joint.warm_start(a);
+        }
+
+        if let (Some(constraint), Some(b)) = (self.constraint(), b) {
+            constraint.warm_start(a, b);
+        }
+    }
+
+
+    pub(crate) fn solve(&mut self, a: &mut Body, b: Option<&mut Body>) {
+        if let Joint::Mouse(ref mut joint) = *self {
+            return joint.solve(a);
+        }
+
+        if let (Some(constraint), Some(b)) = (self.constraint(), b) {
+            constraint.solve(a, b);
+        }
+    }
+}
+
+
+/// A joint between two bodies, solved once per step and then iteratively
+trait Constraint {
+    /// Computes everything that stays the same while solving a step
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64);
+
+    /// Applies the impulses from the previous step
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body);
+
+    /// Moves the velocities of the bodies closer to satisfying the joint
+    fn solve(&mut self, a: &mut Body, b: &mut Body);
+}
+
+
+/// Keeps two points on two bodies at a distance, optionally acting as a spring
+pub struct DistanceJoint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+
+    pub length: f64,
+
+    /// How many times per second the spring oscillates, 0 keeps the distance rigid
+    pub frequency: f64,
+
+    /// How fast the spring stops oscillating, 1 stops it without overshooting
+    pub damping_ratio: f64,
+
+    pub collide_connected: bool,
+
+    r_a: Vector2,
+    r_b: Vector2,
+    u: Vector2,
+    mass: f64,
+    gamma: f64,
+    bias: f64,
+    impulse: f64,
+}
+
+
+impl DistanceJoint {
+    /// Connects two bodies at two points in world space, keeping their current distance
+    pub fn new(world: &World, a: BodyHandle, b: BodyHandle, anchor_a: Vector2, anchor_b: Vector2) -> Option<DistanceJoint> {
+        let (body_a, body_b) = (world.body(a)?, world.body(b)?);
+        let length = (anchor_b - anchor_a).dot(anchor_b - anchor_a).sqrt();
+
+        Some(DistanceJoint {
+            a,
+            b,
+
+            local_anchor_a: body_a.local_point(anchor_a),
+            local_anchor_b: body_b.local_point(anchor_b),
+
+            length,
+            frequency: 0.0,
+            damping_ratio: 0.0,
+
+            collide_connected: false,
+
+            r_a: Vector2::new(0.0, 0.0),
+            r_b: Vector2::new(0.0, 0.0),
+            u: Vector2::new(0.0, 0.0),
+            mass: 0.0,
+            gamma: 0.0,
+            bias: 0.0,
+            impulse: 0.0,
+        })
+    }
+}
+
+
+impl Constraint for DistanceJoint {
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64) {
+        self.r_a = a.world_vector(self.local_anchor_a);
+        self.r_b = b.world_vector(self.local_anchor_b);
+
+        let (u, length) = direction(b.position + self.r_b - a.position - self.r_a);
+        self.u = u;
+
+        let error = length - self.length;
+        let mass = effective_mass(a, b, self.r_a, self.r_b, u);
+
+        if self.frequency > 0.0 && mass > 0.0 {
+            let (gamma, beta) = soft(mass, self.frequency, self.damping_ratio, dt);
+
+            self.gamma = gamma;
+            self.bias = error * beta;
+            self.mass = 1.0 / (1.0 / mass + gamma);
+        } else {
+            self.gamma = 0.0;
+            self.bias = BAUMGARTE / dt * error;
+            self.mass = mass;
+        }
+    }
+
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body) {
+        apply_impulses(a, b, self.r_a, self.r_b, self.u * self.impulse);
+    }
+
+    fn solve(&mut self, a: &mut Body, b: &mut Body) {
+        let velocity = self.u.dot(relative_velocity(a, b, self.r_a, self.r_b));
+        let lambda = -self.mass * (velocity + self.bias + self.gamma * self.impulse);
+
+        self.impulse += lambda;
+        apply_impulses(a, b, self.r_a, self.r_b, self.u * lambda);
+    }
+}
+
+
+/// Keeps two points on two bodies from moving further apart than a length
+pub struct RopeJoint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+
+    pub max_length: f64,
+
+    pub collide_connected: bool,
+
+    r_a: Vector2,
+    r_b: Vector2,
+    u: Vector2,
+    mass: f64,
+    bias: f64,
+    impulse: f64,
+}
+
+
+impl RopeJoint {
+    /// Connects two bodies at two points in world space, with their current distance as the length of the rope
+    pub fn new(world: &World, a: BodyHandle, b: BodyHandle, anchor_a: Vector2, anchor_b: Vector2) -> Option<RopeJoint> {
+        let (body_a, body_b) = (world.body(a)?, world.body(b)?);
+        let max_length = (anchor_b - anchor_a).dot(anchor_b - anchor_a).sqrt();
+
+        Some(RopeJoint {
+            a,
+            b,
+
+            local_anchor_a: body_a.local_point(anchor_a),
+            local_anchor_b: body_b.local_point(anchor_b),
+
+            max_length,
+
+            collide_connected: true,
+
+            r_a: Vector2::new(0.0, 0.0),
+            r_b: Vector2::new(0.0, 0.0),
+            u: Vector2::new(0.0, 0.0),
+            mass: 0.0,
+            bias: 0.0,
+            impulse: 0.0,
+        })
+    }
+}
+
+
+impl Constraint for RopeJoint {
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64) {
+        self.r_a = a.world_vector(self.local_anchor_a);
+        self.r_b = b.world_vector(self.local_anchor_b);
+
+        let (u, length) = direction(b.position + self.r_b - a.position - self.r_a);
+        self.u = u;
+        self.mass = effective_mass(a, b, self.r_a, self.r_b, u);
+
+        self.bias = limit_bias(self.max_length - length, dt);
+    }
+
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body) {
+        apply_impulses(a, b, self.r_a, self.r_b, self.u * -self.impulse);
+    }
+
+    fn solve(&mut self, a: &mut Body, b: &mut Body) {
+        // The rope can only pull the bodies together
+        let velocity = -self.u.dot(relative_velocity(a, b, self.r_a, self.r_b));
+        let lambda = accumulate(&mut self.impulse, -self.mass * (velocity + self.bias));
+
+        apply_impulses(a, b, self.r_a, self.r_b, self.u * -lambda);
+    }
+}
+
+
+/// Pins two bodies together at a point they rotate around
+pub struct RevoluteJoint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+
+    /// The angle between the bodies when the joint was created
+    pub reference_angle: f64,
+
+    /// The lowest and highest angle the bodies may rotate to, relative to the reference angle
+    pub limits: Option<(f64, f64)>,
+
+    pub motor: Option<Motor>,
+
+    pub collide_connected: bool,
+
+    r_a: Vector2,
+    r_b: Vector2,
+    k: [[f64; 2]; 2],
+    angular_mass: f64,
+    angle: f64,
+    bias: Vector2,
+    dt: f64,
+
+    impulse: Vector2,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+}
+
+
+impl RevoluteJoint {
+    /// Connects two bodies at a point in world space
+    pub fn new(world: &World, a: BodyHandle, b: BodyHandle, anchor: Vector2) -> Option<RevoluteJoint> {
+        let (body_a, body_b) = (world.body(a)?, world.body(b)?);
+
+        Some(RevoluteJoint {
+            a,
+            b,
+
+            local_anchor_a: body_a.local_point(anchor),
+            local_anchor_b: body_b.local_point(anchor),
+
+            reference_angle: body_b.angle - body_a.angle,
+
+            limits: None,
+            motor: None,
+
+            collide_connected: false,
+
+            r_a: Vector2::new(0.0, 0.0),
+            r_b: Vector2::new(0.0, 0.0),
+            k: [[0.0; 2]; 2],
+            angular_mass: 0.0,
+            angle: 0.0,
+            bias: Vector2::new(0.0, 0.0),
+            dt: 0.0,
+
+            impulse: Vector2::new(0.0, 0.0),
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+        })
+    }
+
+
+    /// Returns the current angle between the bodies, relative to the reference angle
+    pub fn angle(&self, world: &World) -> Option<f64> {
+        Some(world.body(self.b)?.angle - world.body(self.a)?.angle - self.reference_angle)
+    }
+}
+
+
+impl Constraint for RevoluteJoint {
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64) {
+        self.r_a = a.world_vector(self.local_anchor_a);
+        self.r_b = b.world_vector(self.local_anchor_b);
+
+        self.k = point_mass(a, b, self.r_a, self.r_b);
+        self.angular_mass = angular_mass(a, b);
+
+        self.angle = b.angle - a.angle - self.reference_angle;
+        self.bias = (b.position + self.r_b - a.position - self.r_a) * (BAUMGARTE / dt);
+        self.dt = dt;
+
+        if self.motor.is_none() {
+            self.motor_impulse = 0.0;
+        }
+
+        if self.limits.is_none() {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+    }
+
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body) {
+        apply_impulses(a, b, self.r_a, self.r_b, self.impulse);
+        apply_angular(a, b, self.motor_impulse + self.lower_impulse - self.upper_impulse);
+    }
+
+    fn solve(&mut self, a: &mut Body, b: &mut Body) {
+        if let Some(motor) = self.motor {
+            let velocity = b.angular_velocity - a.angular_velocity - motor.speed;
+            let max = motor.max_force * self.dt;
+
+            let lambda = clamp_accumulate(&mut self.motor_impulse, -self.angular_mass * velocity, max);
+            apply_angular(a, b, lambda);
+        }
+
+        if let Some((lower, upper)) = self.limits {
+            let velocity = b.angular_velocity - a.angular_velocity;
+            let bias = limit_bias(self.angle - lower, self.dt);
+            let lambda = accumulate(&mut self.lower_impulse, -self.angular_mass * (velocity + bias));
+            apply_angular(a, b, lambda);
+
+            let velocity = a.angular_velocity - b.angular_velocity;
+            let bias = limit_bias(upper - self.angle, self.dt);
+            let lambda = accumulate(&mut self.upper_impulse, -self.angular_mass * (velocity + bias));
+            apply_angular(a, b, -lambda);
+        }
+
+        let velocity = relative_velocity(a, b, self.r_a, self.r_b);
+        let lambda = solve(self.k, -(velocity + self.bias));
+
+        self.impulse += lambda;
+        apply_impulses(a, b, self.r_a, self.r_b, lambda);
+    }
+}
+
+
+/// Lets two bodies slide along an axis without rotating relative to each other
+pub struct PrismaticJoint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+
+    /// The direction the bodies slide along, relative to the first body
+    pub local_axis: Vector2,
+
+    /// The angle between the bodies when the joint was created
+    pub reference_angle: f64,
+
+    /// The lowest and highest distance along the axis the bodies may slide to
+    pub limits: Option<(f64, f64)>,
+
+    pub motor: Option<Motor>,
+
+    pub collide_connected: bool,
+
+    axis: Vector2,
+    perpendicular: Vector2,
+
+    // How much each body rotates for a movement along the axis and perpendicular to it
+    arms_axis: (f64, f64),
+    arms_perpendicular: (f64, f64),
+
+    axial_mass: f64,
+    perpendicular_mass: f64,
+    angular_mass: f64,
+
+    translation: f64,
+    perpendicular_bias: f64,
+    angular_bias: f64,
+    dt: f64,
+
+    perpendicular_impulse: f64,
+    angular_impulse: f64,
+    motor_impulse: f64,
+    lower_impulse: f64,
+    upper_impulse: f64,
+}
+
+
+impl PrismaticJoint {
+    /// Connects two bodies at a point in world space, sliding along an axis in world space
+    pub fn new(world: &World, a: BodyHandle, b: BodyHandle, anchor: Vector2, axis: Vector2) -> Option<PrismaticJoint> {
+        let (body_a, body_b) = (world.body(a)?, world.body(b)?);
+        let (local_axis, _) = direction(body_a.local_vector(axis));
+
+        Some(PrismaticJoint {
+            a,
+            b,
+
+            local_anchor_a: body_a.local_point(anchor),
+            local_anchor_b: body_b.local_point(anchor),
+
+            local_axis,
+
+            reference_angle: body_b.angle - body_a.angle,
+
+            limits: None,
+            motor: None,
+
+            collide_connected: false,
+
+            axis: Vector2::new(0.0, 0.0),
+            perpendicular: Vector2::new(0.0, 0.0),
+
+            arms_axis: (0.0, 0.0),
+            arms_perpendicular: (0.0, 0.0),
+
+            axial_mass: 0.0,
+            perpendicular_mass: 0.0,
+            angular_mass: 0.0,
+
+            translation: 0.0,
+            perpendicular_bias: 0.0,
+            angular_bias: 0.0,
+            dt: 0.0,
+
+            perpendicular_impulse: 0.0,
+            angular_impulse: 0.0,
+            motor_impulse: 0.0,
+            lower_impulse: 0.0,
+            upper_impulse: 0.0,
+        })
+    }
+
+
+    /// Returns how far the bodies have slid along the axis
+    pub fn translation(&self, world: &World) -> Option<f64> {
+        let (a, b) = (world.body(self.a)?, world.body(self.b)?);
+        let d = b.world_point(self.local_anchor_b) - a.world_point(self.local_anchor_a);
+
+        Some(a.world_vector(self.local_axis).dot(d))
+    }
+}
+
+
+impl Constraint for PrismaticJoint {
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64) {
+        let r_a = a.world_vector(self.local_anchor_a);
+        let r_b = b.world_vector(self.local_anchor_b);
+        let d = b.position + r_b - a.position - r_a;
+
+        self.axis = a.world_vector(self.local_axis);
+        self.perpendicular = perpendicular(self.axis);
+
+        // The first body's anchor is wherever the second one is along the axis
+        self.arms_axis = (cross(d + r_a, self.axis), cross(r_b, self.axis));
+        self.arms_perpendicular = (cross(d + r_a, self.perpendicular), cross(r_b, self.perpendicular));
+
+        self.axial_mass = axial_mass(a, b, self.arms_axis);
+        self.perpendicular_mass = axial_mass(a, b, self.arms_perpendicular);
+        self.angular_mass = angular_mass(a, b);
+
+        self.translation = self.axis.dot(d);
+        self.perpendicular_bias = BAUMGARTE / dt * self.perpendicular.dot(d);
+        self.angular_bias = BAUMGARTE / dt * (b.angle - a.angle - self.reference_angle);
+        self.dt = dt;
+
+        if self.motor.is_none() {
+            self.motor_impulse = 0.0;
+        }
+
+        if self.limits.is_none() {
+            self.lower_impulse = 0.0;
+            self.upper_impulse = 0.0;
+        }
+    }
+
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body) {
+        let along = self.motor_impulse + self.lower_impulse - self.upper_impulse;
+
+        apply_axial(a, b, self.axis, self.arms_axis, along);
+        apply_axial(a, b, self.perpendicular, self.arms_perpendicular, self.perpendicular_impulse);
+        apply_angular(a, b, self.angular_impulse);
+    }
+
+    fn solve(&mut self, a: &mut Body, b: &mut Body) {
+        if let Some(motor) = self.motor {
+            let velocity = axial_velocity(a, b, self.axis, self.arms_axis) - motor.speed;
+            let max = motor.max_force * self.dt;
+
+            let lambda = clamp_accumulate(&mut self.motor_impulse, -self.axial_mass * velocity, max);
+            apply_axial(a, b, self.axis, self.arms_axis, lambda);
+        }
+
+        if let Some((lower, upper)) = self.limits {
+            let velocity = axial_velocity(a, b, self.axis, self.arms_axis);
+            let bias = limit_bias(self.translation - lower, self.dt);
+            let lambda = accumulate(&mut self.lower_impulse, -self.axial_mass * (velocity + bias));
+            apply_axial(a, b, self.axis, self.arms_axis, lambda);
+
+            let velocity = -axial_velocity(a, b, self.axis, self.arms_axis);
+            let bias = limit_bias(upper - self.translation, self.dt);
+            let lambda = accumulate(&mut self.upper_impulse, -self.axial_mass * (velocity + bias));
+            apply_axial(a, b, self.axis, self.arms_axis, -lambda);
+        }
+
+        let velocity = axial_velocity(a, b, self.perpendicular, self.arms_perpendicular);
+        let lambda = -self.perpendicular_mass * (velocity + self.perpendicular_bias);
+        self.perpendicular_impulse += lambda;
+        apply_axial(a, b, self.perpendicular, self.arms_perpendicular, lambda);
+
+        let velocity = b.angular_velocity - a.angular_velocity;
+        let lambda = -self.angular_mass * (velocity + self.angular_bias);
+        self.angular_impulse += lambda;
+        apply_angular(a, b, lambda);
+    }
+}
+
+
+/// Glues two bodies together
+pub struct WeldJoint {
+    pub a: BodyHandle,
+    pub b: BodyHandle,
+
+    pub local_anchor_a: Vector2,
+    pub local_anchor_b: Vector2,
+
+    /// The angle between the bodies when the joint was created
+    pub reference_angle: f64,
+
+    pub collide_connected: bool,
+
+    r_a: Vector2,
+    r_b: Vector2,
+    k: [[f64; 2]; 2],
+    angular_mass: f64,
+    bias: Vector2,
+    angular_bias: f64,
+
+    impulse: Vector2,
+    angular_impulse: f64,
+}
+
+
+impl WeldJoint {
+    /// Connects two bodies at a point in world space
+    pub fn new(world: &World, a: BodyHandle, b: BodyHandle, anchor: Vector2) -> Option<WeldJoint> {
+        let (body_a, body_b) = (world.body(a)?, world.body(b)?);
+
+        Some(WeldJoint {
+            a,
+            b,
+
+            local_anchor_a: body_a.local_point(anchor),
+            local_anchor_b: body_b.local_point(anchor),
+
+            reference_angle: body_b.angle - body_a.angle,
+
+            collide_connected: false,
+
+            r_a: Vector2::new(0.0, 0.0),
+            r_b: Vector2::new(0.0, 0.0),
+            k: [[0.0; 2]; 2],
+            angular_mass: 0.0,
+            bias: Vector2::new(0.0, 0.0),
+            angular_bias: 0.0,
+
+            impulse: Vector2::new(0.0, 0.0),
+            angular_impulse: 0.0,
+        })
+    }
+}
+
+
+impl Constraint for WeldJoint {
+    fn prepare(&mut self, a: &Body, b: &Body, dt: f64) {
+        self.r_a = a.world_vector(self.local_anchor_a);
+        self.r_b = b.world_vector(self.local_anchor_b);
+
+        self.k = point_mass(a, b, self.r_a, self.r_b);
+        self.angular_mass = angular_mass(a, b);
+
+        self.bias = (b.position + self.r_b - a.position - self.r_a) * (BAUMGARTE / dt);
+        self.angular_bias = BAUMGARTE / dt * (b.angle - a.angle - self.reference_angle);
+    }
+
+    fn warm_start(&mut self, a: &mut Body, b: &mut Body) {
+        apply_impulses(a, b, self.r_a, self.r_b, self.impulse);
+        apply_angular(a, b, self.angular_impulse);
+    }
+
+    fn solve(&mut self, a: &mut Body, b: &mut Body) {
+        let velocity = b.angular_velocity - a.angular_velocity;
+        let lambda = -self.angular_mass * (velocity + self.angular_bias);
+        self.angular_impulse += lambda;
+        apply_angular(a, b, lambda);
+
+        let velocity = relative_velocity(a, b, self.r_a, self.r_b);
+        let lambda = solve(self.k, -(velocity + self.bias));
+        self.impulse += lambda;
+        apply_impulses(a, b, self.r_a, self.r_b, lambda);
+    }
+}
+
+
+/// Pulls a point on a body toward a target with a limited force, for dragging bodies around
+pub struct MouseJoint {
+    pub body: BodyHandle,
+
+    pub local_anchor: Vector2,
+
+    /// The point in world space the body is pulled toward
+    pub target: Vector2,
+
+    pub max_force: f64,
+
+    /// How many times per second the body oscillates around the target
+    pub frequency: f64,
+
+    /// How fast the body stops oscillating, 1 stops it without overshooting
+    pub damping_ratio: f64,
+
+    r: Vector2,
+    k: [[f64; 2]; 2],
+    gamma: f64,
+    bias: Vector2,
+    dt: f64,
+
+    impulse: Vector2,
+}
+
+
+impl MouseJoint {
+    /// Grabs a body at a point in world space.
+    /// The force is enough to lift a thousand times the body's weight at a gravity of 1.
+    pub fn new(world: &World, body: BodyHandle, target: Vector2) -> Option<MouseJoint> {
+        let grabbed = world.body(body)?;
+
+        Some(MouseJoint {
+            body,
+
+            local_anchor: grabbed.local_point(target),
+            target,
+
+            max_force: 1000.0 * grabbed.mass(),
+            frequency: 5.0,
+            damping_ratio: 0.7,
+
+            r: Vector2::new(0.0, 0.0),
+            k: [[0.0; 2]; 2],
+            gamma: 0.0,
+            bias: Vector2::new(0.0, 0.0),
+            dt: 0.0,
+
+            impulse: Vector2::new(0.0, 0.0),
+        })
+    }
+
+
+    fn prepare(&mut self, body: &Body, dt: f64) {
+        self.r = body.world_vector(self.local_anchor);
+        self.dt = dt;
+
+        let (gamma, beta) = if body.inverse_mass > 0.0 {
+            soft(body.mass(), self.frequency, self.damping_ratio, dt)
+        } else {
+            (0.0, 0.0)
+        };
+
+        self.gamma = gamma;
+        self.bias = (body.position + self.r - self.target) * beta;
+
+        let (m, i, r) = (body.inverse_mass, body.inverse_inertia, self.r);
+        self.k = [
+            [m + i * r.y * r.y + gamma, -i * r.x * r.y],
+            [-i * r.x * r.y, m + i * r.x * r.x + gamma],
+        ];
+    }
+
+    fn warm_start(&mut self, body: &mut Body) {
+        body.velocity += self.impulse * body.inverse_mass;
+        body.angular_velocity += body.inverse_inertia * cross(self.r, self.impulse);
+    }
+
+    fn solve(&mut self, body: &mut Body) {
+        let velocity = body.velocity + cross_scalar(body.angular_velocity, self.r);
+        let lambda = solve(self.k, -(velocity + self.bias + self.impulse * self.gamma));
+
+        let previous = self.impulse;
+        self.impulse += lambda;
+
+        let max = self.max_force * self.dt;
+        let length = self.impulse.dot(self.impulse).sqrt();
+        if length > max {
+            self.impulse = self.impulse * (max / length);
+        }
+
+        let lambda = self.impulse - previous;
+        body.velocity += lambda * body.inverse_mass;
+        body.angular_velocity += body.inverse_inertia * cross(self.r, lambda);
+    }
+}
+
+
+impl From<DistanceJoint> for Joint {
+    fn from(joint: DistanceJoint) -> Joint {
+        Joint::Distance(joint)
+    }
+}
+
+impl From<RopeJoint> for Joint {
+    fn from(joint: RopeJoint) -> Joint {
+        Joint::Rope(joint)
+    }
+}
+
+impl From<RevoluteJoint> for Joint {
+    fn from(joint: RevoluteJoint) -> Joint {
+        Joint::Revolute(joint)
+    }
+}
+
+impl From<PrismaticJoint> for Joint {
+    fn from(joint: PrismaticJoint) -> Joint {
+        Joint::Prismatic(joint)
+    }
+}
+
+impl From<WeldJoint> for Joint {
+    fn from(joint: WeldJoint) -> Joint {
+        Joint::Weld(joint)
+    }
+}
+
+impl From<MouseJoint> for Joint {
+    fn from(joint: MouseJoint) -> Joint {
+        Joint::Mouse(joint)
+    }
+}
+
+
+/// Returns a vector scaled to a length of one along with its original length
+fn direction(v: Vector2) -> (Vector2, f64) {
+    let length = v.dot(v).sqrt();
+
+    if length > 0.0 {
+        (v / length, length)
+    } else {
+        (Vector2::new(0.0, 0.0), 0.0)
+    }
+}
+
+
+/// Returns the softness and position correction factor of a spring with a mass
+fn soft(mass: f64, frequency: f64, damping_ratio: f64, dt: f64) -> (f64, f64) {
+    let omega = 2.0 * PI * frequency;
+    let damping = 2.0 * mass * damping_ratio * omega;
+    let stiffness = mass * omega * omega;
+
+    let gamma = dt * (damping + dt * stiffness);
+    let gamma = if gamma > 0.0 { 1.0 / gamma } else { 0.0 };
+
+    (gamma, dt * stiffness * gamma)
+}
+
+
+/// The bias of a one-sided limit, a positive distance to the limit may still be closed within a step
+fn limit_bias(distance: f64, dt: f64) -> f64 {
+    if distance > 0.0 {
+        distance / dt
+    } else {
+        BAUMGARTE / dt * distance
+    }
+}
+
+
+/// Adds to an impulse that may only push, returning the change that was applied
+fn accumulate(total: &mut f64, lambda: f64) -> f64 {
+    let previous = *total;
+    *total = (previous + lambda).max(0.0);
+    *total - previous
+}
+
+
+/// Adds to an impulse limited in both directions, returning the change that was applied
+fn clamp_accumulate(total: &mut f64, lambda: f64, max: f64) -> f64 {
+    let previous = *total;
+    *total = (previous + lambda).max(-max).min(max);
+    *total - previous
+}
+
+
+/// The matrix relating an impulse at a point to the relative velocity it causes
+fn point_mass(a: &Body, b: &Body, r_a: Vector2, r_b: Vector2) -> [[f64; 2]; 2] {
+    let m = a.inverse_mass + b.inverse_mass;
+    let (i_a, i_b) = (a.inverse_inertia, b.inverse_inertia);
+
+    let xy = -i_a * r_a.x * r_a.y - i_b * r_b.x * r_b.y;
+
+    [
+        [m + i_a * r_a.y * r_a.y + i_b * r_b.y * r_b.y, xy],
+        [xy, m + i_a * r_a.x * r_a.x + i_b * r_b.x * r_b.x],
+    ]
+}
+
+
+/// Solves a 2x2 system of equations, returning zero if it has no single solution
+fn solve(k: [[f64; 2]; 2], b: Vector2) -> Vector2 {
+    let determinant = k[0][0] * k[1][1] - k[0][1] * k[1][0];
+
+    if determinant == 0.0 {
+        return Vector2::new(0.0, 0.0);
+    }
+
+    Vector2::new(
+        (k[1][1] * b.x - k[0][1] * b.y) / determinant,
+        (k[0][0] * b.y - k[1][0] * b.x) / determinant
+    )
+}
+
+
+fn angular_mass(a: &Body, b: &Body) -> f64 {
+    let k = a.inverse_inertia + b.inverse_inertia;
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+
+fn apply_angular(a: &mut Body, b: &mut Body, impulse: f64) {
+    a.angular_velocity -= a.inverse_inertia * impulse;
+    b.angular_velocity += b.inverse_inertia * impulse;
+}
+
+
+fn axial_mass(a: &Body, b: &Body, (arm_a, arm_b): (f64, f64)) -> f64 {
+    let k = a.inverse_mass + b.inverse_mass + a.inverse_inertia * arm_a * arm_a + b.inverse_inertia * arm_b * arm_b;
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+
+/// The relative velocity along a direction, where each body rotates by its arm
+fn axial_velocity(a: &Body, b: &Body, direction: Vector2, (arm_a, arm_b): (f64, f64)) -> f64 {
+    direction.dot(b.velocity - a.velocity) + arm_b * b.angular_velocity - arm_a * a.angular_velocity
+}
+
+
+fn apply_axial(a: &mut Body, b: &mut Body, direction: Vector2, (arm_a, arm_b): (f64, f64), impulse: f64) {
+    a.velocity = a.velocity - direction * (impulse * a.inverse_mass);
+    a.angular_velocity -= a.inverse_inertia * arm_a * impulse;
+
+    b.velocity += direction * (impulse * b.inverse_mass);
+    b.angular_velocity += b.inverse_inertia * arm_b * impulse;
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use physics::BodyType;
+    use shapes::Circle;
+
+    const DT: f64 = 1.0 / 60.0;
+
+
+    fn distance(world: &World, a: BodyHandle, b: BodyHandle) -> f64 {
+        let offset = world.body(b).unwrap().position - world.body(a).unwrap().position;
+        offset.dot(offset).sqrt()
+    }
+
+
+    // A fixed anchor and a ball hanging sideways from it, so gravity swings it down
+    fn pendulum() -> (World, BodyHandle, BodyHandle) {
+        let mut world = World::new(Vector2::new(0.0, -10.0));
+
+        let anchor = world.add_body(Body::new(BodyType::Static, Circle::new(Vector2::new(0.0, 0.0), 0.1), 1.0));
+        let ball = world.add_body(Body::new(BodyType::Dynamic, Circle::new(Vector2::new(2.0, 0.0), 0.25), 1.0));
+
+        (world, anchor, ball)
+    }
+
+
+    #[test]
+    fn distance_joints_keep_their_length() {
+        let (mut world, anchor, ball) = pendulum();
+
+        let joint = DistanceJoint::new(&world, anchor, ball, Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0)).unwrap();
+        assert!((joint.length - 2.0).abs() < 1e-9);
+        world.add_joint(joint);
+
+        for _ in 0..300 {
+            world.step(DT);
+
+            let length = distance(&world, anchor, ball);
+            assert!((length - 2.0).abs() < 0.05, "length drifted to {}", length);
+        }
+
+        // The ball swung down instead of staying where it started
+        assert!(world.body(ball).unwrap().position.y < -0.5);
+    }
+
+
+    #[test]
+    fn rope_joints_never_stretch() {
+        let (mut world, anchor, ball) = pendulum();
+
+        let joint = RopeJoint::new(&world, anchor, ball, Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0)).unwrap();
+        world.add_joint(joint);
+
+        // Thrown away from the anchor, the rope has to catch it
+        world.body_mut(ball).unwrap().velocity = Vector2::new(3.0, 0.0);
+
+        for _ in 0..300 {
+            world.step(DT);
+
+            let length = distance(&world, anchor, ball);
+            assert!(length < 2.0 + 0.05, "stretched to {}", length);
+        }
+    }
+
+
+    #[test]
+    fn ropes_are_slack_when_shorter() {
+        let (mut world, anchor, ball) = pendulum();
+
+        let joint = RopeJoint::new(&world, anchor, ball, Vector2::new(0.0, 0.0), Vector2::new(2.0, 0.0)).unwrap();
+        world.add_joint(joint);
+
+        // Moving toward the anchor isn't resisted
+        world.body_mut(ball).unwrap().velocity = Vector2::new(-3.0, 0.0);
+        world.gravity = Vector2::new(0.0, 0.0);
+        world.step(DT);
+
+        assert!((world.body(ball).unwrap().velocity.x + 3.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,67 @@
+use trap::Vector2;
+use shapes::cross;
+
+
+mod body;
+pub use self::body::Body;
+pub use self::body::BodyType;
+pub use self::body::BodyHandle;
+
+mod contact;
+
+mod joint;
+pub use self::joint::Joint;
+pub use self::joint::JointHandle;
+pub use self::joint::Motor;
+pub use self::joint::DistanceJoint;
+pub use self::joint::RopeJoint;
+pub use self::joint::RevoluteJoint;
+pub use self::joint::PrismaticJoint;
+pub use self::joint::WeldJoint;
+pub use self::joint::MouseJoint;
+
+mod world;
+pub use self::world::World;
+pub use self::world::ContactListener;
+
+
+// How much of a position error is corrected each step
+pub(crate) const BAUMGARTE: f64 = 0.2;
+
+
+/// Returns the velocity of the second body relative to the first at a point, given from each center of mass
+pub(crate) fn relative_velocity(a: &Body, b: &Body, r_a: Vector2, r_b: Vector2) -> Vector2 {
+    b.velocity + cross_scalar(b.angular_velocity, r_b) - a.velocity - cross_scalar(a.angular_velocity, r_a)
+}
+
+
+/// Pushes the second body along an impulse and the first in the opposite direction
+pub(crate) fn apply_impulses(a: &mut Body, b: &mut Body, r_a: Vector2, r_b: Vector2, impulse: Vector2) {
+    a.velocity = a.velocity - impulse * a.inverse_mass;
+    a.angular_velocity -= a.inverse_inertia * cross(r_a, impulse);
+
+    b.velocity += impulse * b.inverse_mass;
+    b.angular_velocity += b.inverse_inertia * cross(r_b, impulse);
+}
+
+
+/// Returns the inverse of how much the relative velocity along a direction changes per unit impulse
+pub(crate) fn effective_mass(a: &Body, b: &Body, r_a: Vector2, r_b: Vector2, direction: Vector2) -> f64 {
+    let ra = cross(r_a, direction);
+    let rb = cross(r_b, direction);
+
+    let k = a.inverse_mass + b.inverse_mass + a.inverse_inertia * ra * ra + b.inverse_inertia * rb * rb;
+
+    if k > 0.0 { 1.0 / k } else { 0.0 }
+}
+
+
+pub(crate) fn perpendicular(v: Vector2) -> Vector2 {
+    Vector2::new(-v.y, v.x)
+}
+
+
+/// The cross product of an angular velocity and a vector
+pub(crate) fn cross_scalar(w: f64, v: Vector2) -> Vector2 {
+    Vector2::new(-w * v.y, w * v.x)
+}
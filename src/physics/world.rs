@@ -0,0 +1,671 @@
+use trap::Vector2;
+use collision::BroadPhase;
+use collision::ProxyId;
+use collision::Manifold;
+use collision::manifold;
+use collision::intersect;
+use collision::SensorEvent;
+use collision::sensor_events;
+use physics::Body;
+use physics::BodyHandle;
+use physics::Joint;
+use physics::JointHandle;
+use physics::contact::ContactConstraint;
+use physics::contact::ContactImpulses;
+use timestep::FixedTimestep;
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+
+/// Receives collision events from a world
+pub trait ContactListener {
+    // Called when two bodies start touching
+    #[allow(unused_variables)]
+    fn begin_contact(&mut self, a: BodyHandle, b: BodyHandle, manifold: &Manifold) {}
+
+    // Called when two bodies stop touching
+    #[allow(unused_variables)]
+    fn end_contact(&mut self, a: BodyHandle, b: BodyHandle) {}
+}
+
+
+struct Entry {
+    body: Option<Body>,
+    generation: u32,
+    proxy: Option<ProxyId>,
+}
+
+
+struct JointEntry {
+    joint: Option<Joint>,
+    generation: u32,
+}
+
+
+/// A collection of bodies moving and colliding with each other
+pub struct World {
+    pub gravity: Vector2,
+
+    /// How many times the contacts are solved each step, more is more accurate but slower
+    pub velocity_iterations: u32,
+
+    clock: FixedTimestep,
+
+    entries: Vec<Entry>,
+    free: Vec<usize>,
+
+    broad_phase: BroadPhase<BodyHandle>,
+
+    joints: Vec<JointEntry>,
+    free_joints: Vec<usize>,
+
+    // The impulses of every touching pair, ordered by handle
+    contacts: HashMap<(BodyHandle, BodyHandle), ContactImpulses>,
+
+    // Pairs of sensors and the bodies overlapping them
+    sensor_overlaps: HashSet<(BodyHandle, BodyHandle)>,
+    sensor_events: Vec<SensorEvent<BodyHandle>>,
+
+    listener: Option<Box<ContactListener>>,
+}
+
+
+impl World {
+    /// Creates an empty world stepping 60 times per second
+    pub fn new(gravity: Vector2) -> World {
+        World::with_cell_size(gravity, 4.0)
+    }
+
+
+    /// Creates an empty world, the cell size should be slightly larger than a typical body
+    pub fn with_cell_size(gravity: Vector2, cell_size: f64) -> World {
+        World {
+            gravity,
+            velocity_iterations: 8,
+
+            clock: FixedTimestep::new(60.0),
+
+            entries: Vec::new(),
+            free: Vec::new(),
+
+            broad_phase: BroadPhase::new(cell_size),
+
+            joints: Vec::new(),
+            free_joints: Vec::new(),
+
+            contacts: HashMap::new(),
+
+            sensor_overlaps: HashSet::new(),
+            sensor_events: Vec::new(),
+
+            listener: None,
+        }
+    }
+
+
+    /// Sets the length of a single step in seconds
+    pub fn set_timestep(&mut self, timestep: f64) {
+        self.clock.set_timestep(timestep);
+    }
+
+    pub fn timestep(&self) -> f64 {
+        self.clock.timestep()
+    }
+
+
+    /// Sets how many steps a single update may take at most, to avoid falling further behind after a slow frame
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.clock = self.clock.with_max_steps(max_steps);
+    }
+
+
+    /// Sets the object receiving collision events
+    pub fn set_contact_listener(&mut self, listener: Box<ContactListener>) {
+        self.listener = Some(listener);
+    }
+
+    /// Removes the object receiving collision events, returning it
+    pub fn take_contact_listener(&mut self) -> Option<Box<ContactListener>> {
+        self.listener.take()
+    }
+
+
+    /// Adds a body to the world
+    pub fn add_body(&mut self, body: Body) -> BodyHandle {
+        let index = match self.free.pop() {
+            Some(index) => {
+                let entry = &mut self.entries[index];
+                entry.generation = entry.generation.wrapping_add(1);
+                index
+            },
+
+            None => {
+                self.entries.push(Entry { body: None, generation: 0, proxy: None });
+                self.entries.len() - 1
+            }
+        };
+
+        let handle = BodyHandle { index, generation: self.entries[index].generation };
+
+        let entry = &mut self.entries[index];
+        entry.proxy = Some(self.broad_phase.insert_filtered(&body, handle, body.filter));
+        entry.body = Some(body);
+
+        handle
+    }
+
+
+    /// Removes a body from the world along with its joints, returning it
+    pub fn remove_body(&mut self, handle: BodyHandle) -> Option<Body> {
+        if !self.contains(handle) {
+            return None;
+        }
+
+        let attached: Vec<JointHandle> = self.joints().into_iter()
+            .filter(|&(_, joint)| {
+                let (a, b) = joint.bodies();
+                a == handle || b == Some(handle)
+            })
+            .map(|(joint, _)| joint)
+            .collect();
+
+        for joint in attached {
+            self.remove_joint(joint);
+        }
+
+        let entry = &mut self.entries[handle.index];
+        if let Some(proxy) = entry.proxy.take() {
+            self.broad_phase.remove(proxy);
+        }
+
+        self.free.push(handle.index);
+        entry.body.take()
+    }
+
+
+    /// Returns true if a body is in the world
+    pub fn contains(&self, handle: BodyHandle) -> bool {
+        match self.entries.get(handle.index) {
+            Some(entry) => entry.generation == handle.generation && entry.body.is_some(),
+            None => false
+        }
+    }
+
+
+    pub fn body(&self, handle: BodyHandle) -> Option<&Body> {
+        if self.contains(handle) {
+            self.entries[handle.index].body.as_ref()
+        } else {
+            None
+        }
+    }
+
+    pub fn body_mut(&mut self, handle: BodyHandle) -> Option<&mut Body> {
+        if self.contains(handle) {
+            self.entries[handle.index].body.as_mut()
+        } else {
+            None
+        }
+    }
+
+
+    /// Returns every body in the world
+    pub fn bodies(&self) -> Vec<(BodyHandle, &Body)> {
+        self.entries.iter().enumerate()
+            .filter_map(|(index, entry)| {
+                entry.body.as_ref().map(|body| (BodyHandle { index, generation: entry.generation }, body))
+            })
+            .collect()
+    }
+
+
+    /// Adds a joint to the world
+    pub fn add_joint<J: Into<Joint>>(&mut self, joint: J) -> JointHandle {
+        let joint = joint.into();
+
+        let index = match self.free_joints.pop() {
+            Some(index) => {
+                let entry = &mut self.joints[index];
+                entry.generation = entry.generation.wrapping_add(1);
+                entry.joint = Some(joint);
+                index
+            },
+
+            None => {
+                self.joints.push(JointEntry { joint: Some(joint), generation: 0 });
+                self.joints.len() - 1
+            }
+        };
+
+        JointHandle { index, generation: self.joints[index].generation }
+    }
+
+
+    /// Removes a joint from the world, returning it
+    pub fn remove_joint(&mut self, handle: JointHandle) -> Option<Joint> {
+        if self.joint(handle).is_none() {
+            return None;
+        }
+
+        self.free_joints.push(handle.index);
+        self.joints[handle.index].joint.take()
+    }
+
+
+    pub fn joint(&self, handle: JointHandle) -> Option<&Joint> {
+        match self.joints.get(handle.index) {
+            Some(entry) if entry.generation == handle.generation => entry.joint.as_ref(),
+            _ => None
+        }
+    }
+
+    pub fn joint_mut(&mut self, handle: JointHandle) -> Option<&mut Joint> {
+        if self.joint(handle).is_some() {
+            self.joints[handle.index].joint.as_mut()
+        } else {
+            None
+        }
+    }
+
+
+    /// Returns every joint in the world
+    pub fn joints(&self) -> Vec<(JointHandle, &Joint)> {
+        self.joints.iter().enumerate()
+            .filter_map(|(index, entry)| {
+                entry.joint.as_ref().map(|joint| (JointHandle { index, generation: entry.generation }, joint))
+            })
+            .collect()
+    }
+
+
+    /// Returns the pairs of bodies that touched during the last step
+    pub fn contacts(&self) -> Vec<(BodyHandle, BodyHandle)> {
+        let mut pairs: Vec<_> = self.contacts.keys().cloned().collect();
+        pairs.sort();
+        pairs
+    }
+
+
+    /// Removes and returns the sensor events since the last time they were drained.
+    /// Sensors report every step, so a single update may report the same overlap more than once.
+    pub fn drain_sensor_events(&mut self) -> Vec<SensorEvent<BodyHandle>> {
+        ::std::mem::replace(&mut self.sensor_events, Vec::new())
+    }
+
+
+    /// Advances the world by an amount of time in fixed steps, returning the number of steps taken.
+    /// Time that doesn't fill a whole step is carried over to the next update.
+    pub fn update(&mut self, delta_time: f64) -> u32 {
+        let steps = self.clock.advance(delta_time);
+        let timestep = self.clock.timestep();
+
+        for _ in 0..steps {
+            self.step(timestep);
+        }
+
+        steps
+    }
+
+
+    /// Returns how far the world is between the last step and the next, between 0 and 1
+    pub fn alpha(&self) -> f64 {
+        self.clock.alpha()
+    }
+
+
+    /// Advances the world by a single step
+    pub fn step(&mut self, dt: f64) {
+        let gravity = self.gravity;
+        for entry in self.entries.iter_mut() {
+            if let Some(ref mut body) = entry.body {
+                body.integrate_velocity(gravity, dt);
+            }
+        }
+
+        self.update_broad_phase();
+
+        let pairs = self.broad_phase.pairs();
+        let (mut constraints, manifolds) = self.find_contacts(&pairs, dt);
+        let sensor_overlaps = self.find_sensor_overlaps(&pairs);
+        let joints = self.prepare_joints(dt);
+
+        for &index in joints.iter() {
+            if let Some(ref mut joint) = self.joints[index].joint {
+                let (a, b) = joint_bodies(&mut self.entries, joint);
+                joint.warm_start(a, b);
+            }
+        }
+
+        for constraint in constraints.iter() {
+            let (a, b) = pair_mut(&mut self.entries, constraint.a.index, constraint.b.index);
+            constraint.warm_start(a, b);
+        }
+
+        for _ in 0..self.velocity_iterations {
+            for &index in joints.iter() {
+                if let Some(ref mut joint) = self.joints[index].joint {
+                    let (a, b) = joint_bodies(&mut self.entries, joint);
+                    joint.solve(a, b);
+                }
+            }
+
+            for constraint in constraints.iter_mut() {
+                let (a, b) = pair_mut(&mut self.entries, constraint.a.index, constraint.b.index);
+                constraint.solve(a, b);
+            }
+        }
+
+        for entry in self.entries.iter_mut() {
+            if let Some(ref mut body) = entry.body {
+                body.integrate_position(dt);
+            }
+        }
+
+        let mut contacts = HashMap::new();
+        for constraint in constraints.iter() {
+            contacts.insert((constraint.a, constraint.b), constraint.impulses());
+        }
+
+        let previous = ::std::mem::replace(&mut self.contacts, contacts);
+        self.report_contacts(&previous, manifolds);
+
+        let previous = ::std::mem::replace(&mut self.sensor_overlaps, sensor_overlaps);
+        sensor_events(&previous, &self.sensor_overlaps, &mut self.sensor_events);
+    }
+
+
+    fn update_broad_phase(&mut self) {
+        for entry in self.entries.iter() {
+            if let (&Some(ref body), Some(proxy)) = (&entry.body, entry.proxy) {
+                self.broad_phase.update(proxy, body);
+                self.broad_phase.set_filter(proxy, body.filter);
+            }
+        }
+    }
+
+
+    /// Prepares every joint whose bodies exist and can move, returning their indices
+    fn prepare_joints(&mut self, dt: f64) -> Vec<usize> {
+        let mut active = Vec::new();
+
+        for (index, entry) in self.joints.iter_mut().enumerate() {
+            if let Some(ref mut joint) = entry.joint {
+                let (a, b) = joint.bodies();
+
+                let body_a = match body(&self.entries, a) {
+                    Some(body) => body,
+                    None => continue
+                };
+
+                let body_b = match b {
+                    Some(b) if b == a => continue,
+                    Some(b) => match body(&self.entries, b) {
+                        Some(body) => Some(body),
+                        None => continue
+                    },
+                    None => None
+                };
+
+                let movable = |body: &Body| body.inverse_mass > 0.0 || body.inverse_inertia > 0.0;
+                if !movable(body_a) && !body_b.map_or(false, |body| movable(body)) {
+                    continue;
+                }
+
+                joint.prepare(body_a, body_b, dt);
+                active.push(index);
+            }
+        }
+
+        active
+    }
+
+
+    /// Runs the narrow phase on every pair of bodies the broad phase found
+    fn find_contacts(&self, pairs: &[(ProxyId, ProxyId)], dt: f64)
+                     -> (Vec<ContactConstraint>, Vec<((BodyHandle, BodyHandle), Manifold)>) {
+        let mut constraints = Vec::new();
+        let mut manifolds = Vec::new();
+
+        // Pairs of bodies held together by a joint that doesn't let them collide
+        let connected: HashSet<(BodyHandle, BodyHandle)> = self.joints.iter()
+            .filter_map(|entry| entry.joint.as_ref())
+            .filter(|joint| !joint.collide_connected())
+            .filter_map(|joint| match joint.bodies() {
+                (a, Some(b)) => Some(if a < b { (a, b) } else { (b, a) }),
+                _ => None
+            })
+            .collect();
+
+        for &(proxy_a, proxy_b) in pairs.iter() {
+            let (a, b) = match (self.broad_phase.get(proxy_a), self.broad_phase.get(proxy_b)) {
+                (Some(&a), Some(&b)) => if a < b { (a, b) } else { (b, a) },
+                _ => continue
+            };
+
+            if connected.contains(&(a, b)) {
+                continue;
+            }
+
+            let (body_a, body_b) = match (self.body(a), self.body(b)) {
+                (Some(body_a), Some(body_b)) => (body_a, body_b),
+                _ => continue
+            };
+
+            if body_a.sensor || body_b.sensor {
+                continue;
+            }
+
+            // Bodies that can't be pushed don't collide with each other
+            if body_a.inverse_mass == 0.0 && body_b.inverse_mass == 0.0 {
+                continue;
+            }
+
+            if let Some(manifold) = manifold(body_a, body_b) {
+                constraints.push(ContactConstraint::new(
+                    a, b, body_a, body_b, &manifold, self.contacts.get(&(a, b)), dt
+                ));
+
+                manifolds.push(((a, b), manifold));
+            }
+        }
+
+        (constraints, manifolds)
+    }
+
+
+    /// Finds the bodies overlapping each sensor, sensors don't detect each other
+    fn find_sensor_overlaps(&self, pairs: &[(ProxyId, ProxyId)]) -> HashSet<(BodyHandle, BodyHandle)> {
+        let mut overlaps = HashSet::new();
+
+        for &(proxy_a, proxy_b) in pairs.iter() {
+            let (a, b) = match (self.broad_phase.get(proxy_a), self.broad_phase.get(proxy_b)) {
+                (Some(&a), Some(&b)) => (a, b),
+                _ => continue
+            };
+
+            let (body_a, body_b) = match (self.body(a), self.body(b)) {
+                (Some(body_a), Some(body_b)) => (body_a, body_b),
+                _ => continue
+            };
+
+            let pair = match (body_a.sensor, body_b.sensor) {
+                (true, false) => (a, b),
+                (false, true) => (b, a),
+                _ => continue
+            };
+
+            if intersect(body_a, body_b) {
+                overlaps.insert(pair);
+            }
+        }
+
+        overlaps
+    }
+
+
+    /// Tells the listener which pairs started and stopped touching
+    fn report_contacts(&mut self, previous: &HashMap<(BodyHandle, BodyHandle), ContactImpulses>,
+                       manifolds: Vec<((BodyHandle, BodyHandle), Manifold)>) {
+        let mut listener = match self.listener.take() {
+            Some(listener) => listener,
+            None => return
+        };
+
+        for &((a, b), ref manifold) in manifolds.iter() {
+            if !previous.contains_key(&(a, b)) {
+                listener.begin_contact(a, b, manifold);
+            }
+        }
+
+        let mut ended: Vec<_> = previous.keys()
+            .filter(|&&pair| !self.contacts.contains_key(&pair))
+            .cloned()
+            .collect();
+        ended.sort();
+
+        for (a, b) in ended {
+            listener.end_contact(a, b);
+        }
+
+        self.listener = Some(listener);
+    }
+}
+
+
+/// Borrows two different bodies at once
+fn pair_mut(entries: &mut [Entry], a: usize, b: usize) -> (&mut Body, &mut Body) {
+    let (entry_a, entry_b) = if a < b {
+        let (left, right) = entries.split_at_mut(b);
+        (&mut left[a], &mut right[0])
+    } else {
+        let (left, right) = entries.split_at_mut(a);
+        (&mut right[0], &mut left[b])
+    };
+
+    (entry_a.body.as_mut().unwrap(), entry_b.body.as_mut().unwrap())
+}
+
+
+/// Returns a body if it exists
+fn body(entries: &[Entry], handle: BodyHandle) -> Option<&Body> {
+    match entries.get(handle.index) {
+        Some(entry) if entry.generation == handle.generation => entry.body.as_ref(),
+        _ => None
+    }
+}
+
+
+/// Borrows the bodies connected by a joint, which have to exist
+fn joint_bodies<'a>(entries: &'a mut [Entry], joint: &Joint) -> (&'a mut Body, Option<&'a mut Body>) {
+    match joint.bodies() {
+        (a, Some(b)) => {
+            let (a, b) = pair_mut(entries, a.index, b.index);
+            (a, Some(b))
+        },
+
+        (a, None) => (entries[a.index].body.as_mut().unwrap(), None)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use physics::BodyType;
+    use collision::Collide;
+    use shapes::Rectangle;
+
+    const DT: f64 = 1.0 / 60.0;
+
+
+    fn ground(world: &mut World) -> BodyHandle {
+        world.add_body(Body::new(BodyType::Static, Rectangle::new(-10.0, 10.0, 0.0, -1.0), 1.0))
+    }
+
+
+    #[test]
+    fn boxes_come_to_rest_on_the_ground() {
+        let mut world = World::new(Vector2::new(0.0, -10.0));
+        ground(&mut world);
+        let falling = world.add_body(Body::new(BodyType::Dynamic, Rectangle::new(-0.5, 0.5, 2.0, 1.0), 1.0));
+
+        for _ in 0..240 {
+            world.step(DT);
+
+            // Never sinks much further than the slop of 0.005 the contacts allow
+            let bottom = world.body(falling).unwrap().get_bounding_box().bottom;
+            assert!(bottom > -0.02, "sank into the ground: {}", bottom);
+        }
+
+        let body = world.body(falling).unwrap();
+        let bottom = body.get_bounding_box().bottom;
+
+        assert!(bottom > -0.01 && bottom < 0.01, "not resting on the ground: {}", bottom);
+        assert!(body.velocity.dot(body.velocity).sqrt() < 0.05);
+        assert!(body.angle.abs() < 0.01);
+        assert_eq!(world.contacts().len(), 1);
+    }
+
+
+    #[test]
+    fn static_bodies_never_move() {
+        let mut world = World::new(Vector2::new(0.0, -10.0));
+        let ground = ground(&mut world);
+
+        {
+            let body = world.body_mut(ground).unwrap();
+            body.velocity = Vector2::new(1.0, 2.0);
+            body.angular_velocity = 1.0;
+            body.apply_force(Vector2::new(100.0, 100.0));
+        }
+
+        let position = world.body(ground).unwrap().position;
+        world.add_body(Body::new(BodyType::Dynamic, Rectangle::new(-0.5, 0.5, 1.5, 0.5), 1.0));
+
+        for _ in 0..120 {
+            world.step(DT);
+        }
+
+        let body = world.body(ground).unwrap();
+        assert_eq!(body.position.x, position.x);
+        assert_eq!(body.position.y, position.y);
+        assert_eq!(body.angle, 0.0);
+    }
+
+
+    #[test]
+    fn sensors_report_enter_stay_and_exit_once() {
+        let mut world = World::new(Vector2::new(0.0, 0.0));
+
+        let mut sensor = Body::new(BodyType::Static, Rectangle::new(0.0, 1.0, 1.0, 0.0), 1.0);
+        sensor.sensor = true;
+        let sensor = world.add_body(sensor);
+
+        let mut mover = Body::new(BodyType::Kinematic, Rectangle::new(-1.0, -0.8, 0.6, 0.4), 1.0);
+        mover.velocity = Vector2::new(3.0, 0.0);
+        let mover = world.add_body(mover);
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            world.step(DT);
+
+            // Every step reports each overlapping pair exactly once
+            let step = world.drain_sensor_events();
+            assert!(step.len() <= 1, "{:?}", step);
+            events.extend(step);
+        }
+
+        assert!(events.len() > 2);
+        assert_eq!(events[0], SensorEvent::Enter { sensor, other: mover });
+        assert_eq!(events[events.len() - 1], SensorEvent::Exit { sensor, other: mover });
+
+        for event in events[1..events.len() - 1].iter() {
+            assert_eq!(*event, SensorEvent::Stay { sensor, other: mover });
+        }
+
+        // The box is 0.2 wide and crosses a sensor 1.0 wide at 0.05 per step, overlapping for about 23 steps
+        let stays = events.len() - 2;
+        assert!(stays >= 20 && stays <= 24, "{} stays", stays);
+    }
+}
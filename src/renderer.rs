@@ -12,6 +12,9 @@ use glium::DrawParameters;
 use glium::Blend;
 use glium::BlendingFunction;
 use glium::LinearBlendingFactor;
+use glium::draw_parameters::Stencil;
+use glium::draw_parameters::StencilTest;
+use glium::draw_parameters::StencilOperation;
 
 use glium::uniforms::MagnifySamplerFilter;
 use glium::uniforms::MinifySamplerFilter;
@@ -28,6 +31,9 @@ use Color;
 use Texture;
 use Context;
 use shapes::Rectangle;
+use path::Path;
+use path::FillRule;
+use path::StrokeStyle;
 
 
 /// Render an object
@@ -38,9 +44,62 @@ pub trait Render<S> {
 
 
 
-/// Anything that can be turned into triangles
+/// Anything that can be turned into triangles.
+///
+/// Only `Renderer::fill_shape` divides curves finely enough for the current view,
+/// filling the result of `get_triangles` always uses the same number of segments.
 pub trait Triangulate {
+    /// Turns the object into triangles, dividing any curves into 64 segments per full circle
     fn get_triangles(&self) -> Triangles;
+
+    /// Turns the object into triangles, dividing any curves as requested.
+    /// Objects without curves ignore the tessellation.
+    #[allow(unused_variables)]
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
+        self.get_triangles()
+    }
+}
+
+
+/// Determines how finely curves are divided into straight segments
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tessellation {
+    /// The largest distance, in world units, allowed between the segments and the true curve
+    Tolerance(f64),
+
+    /// A fixed number of segments for a full circle, at least 3
+    Segments(u32),
+}
+
+
+impl Tessellation {
+    /// Returns the number of segments to use for a circular arc
+    pub fn arc_segments(&self, radius: f64, sweep: f64) -> u32 {
+        use std::f64::consts::PI;
+        const MAX_SEGMENTS: f64 = 4096.0;
+
+        let turns = sweep.abs() / (2.0 * PI);
+
+        let segments = match *self {
+            Tessellation::Tolerance(tolerance) => {
+                let radius = radius.abs();
+                let tolerance = tolerance.abs();
+
+                if radius <= tolerance || tolerance == 0.0 {
+                    // A triangle is already within the tolerance, or no tolerance can be met
+                    if tolerance == 0.0 { MAX_SEGMENTS } else { 3.0 * turns }
+                } else {
+                    let step = 2.0 * (1.0 - tolerance / radius).acos();
+                    (sweep.abs() / step).max(3.0 * turns)
+                }
+            },
+
+            // Fewer than three segments can't enclose an area
+            Tessellation::Segments(segments) => segments.max(3) as f64 * turns,
+        };
+
+        segments.ceil().max(1.0).min(MAX_SEGMENTS) as u32
+    }
 }
 
 
@@ -80,7 +139,13 @@ pub struct Renderer<'a> {
     default_texture: Texture,
 
     // If textures should be flipped vertically
-    flip_textures: bool
+    flip_textures: bool,
+
+    // The style used when stroking paths
+    stroke_style: StrokeStyle,
+
+    // The largest distance, in pixels, that curves may be from their true shape
+    pixel_tolerance: f64
 }
 
 impl<'a> Renderer<'a> {
@@ -113,13 +178,18 @@ impl<'a> Renderer<'a> {
             fill_color: Color::grey(1.0),
             texture: None,
             default_texture,
-            flip_textures: false
+            flip_textures: false,
+
+            stroke_style: StrokeStyle::new(1.0),
+            pixel_tolerance: 0.25
         }
     }
 
     pub(crate) fn begin(&mut self) {
         if self.frame.is_none() {
-            self.frame = Some(self.display.draw());
+            let mut frame = self.display.draw();
+            frame.clear_stencil(0);
+            self.frame = Some(frame);
 
             self.draw_parameters = DrawParameters::default();
             self.draw_parameters.blend = Blend {
@@ -188,6 +258,11 @@ impl<'a> Renderer<'a> {
         self.view = view;
     }
 
+    /// Returns the current view
+    pub fn view(&self) -> Rectangle {
+        self.view
+    }
+
 
     /// Sets the current fill color
     pub fn set_color(&mut self, color: Color) {
@@ -225,6 +300,31 @@ impl<'a> Renderer<'a> {
     }
 
 
+    /// Sets the style used when stroking paths
+    pub fn set_stroke_style(&mut self, style: StrokeStyle) {
+        self.stroke_style = style;
+    }
+
+
+    /// Sets the largest distance, in pixels, that curves may deviate from their true shape
+    pub fn set_tolerance(&mut self, pixels: f64) {
+        self.pixel_tolerance = pixels;
+    }
+
+
+    /// Returns the largest distance, in world units, that curves may deviate from their true shape
+    pub fn tolerance(&self) -> f64 {
+        view_tolerance(self.view, self.display.get_framebuffer_dimensions(), self.pixel_tolerance)
+    }
+
+
+    /// Fills a shape, dividing its curves finely enough for the current view
+    pub fn fill_shape(&mut self, shape: &Triangulate) {
+        let tolerance = self.tolerance();
+        self.fill(shape.tessellate(Tessellation::Tolerance(tolerance)));
+    }
+
+
 
     /// Creates a new vertex based on the current state
     fn new_vertex(&self, position: Vector2, tex_coord: Option<Vector2>) -> Vertex {
@@ -268,6 +368,93 @@ impl<'a> Renderer<'a> {
             &vertices, &[0, 1], PrimitiveType::LinesList
         );
     }
+
+
+    /// Fills the inside of a path according to its fill rule
+    pub fn fill_path(&mut self, path: &Path) {
+        use std::f64::INFINITY;
+
+        let polylines = path.flatten(self.tolerance());
+
+        // Every closed outline is drawn as a fan into the stencil buffer,
+        // overlapping fans cancel out according to the fill rule
+        let mut vertices = Vec::new();
+        let mut indices = Vec::new();
+        let mut min = Vector2::new(INFINITY, INFINITY);
+        let mut max = Vector2::new(-INFINITY, -INFINITY);
+
+        for polyline in polylines.iter() {
+            let first = vertices.len() as u32;
+
+            for point in polyline.points.iter() {
+                vertices.push(self.new_vertex(*point, None));
+
+                if point.x < min.x { min.x = point.x }
+                if point.x > max.x { max.x = point.x }
+                if point.y < min.y { min.y = point.y }
+                if point.y > max.y { max.y = point.y }
+            }
+
+            for i in 1..polyline.points.len() as u32 - 1 {
+                indices.push(first);
+                indices.push(first + i);
+                indices.push(first + i + 1);
+            }
+        }
+
+        if indices.is_empty() {
+            return;
+        }
+
+        let previous_parameters = self.draw_parameters.clone();
+
+        let (operation, mask) = match path.fill_rule() {
+            FillRule::NonZero => ((StencilOperation::IncrementWrap, StencilOperation::DecrementWrap), !0),
+            FillRule::EvenOdd => ((StencilOperation::Invert, StencilOperation::Invert), 1),
+        };
+
+        self.draw_parameters.color_mask = (false, false, false, false);
+        self.draw_parameters.stencil = Stencil {
+            test_clockwise: StencilTest::AlwaysPass,
+            depth_pass_operation_clockwise: operation.0,
+            test_counter_clockwise: StencilTest::AlwaysPass,
+            depth_pass_operation_counter_clockwise: operation.1,
+            .. Default::default()
+        };
+
+        self.draw_vertices(&vertices, &indices, PrimitiveType::TrianglesList);
+
+
+        // Cover the path's bounds, only drawing where the stencil was marked and resetting it
+        self.draw_parameters.color_mask = previous_parameters.color_mask;
+        self.draw_parameters.stencil = Stencil {
+            test_clockwise: StencilTest::IfNotEqual { mask },
+            reference_value_clockwise: 0,
+            depth_pass_operation_clockwise: StencilOperation::Zero,
+            test_counter_clockwise: StencilTest::IfNotEqual { mask },
+            reference_value_counter_clockwise: 0,
+            depth_pass_operation_counter_clockwise: StencilOperation::Zero,
+            .. Default::default()
+        };
+
+        let cover = [
+            self.new_vertex(Vector2::new(min.x, min.y), None),
+            self.new_vertex(Vector2::new(max.x, min.y), None),
+            self.new_vertex(Vector2::new(max.x, max.y), None),
+            self.new_vertex(Vector2::new(min.x, max.y), None),
+        ];
+
+        self.draw_vertices(&cover, &[0, 1, 2, 2, 3, 0], PrimitiveType::TrianglesList);
+
+        self.draw_parameters = previous_parameters;
+    }
+
+
+    /// Draws the outline of a path
+    pub fn stroke_path(&mut self, path: &Path, style: &StrokeStyle) {
+        let triangles = path.stroke(style, self.tolerance());
+        self.fill(triangles);
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -283,6 +470,16 @@ implement_vertex!(Vertex, position, color, tex_coord);
 
 
 
+// Converts a distance in pixels to world units for a view covering a framebuffer
+fn view_tolerance(view: Rectangle, (width, height): (u32, u32), pixels: f64) -> f64 {
+    let x_scale = (view.right - view.left).abs() / width.max(1) as f64;
+    let y_scale = (view.top - view.bottom).abs() / height.max(1) as f64;
+
+    pixels * x_scale.min(y_scale)
+}
+
+
+
 impl<'a> Render<Triangles> for Renderer<'a> {
     fn fill(&mut self, object: Triangles) {
         let mut vertices = Vec::new();
@@ -319,3 +516,55 @@ impl<'a> Render<Triangles> for Renderer<'a> {
     }
 }
 
+
+impl<'a, 'b> Render<&'b Path> for Renderer<'a> {
+    fn fill(&mut self, path: &'b Path) {
+        self.fill_path(path);
+    }
+
+    fn draw(&mut self, path: &'b Path) {
+        let style = self.stroke_style;
+        self.stroke_path(path, &style);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Circle;
+
+
+    fn circle_segments(radius: f64, tolerance: f64) -> usize {
+        let circle = Circle::new(Vector2::new(0.0, 0.0), radius);
+
+        match circle.tessellate(Tessellation::Tolerance(tolerance)) {
+            Triangles::IndexedTriangles(points, _) => points.len() - 1,
+            Triangles::TriangleList(triangles) => triangles.len(),
+        }
+    }
+
+
+    #[test]
+    fn larger_circles_get_more_segments() {
+        let small = circle_segments(1.0, 0.01);
+        let large = circle_segments(100.0, 0.01);
+
+        assert!(large > small, "{} segments for r = 100, {} for r = 1", large, small);
+    }
+
+
+    #[test]
+    fn zooming_in_adds_segments() {
+        let screen = (800, 600);
+        let far = Rectangle { left: -40.0, right: 40.0, top: 30.0, bottom: -30.0 };
+        let near = Rectangle { left: -4.0, right: 4.0, top: 3.0, bottom: -3.0 };
+
+        let far_tolerance = view_tolerance(far, screen, 0.25);
+        let near_tolerance = view_tolerance(near, screen, 0.25);
+        assert!((far_tolerance - 0.025).abs() < 1e-12);
+        assert!((near_tolerance - 0.0025).abs() < 1e-12);
+
+        assert!(circle_segments(1.0, near_tolerance) > circle_segments(1.0, far_tolerance));
+    }
+}
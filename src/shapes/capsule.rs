@@ -0,0 +1,132 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use renderer::Tessellation;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// A line segment with a radius, or a rectangle with semicircles at both ends
+#[derive(Copy, Clone)]
+pub struct Capsule {
+    pub start: Vector2,
+    pub end: Vector2,
+    pub radius: f64
+}
+
+impl Capsule {
+    pub fn new(start: Vector2, end: Vector2, radius: f64) -> Capsule {
+        Capsule { start, end, radius }
+    }
+}
+
+
+impl super::Shape for Capsule {
+    fn translate(&mut self, amount: Vector2) {
+        self.start += amount;
+        self.end += amount;
+    }
+
+    fn center(&self) -> Vector2 {
+        (self.start + self.end) * 0.5
+    }
+
+
+    /// Non-uniform scaling scales the radius by the square root of the change in area,
+    /// since a capsule can't be stretched
+    fn transform(&mut self, transform: &Affine2) {
+        self.start = transform.transform_point(self.start);
+        self.end = transform.transform_point(self.end);
+        self.radius *= transform.uniform_scale();
+    }
+
+
+    fn area(&self) -> f64 {
+        use std::f64::consts::PI;
+        2.0 * self.radius * self.length() + PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        use std::f64::consts::PI;
+        2.0 * self.length() + 2.0 * PI * self.radius
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        use std::f64::consts::PI;
+
+        let area = super::Shape::area(self);
+        if area == 0.0 {
+            return 0.0;
+        }
+
+        let r = self.radius;
+        let half_length = 0.5 * self.length();
+
+        // The rectangle in the middle
+        let rectangle_area = 2.0 * r * 2.0 * half_length;
+        let rectangle = rectangle_area * (4.0 * half_length * half_length + 4.0 * r * r) / 12.0;
+
+        // The two half circles, moved from their own centroids to the center of the capsule
+        let half_circle_area = 0.5 * PI * r * r;
+        let offset = 4.0 * r / (3.0 * PI);
+        let half_circle = half_circle_area * (0.5 * r * r - offset * offset + (half_length + offset) * (half_length + offset));
+
+        mass * (rectangle + 2.0 * half_circle) / area
+    }
+}
+
+
+impl Capsule {
+    /// Returns the distance between the centers of the ends
+    pub fn length(&self) -> f64 {
+        let d = self.end - self.start;
+        d.dot(d).sqrt()
+    }
+}
+
+
+
+impl Triangulate for Capsule {
+    fn get_triangles(&self) -> Triangles {
+        self.tessellate(Tessellation::Segments(64))
+    }
+
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
+        use std::f64::consts::PI;
+
+        let segments = tessellation.arc_segments(self.radius, PI);
+
+        let direction = self.end - self.start;
+        let angle = direction.y.atan2(direction.x);
+
+        // Half a circle around each end, starting on the right side of the segment
+        let mut outline = Vec::with_capacity(2 * segments as usize + 2);
+        for &(center, start) in [(self.end, angle - 0.5 * PI), (self.start, angle + 0.5 * PI)].iter() {
+            for i in 0..segments + 1 {
+                let (dy, dx) = (start + i as f64 / segments as f64 * PI).sin_cos();
+                outline.push(center + self.radius * Vector2::new(dx, dy));
+            }
+        }
+
+        super::fan((self.start + self.end) * 0.5, outline)
+    }
+}
+
+
+impl Collide for Capsule {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        let end = if axis.dot(self.end) > axis.dot(self.start) { self.end } else { self.start };
+
+        end + self.radius * super::unit(axis)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle {
+            left: self.start.x.min(self.end.x) - self.radius,
+            right: self.start.x.max(self.end.x) + self.radius,
+            top: self.start.y.max(self.end.y) + self.radius,
+            bottom: self.start.y.min(self.end.y) - self.radius,
+        }
+    }
+}
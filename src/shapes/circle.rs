@@ -1,8 +1,10 @@
 use trap::Vector2;
 use renderer::Triangulate;
 use renderer::Triangles;
+use renderer::Tessellation;
 use collision::Collide;
 use shapes::Rectangle;
+use transform::Affine2;
 
 
 #[derive(Copy, Clone)]
@@ -26,28 +28,56 @@ impl super::Shape for Circle {
     fn center(&self) -> Vector2 {
         self.center
     }
+
+
+    /// Non-uniform scaling scales the radius by the square root of the change in area,
+    /// since a circle can't be stretched
+    fn transform(&mut self, transform: &Affine2) {
+        self.center = transform.transform_point(self.center);
+        self.radius *= transform.uniform_scale();
+    }
+
+
+    fn area(&self) -> f64 {
+        use std::f64::consts::PI;
+        PI * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        use std::f64::consts::PI;
+        2.0 * PI * self.radius
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        0.5 * mass * self.radius * self.radius
+    }
 }
 
 
 
 impl Triangulate for Circle {
     fn get_triangles(&self) -> Triangles {
+        self.tessellate(Tessellation::Segments(64))
+    }
+
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
         use std::f64::consts::PI;
-        const SEGMENTS: u32 = 64;
+
+        let segments = tessellation.arc_segments(self.radius, 2.0 * PI);
 
         let mut points = Vec::new();
         let mut indices = Vec::new();
 
         points.push(self.center);
 
-        for i in 0..SEGMENTS {
-            let (dy, dx) = (i as f64 / SEGMENTS as f64 * PI * 2.0).sin_cos();
+        for i in 0..segments {
+            let (dy, dx) = (i as f64 / segments as f64 * PI * 2.0).sin_cos();
 
             points.push(self.center + self.radius * Vector2::new(dx, dy));
 
             indices.push(0);
             indices.push(i + 1);
-            indices.push((i + 1) % SEGMENTS + 1);
+            indices.push((i + 1) % segments + 1);
         }
 
         Triangles::IndexedTriangles(
@@ -60,7 +90,7 @@ impl Triangulate for Circle {
 
 impl Collide for Circle {
     fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
-        self.center + self.radius * axis.normal()
+        self.center + self.radius * super::unit(axis)
     }
 
     fn get_bounding_box(&self) -> Rectangle {
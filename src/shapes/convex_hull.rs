@@ -3,6 +3,9 @@ use renderer::Triangulate;
 use renderer::Triangles;
 use collision::Collide;
 use shapes::Rectangle;
+use shapes::cross;
+use shapes::signed_area;
+use transform::Affine2;
 
 
 #[derive(Clone)]
@@ -11,11 +14,117 @@ pub struct ConvexHull {
 }
 
 impl ConvexHull {
+    /// Creates a hull from points that already form a convex, counter-clockwise polygon
     pub fn new(points: Vec<Vector2>) -> ConvexHull {
         ConvexHull {
             points
         }
     }
+
+
+    /// Computes the smallest convex hull containing all points, in counter-clockwise order.
+    /// Duplicate and collinear points are removed, so fewer than three points are
+    /// returned if all points lie on a line. Non-finite points are ignored,
+    /// returns None if no points are left.
+    pub fn from_points(points: &[Vector2]) -> Option<ConvexHull> {
+        use std::cmp::Ordering;
+
+        let mut sorted: Vec<Vector2> = points.iter()
+            .filter(|p| p.x.is_finite() && p.y.is_finite())
+            .cloned()
+            .collect();
+
+        sorted.sort_by(|a, b| {
+            a.x.partial_cmp(&b.x).unwrap_or(Ordering::Equal)
+                .then(a.y.partial_cmp(&b.y).unwrap_or(Ordering::Equal))
+        });
+        sorted.dedup_by(|a, b| a.x == b.x && a.y == b.y);
+
+        if sorted.is_empty() {
+            return None;
+        }
+
+        if sorted.len() < 3 {
+            return Some(ConvexHull::new(sorted));
+        }
+
+        // Andrew's monotone chain: build the lower and upper hulls separately
+        let mut hull: Vec<Vector2> = Vec::with_capacity(2 * sorted.len());
+
+        for point in sorted.iter() {
+            while hull.len() >= 2 && cross(hull[hull.len() - 1] - hull[hull.len() - 2], *point - hull[hull.len() - 2]) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+
+        let lower_length = hull.len() + 1;
+        for point in sorted.iter().rev().skip(1) {
+            while hull.len() >= lower_length && cross(hull[hull.len() - 1] - hull[hull.len() - 2], *point - hull[hull.len() - 2]) <= 0.0 {
+                hull.pop();
+            }
+            hull.push(*point);
+        }
+
+        // The last point is the same as the first
+        hull.pop();
+
+        Some(ConvexHull::new(hull))
+    }
+
+
+    /// Returns true if the points go around counter-clockwise
+    pub fn is_counter_clockwise(&self) -> bool {
+        signed_area(&self.points) > 0.0
+    }
+
+
+    /// Returns true if the points form a convex polygon that doesn't intersect itself
+    pub fn is_convex(&self) -> bool {
+        use std::f64::consts::PI;
+        const TOLERANCE: f64 = 1e-6;
+
+        let count = self.points.len();
+        if count < 3 {
+            return false;
+        }
+
+        // Every corner must turn the same way, and the turns must add up to exactly one revolution
+        let mut sign = 0.0;
+        let mut total_angle = 0.0;
+
+        for i in 0..count {
+            let a = self.points[i];
+            let b = self.points[(i + 1) % count];
+            let c = self.points[(i + 2) % count];
+
+            let ab = b - a;
+            let bc = c - b;
+
+            let turn = cross(ab, bc);
+            if turn != 0.0 {
+                if sign == 0.0 {
+                    sign = turn.signum();
+                } else if turn.signum() != sign {
+                    return false;
+                }
+            }
+
+            if ab.dot(ab) == 0.0 || bc.dot(bc) == 0.0 {
+                return false;
+            }
+
+            total_angle += (ab.x * bc.y - ab.y * bc.x).atan2(ab.dot(bc));
+        }
+
+        sign != 0.0 && (total_angle.abs() - 2.0 * PI).abs() < TOLERANCE
+    }
+
+
+    /// Returns true if the hull is convex and counter-clockwise, as required by collision
+    pub fn is_valid(&self) -> bool {
+        self.is_convex() && self.is_counter_clockwise()
+    }
 }
 
 
@@ -30,18 +139,69 @@ impl super::Shape for ConvexHull {
     fn center(&self) -> Vector2 {
         let mut sum = Vector2::new(0.0, 0.0);
 
+        if self.points.is_empty() {
+            return sum;
+        }
+
         for point in self.points.iter() {
             sum += *point;
         }
 
         sum / self.points.len() as f64
     }
+
+
+    fn transform(&mut self, transform: &Affine2) {
+        for point in self.points.iter_mut() {
+            *point = transform.transform_point(*point);
+        }
+
+        // Mirroring flips the winding, which has to stay counter-clockwise
+        if transform.determinant() < 0.0 {
+            self.points.reverse();
+        }
+    }
+
+
+    fn area(&self) -> f64 {
+        super::polygon_moments(&self.points).0.abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        super::outline_length(&self.points)
+    }
+
+    fn centroid(&self) -> Vector2 {
+        let (area, first, _) = super::polygon_moments(&self.points);
+
+        if area != 0.0 {
+            first / area
+        } else {
+            super::Shape::center(self)
+        }
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let (area, first, second) = super::polygon_moments(&self.points);
+
+        if area != 0.0 {
+            let centroid = first / area;
+            mass * (second / area - centroid.dot(centroid))
+        } else {
+            0.0
+        }
+    }
 }
 
 
 
 impl Triangulate for ConvexHull {
     fn get_triangles(&self) -> Triangles {
+        // Points and lines have no area to fill
+        if self.points.len() < 3 {
+            return Triangles::IndexedTriangles(Vec::new(), Vec::new());
+        }
+
         Triangles::IndexedTriangles(
             self.points.clone(),
             {
@@ -60,41 +220,62 @@ impl Triangulate for ConvexHull {
 }
 
 
+/// Empty hulls collide as a point at the origin
 impl Collide for ConvexHull {
     fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
-        let mut index = 0;
-        let mut max_projection = axis.dot(self.points[0]);
-
-        for i in 1..self.points.len() {
-            let projection = axis.dot(self.points[i]);
-            if projection > max_projection {
-                index = i;
-                max_projection = projection;
-            }
-        }
-
-        self.points[index]
+        super::farthest_point(&self.points, axis)
     }
 
     fn get_bounding_box(&self) -> Rectangle {
-        use std::f64::INFINITY;
+        super::bounding_box(&self.points)
+    }
+}
 
-        let mut min = Vector2::new(INFINITY, INFINITY);
-        let mut max = Vector2::new(-INFINITY, -INFINITY);
 
-        for point in self.points.iter() {
-            if point.x > max.x { max.x = point.x }
-            if point.x < min.x { min.x = point.x }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-            if point.y > max.y { max.y = point.y }
-            if point.y < min.y { min.y = point.y }
-        }
 
-        return Rectangle {
-            left: min.x,
-            right: max.x,
-            top: max.y,
-            bottom: min.y,
-        }
+    #[test]
+    fn hulls_need_a_finite_point() {
+        assert!(ConvexHull::from_points(&[]).is_none());
+
+        let nan = ::std::f64::NAN;
+        let infinity = ::std::f64::INFINITY;
+        assert!(ConvexHull::from_points(&[Vector2::new(nan, 0.0), Vector2::new(0.0, infinity)]).is_none());
+
+        let hull = ConvexHull::from_points(&[Vector2::new(nan, 0.0), Vector2::new(1.0, 2.0)]).unwrap();
+        assert_eq!(hull.points.len(), 1);
+    }
+
+
+    #[test]
+    fn hulls_drop_interior_duplicate_and_collinear_points() {
+        let hull = ConvexHull::from_points(&[
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(2.0, 2.0),
+            Vector2::new(0.0, 2.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(0.0, 0.0),
+        ]).unwrap();
+
+        assert_eq!(hull.points.len(), 4);
+        assert!(hull.is_valid());
+    }
+
+
+    #[test]
+    fn empty_hulls_do_not_panic() {
+        let hull = ConvexHull::new(Vec::new());
+
+        let point = hull.get_farthest_point(Vector2::new(0.0, 1.0));
+        assert_eq!((point.x, point.y), (0.0, 0.0));
+
+        // An inverted infinite box would end up in every cell of a broad phase
+        let bounds = hull.get_bounding_box();
+        assert_eq!((bounds.left, bounds.right, bounds.top, bounds.bottom), (0.0, 0.0, 0.0, 0.0));
     }
 }
@@ -0,0 +1,166 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use renderer::Tessellation;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// An ellipse, rotated counter-clockwise by `angle`
+#[derive(Copy, Clone)]
+pub struct Ellipse {
+    pub center: Vector2,
+
+    /// The radius along the ellipse's local x- and y-axis
+    pub radii: Vector2,
+
+    /// The counter-clockwise rotation in radians
+    pub angle: f64
+}
+
+impl Ellipse {
+    /// Creates an axis aligned ellipse
+    pub fn new(center: Vector2, radii: Vector2) -> Ellipse {
+        Ellipse { center, radii, angle: 0.0 }
+    }
+
+
+    /// Creates a rotated ellipse
+    pub fn rotated(center: Vector2, radii: Vector2, angle: f64) -> Ellipse {
+        Ellipse { center, radii, angle }
+    }
+
+
+    /// Returns the transformation from a unit circle at the origin to this ellipse
+    fn unit_transform(&self) -> Affine2 {
+        Affine2::scale_axes(self.radii)
+            .then(&Affine2::rigid(self.angle, self.center))
+    }
+}
+
+
+impl super::Shape for Ellipse {
+    fn translate(&mut self, amount: Vector2) {
+        self.center += amount;
+    }
+
+    fn center(&self) -> Vector2 {
+        self.center
+    }
+
+
+    fn transform(&mut self, transform: &Affine2) {
+        // Any affine transformation of an ellipse is still an ellipse,
+        // the new axes are found by decomposing the combined transformation
+        let combined = self.unit_transform().then(transform);
+        let (angle, radii, _) = combined.decompose();
+
+        self.center = combined.translation;
+        self.radii = Vector2::new(radii.x.abs(), radii.y.abs());
+        self.angle = angle;
+    }
+
+
+    fn area(&self) -> f64 {
+        use std::f64::consts::PI;
+        PI * self.radii.x * self.radii.y
+    }
+
+    /// Uses Ramanujan's approximation, as there is no exact closed form
+    fn perimeter(&self) -> f64 {
+        use std::f64::consts::PI;
+        let (a, b) = (self.radii.x, self.radii.y);
+        PI * (3.0 * (a + b) - ((3.0 * a + b) * (a + 3.0 * b)).sqrt())
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        0.25 * mass * (self.radii.x * self.radii.x + self.radii.y * self.radii.y)
+    }
+}
+
+
+
+impl Triangulate for Ellipse {
+    fn get_triangles(&self) -> Triangles {
+        self.tessellate(Tessellation::Segments(64))
+    }
+
+    /// The outline is sampled at equal steps of the angle on the unit circle it is stretched from
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
+        use std::f64::consts::PI;
+
+        // With equal angle steps Δ the distance to the curve is at most about a·Δ²/8, the same
+        // as for a circle with the major radius, so that radius is enough to meet the tolerance
+        let major = self.radii.x.abs().max(self.radii.y.abs());
+        let segments = tessellation.arc_segments(major, 2.0 * PI);
+
+        let transform = self.unit_transform();
+        let outline = (0..segments).map(|i| {
+            let (dy, dx) = (i as f64 / segments as f64 * PI * 2.0).sin_cos();
+            transform.transform_point(Vector2::new(dx, dy))
+        }).collect();
+
+        super::fan(self.center, outline)
+    }
+}
+
+
+impl Collide for Ellipse {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        // The ellipse is a transformed circle, so move the axis into circle space and the point back out
+        let transform = self.unit_transform();
+        let local = super::unit(transform.transpose_vector(axis));
+
+        transform.transform_point(local)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        let (sin, cos) = self.angle.sin_cos();
+        let (a, b) = (self.radii.x, self.radii.y);
+
+        let width = (a * a * cos * cos + b * b * sin * sin).sqrt();
+        let height = (a * a * sin * sin + b * b * cos * cos).sqrt();
+
+        Rectangle {
+            left: self.center.x - width,
+            right: self.center.x + width,
+            top: self.center.y + height,
+            bottom: self.center.y - height,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    fn outline_len(triangles: Triangles) -> usize {
+        match triangles {
+            Triangles::IndexedTriangles(points, _) => points.len() - 1,
+            Triangles::TriangleList(triangles) => triangles.len(),
+        }
+    }
+
+
+    #[test]
+    fn eccentric_ellipses_use_the_major_radius() {
+        let circle = Ellipse::new(Vector2::new(0.0, 0.0), Vector2::new(10.0, 10.0));
+        let ellipse = Ellipse::new(Vector2::new(0.0, 0.0), Vector2::new(10.0, 1.0));
+
+        let tolerance = Tessellation::Tolerance(0.01);
+        assert_eq!(outline_len(ellipse.tessellate(tolerance)), outline_len(circle.tessellate(tolerance)));
+    }
+
+
+    #[test]
+    fn too_few_segments_are_raised_to_a_triangle() {
+        let ellipse = Ellipse::new(Vector2::new(0.0, 0.0), Vector2::new(2.0, 1.0));
+
+        assert_eq!(outline_len(ellipse.tessellate(Tessellation::Segments(0))), 3);
+        assert_eq!(outline_len(ellipse.tessellate(Tessellation::Segments(2))), 3);
+        assert_eq!(outline_len(ellipse.tessellate(Tessellation::Segments(16))), 16);
+    }
+}
@@ -3,15 +3,31 @@
 mod rectangle;
 mod circle;
 mod convex_hull;
+mod polygon;
+mod ellipse;
+mod capsule;
+mod rounded_rectangle;
+mod segment;
+mod triangle;
+mod oriented_rectangle;
 
 pub use self::rectangle::Rectangle;
 pub use self::circle::Circle;
 pub use self::convex_hull::ConvexHull;
+pub use self::polygon::Polygon;
+pub use self::ellipse::Ellipse;
+pub use self::capsule::Capsule;
+pub use self::rounded_rectangle::RoundedRectangle;
+pub use self::segment::Segment;
+pub use self::triangle::Triangle;
+pub use self::oriented_rectangle::OrientedRectangle;
 
 
 use Vector2;
 use renderer::Triangulate;
+use renderer::Triangles;
 use collision::Collide;
+use transform::Affine2;
 
 pub trait Shape: Triangulate + Collide {
     /// Translates the shape
@@ -19,5 +35,176 @@ pub trait Shape: Triangulate + Collide {
 
     /// Returns the center of the shape
     fn center(&self) -> Vector2;
+
+
+    /// Applies an affine transformation to the shape.
+    ///
+    /// Shapes that can't represent the result approximate it, and the approximation can't be undone:
+    /// - `Rectangle` and `RoundedRectangle` stay axis aligned, so rotating or shearing them replaces
+    ///   them with the bounding box of their transformed corners, which is larger.
+    ///   Use `Rectangle::rotated` or `OrientedRectangle` for rotated rectangles.
+    /// - `Circle`, `Capsule` and `RoundedRectangle` keep round ends, so under non-uniform scaling
+    ///   their radius is scaled by the square root of the change in area.
+    ///   Use `Ellipse` for stretched circles.
+    fn transform(&mut self, transform: &Affine2);
+
+    /// Rotates the shape counter-clockwise around a pivot, angle in radians.
+    /// Axis aligned shapes grow to cover the rotated shape, see `transform`.
+    fn rotate(&mut self, angle: f64, pivot: Vector2) {
+        self.transform(&Affine2::rotation_around(angle, pivot));
+    }
+
+    /// Scales the shape away from a pivot
+    fn scale(&mut self, factor: f64, pivot: Vector2) {
+        self.transform(&Affine2::scale_around(factor, pivot));
+    }
+
+
+    /// Returns the area of the shape
+    fn area(&self) -> f64;
+
+    /// Returns the length of the shape's outline
+    fn perimeter(&self) -> f64;
+
+    /// Returns the center of mass of the shape, assuming a uniform density
+    fn centroid(&self) -> Vector2 {
+        self.center()
+    }
+
+    /// Returns the moment of inertia around the centroid, for a uniform density and a total mass
+    fn moment_of_inertia(&self, mass: f64) -> f64;
+}
+
+
+/// Returns a vector of unit length in the same direction, or zero for the zero vector
+pub(crate) fn unit(vector: Vector2) -> Vector2 {
+    let length = vector.dot(vector).sqrt();
+
+    if length > 0.0 {
+        vector / length
+    } else {
+        Vector2::new(0.0, 0.0)
+    }
+}
+
+
+/// Returns the z component of the cross product of two vectors,
+/// positive if the second is counter-clockwise from the first
+pub(crate) fn cross(a: Vector2, b: Vector2) -> f64 {
+    a.x * b.y - a.y * b.x
+}
+
+
+/// Returns the signed area of a polygon, positive if it is counter-clockwise
+pub(crate) fn signed_area(points: &[Vector2]) -> f64 {
+    let mut sum = 0.0;
+
+    for i in 0..points.len() {
+        sum += cross(points[i], points[(i + 1) % points.len()]);
+    }
+
+    0.5 * sum
+}
+
+
+/// Returns the point the farthest along an axis, or the origin if there are no points
+pub(crate) fn farthest_point(points: &[Vector2], axis: Vector2) -> Vector2 {
+    let mut farthest = match points.first() {
+        Some(point) => *point,
+        None => return Vector2::new(0.0, 0.0)
+    };
+    let mut max_projection = axis.dot(farthest);
+
+    for point in points.iter().skip(1) {
+        let projection = axis.dot(*point);
+        if projection > max_projection {
+            farthest = *point;
+            max_projection = projection;
+        }
+    }
+
+    farthest
 }
 
+
+/// Returns the smallest rectangle containing all finite points, or an empty rectangle
+/// at the origin if there are none
+pub(crate) fn bounding_box(points: &[Vector2]) -> Rectangle {
+    use std::f64::INFINITY;
+
+    let mut min = Vector2::new(INFINITY, INFINITY);
+    let mut max = Vector2::new(-INFINITY, -INFINITY);
+
+    for point in points.iter().filter(|p| p.x.is_finite() && p.y.is_finite()) {
+        if point.x > max.x { max.x = point.x }
+        if point.x < min.x { min.x = point.x }
+
+        if point.y > max.y { max.y = point.y }
+        if point.y < min.y { min.y = point.y }
+    }
+
+    if min.x > max.x {
+        return Rectangle::new(0.0, 0.0, 0.0, 0.0);
+    }
+
+    Rectangle {
+        left: min.x,
+        right: max.x,
+        top: max.y,
+        bottom: min.y,
+    }
+}
+
+
+/// Triangulates a convex outline as a fan around a point inside it
+pub(crate) fn fan(center: Vector2, outline: Vec<Vector2>) -> Triangles {
+    let count = outline.len() as u32;
+
+    let mut points = Vec::with_capacity(outline.len() + 1);
+    let mut indices = Vec::with_capacity(3 * outline.len());
+
+    points.push(center);
+    points.extend(outline);
+
+    for i in 0..count {
+        indices.push(0);
+        indices.push(i + 1);
+        indices.push((i + 1) % count + 1);
+    }
+
+    Triangles::IndexedTriangles(points, indices)
+}
+
+
+/// Returns the area, the first moment of area and the polar second moment of area
+/// around the origin of a polygon. All are negative if the polygon is clockwise.
+pub(crate) fn polygon_moments(points: &[Vector2]) -> (f64, Vector2, f64) {
+    let mut area = 0.0;
+    let mut first = Vector2::new(0.0, 0.0);
+    let mut second = 0.0;
+
+    for i in 0..points.len() {
+        let p = points[i];
+        let q = points[(i + 1) % points.len()];
+        let cross = p.x * q.y - q.x * p.y;
+
+        area += cross;
+        first += (p + q) * cross;
+        second += cross * (p.dot(p) + p.dot(q) + q.dot(q));
+    }
+
+    (area / 2.0, first / 6.0, second / 12.0)
+}
+
+
+/// Returns the length of a closed outline
+pub(crate) fn outline_length(points: &[Vector2]) -> f64 {
+    let mut length = 0.0;
+
+    for i in 0..points.len() {
+        let d = points[(i + 1) % points.len()] - points[i];
+        length += d.dot(d).sqrt();
+    }
+
+    length
+}
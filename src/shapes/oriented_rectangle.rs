@@ -0,0 +1,132 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// A rectangle that may be rotated
+#[derive(Copy, Clone)]
+pub struct OrientedRectangle {
+    pub center: Vector2,
+
+    /// Half the width and height of the rectangle before rotating it
+    pub half_extents: Vector2,
+
+    /// The counter-clockwise rotation in radians
+    pub angle: f64
+}
+
+impl OrientedRectangle {
+    pub fn new(center: Vector2, half_extents: Vector2, angle: f64) -> OrientedRectangle {
+        OrientedRectangle { center, half_extents, angle }
+    }
+
+
+    /// Returns the directions of the rectangle's local x- and y-axis
+    pub fn axes(&self) -> (Vector2, Vector2) {
+        let (sin, cos) = self.angle.sin_cos();
+        (Vector2::new(cos, sin), Vector2::new(-sin, cos))
+    }
+
+
+    /// Returns the corners in counter-clockwise order
+    pub fn corners(&self) -> [Vector2; 4] {
+        let (u, v) = self.axes();
+        let x = u * self.half_extents.x;
+        let y = v * self.half_extents.y;
+
+        [
+            self.center + x + y,
+            self.center - x + y,
+            self.center - x - y,
+            self.center + x - y,
+        ]
+    }
+}
+
+
+impl super::Shape for OrientedRectangle {
+    fn translate(&mut self, amount: Vector2) {
+        self.center += amount;
+    }
+
+    fn center(&self) -> Vector2 {
+        self.center
+    }
+
+
+    /// Transformations that skew the rectangle are approximated by keeping the
+    /// transformed axes' lengths and the direction of the local x-axis
+    fn transform(&mut self, transform: &Affine2) {
+        let (u, v) = self.axes();
+        let x = transform.transform_vector(u * self.half_extents.x);
+        let y = transform.transform_vector(v * self.half_extents.y);
+
+        self.center = transform.transform_point(self.center);
+        self.angle = x.y.atan2(x.x);
+        self.half_extents = Vector2::new(x.dot(x).sqrt(), y.dot(y).sqrt());
+    }
+
+
+    fn area(&self) -> f64 {
+        4.0 * self.half_extents.x * self.half_extents.y
+    }
+
+    fn perimeter(&self) -> f64 {
+        4.0 * (self.half_extents.x + self.half_extents.y)
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let width = 2.0 * self.half_extents.x;
+        let height = 2.0 * self.half_extents.y;
+        mass * (width * width + height * height) / 12.0
+    }
+}
+
+
+
+impl Triangulate for OrientedRectangle {
+    fn get_triangles(&self) -> Triangles {
+        Triangles::IndexedTriangles(
+            self.corners().to_vec(),
+            vec![
+                0, 1, 2,
+                2, 3, 0
+            ]
+        )
+    }
+}
+
+
+impl Collide for OrientedRectangle {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        let (u, v) = self.axes();
+
+        let side = |projection: f64| if projection > 0.0 {
+            1.0
+        } else if projection < 0.0 {
+            -1.0
+        } else {
+            0.0
+        };
+
+        self.center +
+            u * (side(axis.dot(u)) * self.half_extents.x) +
+            v * (side(axis.dot(v)) * self.half_extents.y)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        let (u, v) = self.axes();
+        let width = (u.x * self.half_extents.x).abs() + (v.x * self.half_extents.y).abs();
+        let height = (u.y * self.half_extents.x).abs() + (v.y * self.half_extents.y).abs();
+
+        Rectangle {
+            left: self.center.x - width,
+            right: self.center.x + width,
+            top: self.center.y + height,
+            bottom: self.center.y - height,
+        }
+    }
+}
@@ -0,0 +1,556 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use collision::Collide;
+use shapes::Rectangle;
+use shapes::ConvexHull;
+use shapes::cross;
+use shapes::signed_area;
+use transform::Affine2;
+
+use std::cmp::Ordering;
+
+
+/// A simple polygon that may be concave and contain holes.
+///
+/// Collision only sees the convex hull of the outline, so concave parts and holes are filled in.
+/// Use `convex_decomposition` to collide against the exact shape, one piece at a time.
+#[derive(Clone)]
+pub struct Polygon {
+    pub points: Vec<Vector2>,
+    pub holes: Vec<Vec<Vector2>>
+}
+
+impl Polygon {
+    pub fn new(points: Vec<Vector2>) -> Polygon {
+        Polygon {
+            points,
+            holes: Vec::new()
+        }
+    }
+
+
+    /// Creates a polygon with holes cut out of it
+    pub fn with_holes(points: Vec<Vector2>, holes: Vec<Vec<Vector2>>) -> Polygon {
+        Polygon {
+            points,
+            holes
+        }
+    }
+
+
+    /// Returns all vertices, followed by the triangles covering the polygon as indices into them
+    pub fn triangulation(&self) -> (Vec<Vector2>, Vec<u32>) {
+        let mut vertices = Vec::new();
+
+        // The outline goes counter-clockwise...
+        let mut outline: Vec<usize> = (0..self.points.len()).collect();
+        if signed_area(&self.points) < 0.0 {
+            outline.reverse();
+        }
+        vertices.extend(self.points.iter().cloned());
+
+        // ...and the holes clockwise
+        let mut holes = Vec::new();
+        for hole in self.holes.iter() {
+            if hole.len() < 3 {
+                continue;
+            }
+
+            let mut indices: Vec<usize> = (vertices.len()..vertices.len() + hole.len()).collect();
+            if signed_area(hole) > 0.0 {
+                indices.reverse();
+            }
+            vertices.extend(hole.iter().cloned());
+            holes.push(indices);
+        }
+
+        // Holes are merged into the outline from right to left, so that each bridge
+        // can only hit the outline or holes that have already been merged
+        holes.sort_by(|a, b| {
+            max_x(&vertices, b).partial_cmp(&max_x(&vertices, a)).unwrap_or(Ordering::Equal)
+        });
+
+        if outline.len() >= 3 {
+            for hole in holes.iter() {
+                outline = bridge_hole(&vertices, outline, hole);
+            }
+        }
+
+        let indices = ear_clip(&vertices, outline);
+
+        (vertices, indices.into_iter().map(|i| i as u32).collect())
+    }
+
+
+    /// Splits the polygon into convex pieces, which can be used for collision
+    pub fn convex_decomposition(&self) -> Vec<ConvexHull> {
+        let (vertices, indices) = self.triangulation();
+
+        let mut pieces: Vec<Vec<usize>> = indices.chunks(3)
+            .map(|t| vec![t[0] as usize, t[1] as usize, t[2] as usize])
+            .filter(|t| cross(vertices[t[1]] - vertices[t[0]], vertices[t[2]] - vertices[t[0]]) > 0.0)
+            .collect();
+
+        // Remove diagonals between pieces for as long as the result stays convex (Hertel-Mehlhorn)
+        let mut merged = true;
+        while merged {
+            merged = false;
+
+            'search: for i in 0..pieces.len() {
+                for j in i + 1..pieces.len() {
+                    if let Some(piece) = merge_pieces(&vertices, &pieces[i], &pieces[j]) {
+                        pieces[i] = piece;
+                        pieces.remove(j);
+                        merged = true;
+                        break 'search;
+                    }
+                }
+            }
+        }
+
+        pieces.into_iter()
+            .map(|piece| ConvexHull::new(piece.into_iter().map(|i| vertices[i]).collect()))
+            .collect()
+    }
+}
+
+
+impl super::Shape for Polygon {
+    fn translate(&mut self, amount: Vector2) {
+        for point in self.points.iter_mut() {
+            *point += amount;
+        }
+
+        for hole in self.holes.iter_mut() {
+            for point in hole.iter_mut() {
+                *point += amount;
+            }
+        }
+    }
+
+
+    fn center(&self) -> Vector2 {
+        let mut sum = Vector2::new(0.0, 0.0);
+
+        if self.points.is_empty() {
+            return sum;
+        }
+
+        for point in self.points.iter() {
+            sum += *point;
+        }
+
+        sum / self.points.len() as f64
+    }
+
+
+    fn transform(&mut self, transform: &Affine2) {
+        for point in self.points.iter_mut() {
+            *point = transform.transform_point(*point);
+        }
+
+        for hole in self.holes.iter_mut() {
+            for point in hole.iter_mut() {
+                *point = transform.transform_point(*point);
+            }
+        }
+    }
+
+
+    fn area(&self) -> f64 {
+        self.moments().0
+    }
+
+    fn perimeter(&self) -> f64 {
+        self.holes.iter().fold(super::outline_length(&self.points), |sum, hole| {
+            sum + super::outline_length(hole)
+        })
+    }
+
+    fn centroid(&self) -> Vector2 {
+        let (area, first, _) = self.moments();
+
+        if area != 0.0 {
+            first / area
+        } else {
+            super::Shape::center(self)
+        }
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let (area, first, second) = self.moments();
+
+        if area != 0.0 {
+            let centroid = first / area;
+            mass * (second / area - centroid.dot(centroid))
+        } else {
+            0.0
+        }
+    }
+}
+
+
+impl Polygon {
+    /// Returns the moments of area of the outline with the holes removed
+    fn moments(&self) -> (f64, Vector2, f64) {
+        let outline = super::polygon_moments(&self.points);
+        let sign = if outline.0 < 0.0 { -1.0 } else { 1.0 };
+
+        let mut area = sign * outline.0;
+        let mut first = outline.1 * sign;
+        let mut second = sign * outline.2;
+
+        for hole in self.holes.iter() {
+            let moments = super::polygon_moments(hole);
+            let sign = if moments.0 < 0.0 { -1.0 } else { 1.0 };
+
+            area = area - sign * moments.0;
+            first = first - moments.1 * sign;
+            second = second - sign * moments.2;
+        }
+
+        (area, first, second)
+    }
+}
+
+
+
+impl Triangulate for Polygon {
+    fn get_triangles(&self) -> Triangles {
+        let (vertices, indices) = self.triangulation();
+
+        Triangles::IndexedTriangles(vertices, indices)
+    }
+}
+
+
+/// Collides as the convex hull of the outline, see `Polygon`
+impl Collide for Polygon {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        super::farthest_point(&self.points, axis)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        super::bounding_box(&self.points)
+    }
+}
+
+
+
+fn max_x(vertices: &[Vector2], indices: &[usize]) -> f64 {
+    indices.iter().fold(-::std::f64::INFINITY, |max, &i| max.max(vertices[i].x))
+}
+
+
+fn same_point(a: Vector2, b: Vector2) -> bool {
+    a.x == b.x && a.y == b.y
+}
+
+
+/// Returns true if a point is inside, or on the edge of, a counter-clockwise triangle
+fn in_triangle(p: Vector2, a: Vector2, b: Vector2, c: Vector2) -> bool {
+    cross(b - a, p - a) >= 0.0 && cross(c - b, p - b) >= 0.0 && cross(a - c, p - c) >= 0.0
+}
+
+
+/// Connects a hole to the outline with a pair of edges, so that they form a single outline
+fn bridge_hole(vertices: &[Vector2], outline: Vec<usize>, hole: &[usize]) -> Vec<usize> {
+    use std::f64::INFINITY;
+
+    // The rightmost vertex of the hole is always visible from the right
+    let mut hole_start = 0;
+    for i in 1..hole.len() {
+        if vertices[hole[i]].x > vertices[hole[hole_start]].x {
+            hole_start = i;
+        }
+    }
+    let m = vertices[hole[hole_start]];
+
+    // Find the closest edge to the right of the hole
+    let count = outline.len();
+    let mut closest = INFINITY;
+    let mut edge = None;
+
+    for i in 0..count {
+        let a = vertices[outline[i]];
+        let b = vertices[outline[(i + 1) % count]];
+
+        // Only edges crossing the horizontal line through the hole can be hit
+        if a.y == b.y || (a.y - m.y) * (b.y - m.y) > 0.0 {
+            continue;
+        }
+
+        let x = a.x + (m.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x >= m.x && x < closest {
+            closest = x;
+            edge = Some(i);
+        }
+    }
+
+    let edge = match edge {
+        Some(edge) => edge,
+
+        // The hole isn't inside the outline
+        None => return outline,
+    };
+
+    let intersection = Vector2::new(closest, m.y);
+
+    // The endpoint of the edge farthest to the right is a candidate for the bridge
+    let mut bridge = if vertices[outline[edge]].x > vertices[outline[(edge + 1) % count]].x {
+        edge
+    } else {
+        (edge + 1) % count
+    };
+
+    // Reflex vertices inside the triangle between the hole, the intersection and
+    // the candidate may block the view, in which case the one with the smallest angle is used
+    let candidate = vertices[outline[bridge]];
+    let (a, b, c) = if cross(intersection - m, candidate - m) > 0.0 {
+        (m, intersection, candidate)
+    } else {
+        (m, candidate, intersection)
+    };
+
+    let mut best_angle = INFINITY;
+    for i in 0..count {
+        let p = vertices[outline[i]];
+        if same_point(p, candidate) {
+            continue;
+        }
+
+        let previous = vertices[outline[(i + count - 1) % count]];
+        let next = vertices[outline[(i + 1) % count]];
+        let reflex = cross(p - previous, next - previous) <= 0.0;
+
+        if reflex && in_triangle(p, a, b, c) {
+            let d = p - m;
+            let angle = d.y.abs().atan2(d.x);
+            if angle < best_angle {
+                best_angle = angle;
+                bridge = i;
+            }
+        }
+    }
+
+    // Walk to the bridge, around the hole and back again
+    let mut merged = Vec::with_capacity(outline.len() + hole.len() + 2);
+    merged.extend_from_slice(&outline[..bridge + 1]);
+    for i in 0..hole.len() + 1 {
+        merged.push(hole[(hole_start + i) % hole.len()]);
+    }
+    merged.extend_from_slice(&outline[bridge..]);
+
+    merged
+}
+
+
+/// Splits a counter-clockwise outline into triangles by repeatedly cutting off ears
+fn ear_clip(vertices: &[Vector2], mut outline: Vec<usize>) -> Vec<usize> {
+    let mut triangles = Vec::new();
+
+    let mut i = 0;
+    let mut attempts = 0;
+
+    while outline.len() > 3 {
+        let count = outline.len();
+        let previous = outline[(i + count - 1) % count];
+        let current = outline[i];
+        let next = outline[(i + 1) % count];
+
+        let (a, b, c) = (vertices[previous], vertices[current], vertices[next]);
+        let area = cross(b - a, c - a);
+
+        if area == 0.0 && !same_point(a, c) {
+            // Collinear vertices add nothing to the polygon
+            outline.remove(i);
+            attempts = 0;
+        } else if area > 0.0 && is_ear(vertices, &outline, i) || attempts > count {
+            // Degenerate input may have no ears left, in which case one is forced
+            triangles.push(previous);
+            triangles.push(current);
+            triangles.push(next);
+
+            outline.remove(i);
+            attempts = 0;
+        } else {
+            i += 1;
+            attempts += 1;
+        }
+
+        if i >= outline.len() {
+            i = 0;
+        }
+    }
+
+    if outline.len() == 3 {
+        triangles.extend_from_slice(&outline);
+    }
+
+    triangles
+}
+
+
+/// Returns true if no other vertex of the outline is inside the triangle at a corner
+fn is_ear(vertices: &[Vector2], outline: &[usize], i: usize) -> bool {
+    let count = outline.len();
+    let a = vertices[outline[(i + count - 1) % count]];
+    let b = vertices[outline[i]];
+    let c = vertices[outline[(i + 1) % count]];
+
+    for j in 0..count {
+        let p = vertices[outline[j]];
+
+        // Bridges duplicate vertices, those on the corners don't block the ear
+        if same_point(p, a) || same_point(p, b) || same_point(p, c) {
+            continue;
+        }
+
+        if in_triangle(p, a, b, c) {
+            return false;
+        }
+    }
+
+    true
+}
+
+
+/// Merges two pieces sharing an edge, if the result is convex
+fn merge_pieces(vertices: &[Vector2], a: &[usize], b: &[usize]) -> Option<Vec<usize>> {
+    for k in 0..a.len() {
+        let start = a[k];
+        let end = a[(k + 1) % a.len()];
+
+        for l in 0..b.len() {
+            if b[l] != end || b[(l + 1) % b.len()] != start {
+                continue;
+            }
+
+            // Go around `a` from the end of the shared edge to its start, then around `b`
+            let mut merged = Vec::with_capacity(a.len() + b.len() - 2);
+            for i in 0..a.len() {
+                merged.push(a[(k + 1 + i) % a.len()]);
+            }
+            for i in 2..b.len() {
+                merged.push(b[(l + i) % b.len()]);
+            }
+
+            for i in 0..merged.len() {
+                if merged[i + 1..].contains(&merged[i]) {
+                    return None;
+                }
+            }
+
+            return if is_convex(vertices, &merged) { Some(merged) } else { None };
+        }
+    }
+
+    None
+}
+
+
+/// Returns true if a counter-clockwise outline only turns left
+fn is_convex(vertices: &[Vector2], outline: &[usize]) -> bool {
+    const TOLERANCE: f64 = 1e-9;
+
+    let count = outline.len();
+    for i in 0..count {
+        let a = vertices[outline[i]];
+        let b = vertices[outline[(i + 1) % count]];
+        let c = vertices[outline[(i + 2) % count]];
+
+        let ab = b - a;
+        let bc = c - b;
+        let scale = (ab.dot(ab) * bc.dot(bc)).sqrt();
+
+        if cross(ab, bc) < -TOLERANCE * scale {
+            return false;
+        }
+    }
+
+    true
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use shapes::Shape;
+
+
+    #[test]
+    fn empty_polygons_do_not_panic() {
+        let polygon = Polygon::new(Vec::new());
+
+        let point = polygon.get_farthest_point(Vector2::new(1.0, 0.0));
+        assert_eq!((point.x, point.y), (0.0, 0.0));
+
+        let bounds = polygon.get_bounding_box();
+        assert_eq!((bounds.left, bounds.right, bounds.top, bounds.bottom), (0.0, 0.0, 0.0, 0.0));
+
+        let center = polygon.center();
+        assert_eq!((center.x, center.y), (0.0, 0.0));
+    }
+
+
+    #[test]
+    fn concave_polygons_decompose_into_convex_pieces() {
+        // An L shape with area 3
+        let polygon = Polygon::new(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(2.0, 0.0),
+            Vector2::new(2.0, 1.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 2.0),
+            Vector2::new(0.0, 2.0),
+        ]);
+
+        let pieces = polygon.convex_decomposition();
+        assert!(pieces.len() >= 2);
+
+        let area: f64 = pieces.iter().map(|piece| piece.area()).sum();
+        assert!((area - 3.0).abs() < 1e-9);
+        assert!(pieces.iter().all(|piece| piece.is_valid()));
+    }
+
+
+    #[test]
+    fn holes_are_left_uncovered() {
+        // A 4 by 4 square with a 2 by 2 hole in the middle, both counter-clockwise
+        let polygon = Polygon::with_holes(
+            vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(4.0, 0.0),
+                Vector2::new(4.0, 4.0),
+                Vector2::new(0.0, 4.0),
+            ],
+            vec![vec![
+                Vector2::new(1.0, 1.0),
+                Vector2::new(3.0, 1.0),
+                Vector2::new(3.0, 3.0),
+                Vector2::new(1.0, 3.0),
+            ]]
+        );
+
+        let (vertices, indices) = polygon.triangulation();
+        assert_eq!(indices.len() % 3, 0);
+
+        let hole_centroid = Vector2::new(2.0, 2.0);
+        let mut area = 0.0;
+
+        for triangle in indices.chunks(3) {
+            let a = vertices[triangle[0] as usize];
+            let b = vertices[triangle[1] as usize];
+            let c = vertices[triangle[2] as usize];
+
+            let triangle_area = 0.5 * cross(b - a, c - a);
+            assert!(triangle_area > -1e-9, "clockwise triangle");
+            assert!(!in_triangle(hole_centroid, a, b, c), "a triangle covers the hole");
+
+            area += triangle_area;
+        }
+
+        assert!((area - (16.0 - 4.0)).abs() < 1e-9, "area = {}", area);
+    }
+}
@@ -3,6 +3,8 @@ use trap::Vector2;
 use renderer::Triangulate;
 use renderer::Triangles;
 use collision::Collide;
+use shapes::OrientedRectangle;
+use transform::Affine2;
 
 #[derive(Copy, Clone)]
 pub struct Rectangle {
@@ -59,6 +61,15 @@ impl Rectangle {
         self.left < other.right && other.left < self.right &&
             self.bottom < other.top && other.bottom < self.top
     }
+
+
+    /// Returns this rectangle rotated counter-clockwise around a pivot
+    pub fn rotated(&self, angle: f64, pivot: Vector2) -> OrientedRectangle {
+        let center = Affine2::rotation_around(angle, pivot).transform_point(super::Shape::center(self));
+        let half_extents = Vector2::new(0.5 * (self.right - self.left), 0.5 * (self.top - self.bottom));
+
+        OrientedRectangle::new(center, half_extents, angle)
+    }
 }
 
 
@@ -77,6 +88,37 @@ impl super::Shape for Rectangle {
             y: (self.top + self.bottom) * 0.5,
         }
     }
+
+
+    /// A rectangle stays axis aligned, so it becomes the bounding box of its transformed corners.
+    /// This is lossy for rotations and shears: rotating back doesn't restore the original size.
+    /// Use `rotated` to get a rotated rectangle.
+    fn transform(&mut self, transform: &Affine2) {
+        let a = transform.transform_point(Vector2::new(self.left, self.top));
+        let b = transform.transform_point(Vector2::new(self.right, self.top));
+        let c = transform.transform_point(Vector2::new(self.right, self.bottom));
+        let d = transform.transform_point(Vector2::new(self.left, self.bottom));
+
+        self.left = a.x.min(b.x).min(c.x).min(d.x);
+        self.right = a.x.max(b.x).max(c.x).max(d.x);
+        self.top = a.y.max(b.y).max(c.y).max(d.y);
+        self.bottom = a.y.min(b.y).min(c.y).min(d.y);
+    }
+
+
+    fn area(&self) -> f64 {
+        (self.right - self.left) * (self.top - self.bottom)
+    }
+
+    fn perimeter(&self) -> f64 {
+        2.0 * ((self.right - self.left) + (self.top - self.bottom))
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let width = self.right - self.left;
+        let height = self.top - self.bottom;
+        mass * (width * width + height * height) / 12.0
+    }
 }
 
 
@@ -99,27 +141,19 @@ impl Triangulate for Rectangle {
 
 
 impl Collide for Rectangle {
+    /// Axes perpendicular to an edge return the middle of that edge,
+    /// and inverted rectangles behave as if their sides were swapped
     fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
-        if self.bottom < self.top {
-            if axis.x > 0.0 {
-                if axis.y > 0.0 {
-                    Vector2::new(self.right, self.top)
-                } else {
-                    Vector2::new(self.right, self.bottom)
-                }
-            } else {
-                if axis.y > 0.0 {
-                    Vector2::new(self.left, self.top)
-                } else {
-                    Vector2::new(self.left, self.bottom)
-                }
-            }
-        } else {
-            unimplemented!()
-        }
+        let (left, right) = (self.left.min(self.right), self.left.max(self.right));
+        let (bottom, top) = (self.bottom.min(self.top), self.bottom.max(self.top));
+
+        Vector2::new(
+            if axis.x > 0.0 { right } else if axis.x < 0.0 { left } else { 0.5 * (left + right) },
+            if axis.y > 0.0 { top } else if axis.y < 0.0 { bottom } else { 0.5 * (bottom + top) }
+        )
     }
 
     fn get_bounding_box(&self) -> Rectangle {
-        self.clone()
+        Rectangle::new(self.left, self.right, self.top, self.bottom)
     }
 }
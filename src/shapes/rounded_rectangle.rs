@@ -0,0 +1,147 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use renderer::Tessellation;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// A rectangle with rounded corners
+#[derive(Copy, Clone)]
+pub struct RoundedRectangle {
+    /// The outer bounds of the rectangle
+    pub bounds: Rectangle,
+
+    /// The radius of the corners
+    pub radius: f64
+}
+
+impl RoundedRectangle {
+    /// Creates a rounded rectangle, the radius is limited to half of the smallest side
+    pub fn new(bounds: Rectangle, radius: f64) -> RoundedRectangle {
+        let width = bounds.right - bounds.left;
+        let height = bounds.top - bounds.bottom;
+
+        RoundedRectangle {
+            bounds,
+            radius: radius.max(0.0).min(0.5 * width.min(height))
+        }
+    }
+
+
+    /// Returns the rectangle traced by the centers of the corners
+    pub fn inner(&self) -> Rectangle {
+        let mut inner = self.bounds;
+        inner.add_margin(-self.radius);
+        inner
+    }
+}
+
+
+impl super::Shape for RoundedRectangle {
+    fn translate(&mut self, amount: Vector2) {
+        super::Shape::translate(&mut self.bounds, amount);
+    }
+
+    fn center(&self) -> Vector2 {
+        super::Shape::center(&self.bounds)
+    }
+
+
+    /// The bounds stay axis aligned like a `Rectangle`'s, and the radius is scaled by the
+    /// square root of the change in area, limited to half of the smallest side
+    fn transform(&mut self, transform: &Affine2) {
+        super::Shape::transform(&mut self.bounds, transform);
+
+        let width = self.bounds.right - self.bounds.left;
+        let height = self.bounds.top - self.bounds.bottom;
+        self.radius = (self.radius * transform.uniform_scale()).max(0.0).min(0.5 * width.min(height));
+    }
+
+
+    fn area(&self) -> f64 {
+        use std::f64::consts::PI;
+        super::Shape::area(&self.bounds) - (4.0 - PI) * self.radius * self.radius
+    }
+
+    fn perimeter(&self) -> f64 {
+        use std::f64::consts::PI;
+        super::Shape::perimeter(&self.bounds) - 8.0 * self.radius + 2.0 * PI * self.radius
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        use std::f64::consts::PI;
+
+        let area = super::Shape::area(self);
+        if area == 0.0 {
+            return 0.0;
+        }
+
+        let r = self.radius;
+        let width = self.bounds.right - self.bounds.left;
+        let height = self.bounds.top - self.bounds.bottom;
+
+        // Polar moment of a rectangle of uniform density, offset from the center
+        let rectangle = |width: f64, height: f64, offset: f64| {
+            let area = width * height;
+            area * (width * width + height * height) / 12.0 + area * offset * offset
+        };
+
+        // A tall rectangle through the middle and two short ones above and below
+        let middle = rectangle(width, height - 2.0 * r, 0.0);
+        let sides = 2.0 * rectangle(width - 2.0 * r, r, 0.5 * (height - r));
+
+        // A quarter circle in each corner, moved from its own centroid to the center
+        let quarter_area = 0.25 * PI * r * r;
+        let offset = 4.0 * r / (3.0 * PI);
+        let corner = Vector2::new(0.5 * width - r + offset, 0.5 * height - r + offset);
+        let corners = 4.0 * quarter_area * (0.5 * r * r - 2.0 * offset * offset + corner.dot(corner));
+
+        mass * (middle + sides + corners) / area
+    }
+}
+
+
+
+impl Triangulate for RoundedRectangle {
+    fn get_triangles(&self) -> Triangles {
+        self.tessellate(Tessellation::Segments(64))
+    }
+
+    fn tessellate(&self, tessellation: Tessellation) -> Triangles {
+        use std::f64::consts::PI;
+
+        let segments = tessellation.arc_segments(self.radius, 0.5 * PI);
+
+        let inner = self.inner();
+        let corners = [
+            (Vector2::new(inner.right, inner.top), 0.0),
+            (Vector2::new(inner.left, inner.top), 0.5 * PI),
+            (Vector2::new(inner.left, inner.bottom), PI),
+            (Vector2::new(inner.right, inner.bottom), 1.5 * PI),
+        ];
+
+        // A quarter of a circle around each corner, counter-clockwise
+        let mut outline = Vec::with_capacity(4 * (segments as usize + 1));
+        for &(center, start) in corners.iter() {
+            for i in 0..segments + 1 {
+                let (dy, dx) = (start + i as f64 / segments as f64 * 0.5 * PI).sin_cos();
+                outline.push(center + self.radius * Vector2::new(dx, dy));
+            }
+        }
+
+        super::fan(super::Shape::center(self), outline)
+    }
+}
+
+
+impl Collide for RoundedRectangle {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        self.inner().get_farthest_point(axis) + self.radius * super::unit(axis)
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        self.bounds
+    }
+}
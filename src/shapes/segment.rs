@@ -0,0 +1,75 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+/// A straight line between two points
+#[derive(Copy, Clone)]
+pub struct Segment {
+    pub start: Vector2,
+    pub end: Vector2
+}
+
+impl Segment {
+    pub fn new(start: Vector2, end: Vector2) -> Segment {
+        Segment { start, end }
+    }
+}
+
+
+impl super::Shape for Segment {
+    fn translate(&mut self, amount: Vector2) {
+        self.start += amount;
+        self.end += amount;
+    }
+
+    fn center(&self) -> Vector2 {
+        (self.start + self.end) * 0.5
+    }
+
+
+    fn transform(&mut self, transform: &Affine2) {
+        self.start = transform.transform_point(self.start);
+        self.end = transform.transform_point(self.end);
+    }
+
+
+    fn area(&self) -> f64 {
+        0.0
+    }
+
+    /// The length of the segment
+    fn perimeter(&self) -> f64 {
+        let d = self.end - self.start;
+        d.dot(d).sqrt()
+    }
+
+    /// Treats the segment as a thin rod
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let d = self.end - self.start;
+        mass * d.dot(d) / 12.0
+    }
+}
+
+
+
+/// A segment has no area, so there is nothing to fill. Use `Renderer::draw_line` to draw it.
+impl Triangulate for Segment {
+    fn get_triangles(&self) -> Triangles {
+        Triangles::IndexedTriangles(Vec::new(), Vec::new())
+    }
+}
+
+
+impl Collide for Segment {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        if axis.dot(self.end) > axis.dot(self.start) { self.end } else { self.start }
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle::from_points(self.start, self.end)
+    }
+}
@@ -0,0 +1,89 @@
+use trap::Vector2;
+use renderer::Triangulate;
+use renderer::Triangles;
+use collision::Collide;
+use shapes::Rectangle;
+use transform::Affine2;
+
+
+#[derive(Copy, Clone)]
+pub struct Triangle {
+    pub a: Vector2,
+    pub b: Vector2,
+    pub c: Vector2
+}
+
+impl Triangle {
+    pub fn new(a: Vector2, b: Vector2, c: Vector2) -> Triangle {
+        Triangle { a, b, c }
+    }
+}
+
+
+impl super::Shape for Triangle {
+    fn translate(&mut self, amount: Vector2) {
+        self.a += amount;
+        self.b += amount;
+        self.c += amount;
+    }
+
+    fn center(&self) -> Vector2 {
+        (self.a + self.b + self.c) / 3.0
+    }
+
+
+    fn transform(&mut self, transform: &Affine2) {
+        self.a = transform.transform_point(self.a);
+        self.b = transform.transform_point(self.b);
+        self.c = transform.transform_point(self.c);
+    }
+
+
+    fn area(&self) -> f64 {
+        super::polygon_moments(&[self.a, self.b, self.c]).0.abs()
+    }
+
+    fn perimeter(&self) -> f64 {
+        super::outline_length(&[self.a, self.b, self.c])
+    }
+
+    fn moment_of_inertia(&self, mass: f64) -> f64 {
+        let (area, first, second) = super::polygon_moments(&[self.a, self.b, self.c]);
+
+        if area != 0.0 {
+            let centroid = first / area;
+            mass * (second / area - centroid.dot(centroid))
+        } else {
+            0.0
+        }
+    }
+}
+
+
+
+impl Triangulate for Triangle {
+    fn get_triangles(&self) -> Triangles {
+        Triangles::TriangleList(vec![(self.a, self.b, self.c)])
+    }
+}
+
+
+impl Collide for Triangle {
+    fn get_farthest_point(&self, axis: Vector2) -> Vector2 {
+        let mut farthest = self.a;
+
+        if axis.dot(self.b) > axis.dot(farthest) { farthest = self.b }
+        if axis.dot(self.c) > axis.dot(farthest) { farthest = self.c }
+
+        farthest
+    }
+
+    fn get_bounding_box(&self) -> Rectangle {
+        Rectangle {
+            left: self.a.x.min(self.b.x).min(self.c.x),
+            right: self.a.x.max(self.b.x).max(self.c.x),
+            top: self.a.y.max(self.b.y).max(self.c.y),
+            bottom: self.a.y.min(self.b.y).min(self.c.y),
+        }
+    }
+}
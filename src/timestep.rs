@@ -0,0 +1,106 @@
+/// Splits elapsed time into steps of a fixed length, carrying the rest over to the next frame
+#[derive(Copy, Clone, Debug)]
+pub struct FixedTimestep {
+    timestep: f64,
+    max_steps: u32,
+    accumulator: f64,
+}
+
+
+impl FixedTimestep {
+    /// Creates a timestep running a number of ticks per second, catching up at most 8 steps per frame
+    pub fn new(ticks_per_second: f64) -> FixedTimestep {
+        FixedTimestep {
+            timestep: 1.0 / ticks_per_second,
+            max_steps: 8,
+            accumulator: 0.0,
+        }
+    }
+
+
+    /// Sets how many steps a single frame may take at most, to avoid falling further behind after a slow frame
+    pub fn with_max_steps(self, max_steps: u32) -> FixedTimestep {
+        FixedTimestep { max_steps, ..self }
+    }
+
+
+    /// Sets the length of a single step in seconds, keeping the time already accumulated
+    pub fn set_timestep(&mut self, timestep: f64) {
+        self.timestep = timestep;
+    }
+
+
+    /// Returns the length of a single step in seconds
+    pub fn timestep(&self) -> f64 {
+        self.timestep
+    }
+
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+
+    /// Adds elapsed time and returns the number of whole steps to take.
+    /// Time that couldn't be caught up with is dropped.
+    pub fn advance(&mut self, delta_time: f64) -> u32 {
+        self.accumulator += delta_time;
+
+        let mut steps = 0;
+        while self.accumulator >= self.timestep && steps < self.max_steps {
+            self.accumulator -= self.timestep;
+            steps += 1;
+        }
+
+        if self.accumulator >= self.timestep {
+            self.accumulator = 0.0;
+        }
+
+        steps
+    }
+
+
+    /// Returns how far the time is between the last step and the next, between 0 and 1
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.timestep).max(0.0).min(1.0)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+
+    #[test]
+    fn carries_the_remainder_over() {
+        let mut clock = FixedTimestep::new(10.0);
+
+        assert_eq!(clock.advance(0.25), 2);
+        assert!((clock.alpha() - 0.5).abs() < 1e-9);
+
+        assert_eq!(clock.advance(0.06), 1);
+        assert!((clock.alpha() - 0.1).abs() < 1e-9);
+    }
+
+
+    #[test]
+    fn drops_time_after_hitting_the_step_limit() {
+        let mut clock = FixedTimestep::new(10.0).with_max_steps(3);
+
+        assert_eq!(clock.advance(1.0), 3);
+        assert_eq!(clock.alpha(), 0.0);
+
+        assert_eq!(clock.advance(0.05), 0);
+    }
+
+
+    #[test]
+    fn changing_the_timestep_keeps_the_accumulator() {
+        let mut clock = FixedTimestep::new(10.0);
+        clock.advance(0.05);
+
+        clock.set_timestep(0.04);
+        assert_eq!(clock.timestep(), 0.04);
+        assert_eq!(clock.advance(0.0), 1);
+    }
+}
@@ -0,0 +1,166 @@
+use trap::Vector2;
+
+
+/// A linear transformation followed by a translation.
+///
+/// A point `p` is mapped to `(a * p.x + b * p.y, c * p.x + d * p.y) + translation`.
+#[derive(Copy, Clone, Debug)]
+pub struct Affine2 {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+
+    pub translation: Vector2,
+}
+
+
+impl Affine2 {
+    pub fn new(a: f64, b: f64, c: f64, d: f64, translation: Vector2) -> Affine2 {
+        Affine2 { a, b, c, d, translation }
+    }
+
+
+    /// A transformation that does nothing
+    pub fn identity() -> Affine2 {
+        Affine2::new(1.0, 0.0, 0.0, 1.0, Vector2::new(0.0, 0.0))
+    }
+
+
+    /// Moves points by an amount
+    pub fn translation(amount: Vector2) -> Affine2 {
+        Affine2::new(1.0, 0.0, 0.0, 1.0, amount)
+    }
+
+
+    /// Rotates points counter-clockwise around the origin, angle in radians
+    pub fn rotation(angle: f64) -> Affine2 {
+        let (sin, cos) = angle.sin_cos();
+        Affine2::new(cos, -sin, sin, cos, Vector2::new(0.0, 0.0))
+    }
+
+
+    /// Scales points away from the origin
+    pub fn scale(factor: f64) -> Affine2 {
+        Affine2::new(factor, 0.0, 0.0, factor, Vector2::new(0.0, 0.0))
+    }
+
+
+    /// Scales points away from the origin by different amounts along the x- and y-axis
+    pub fn scale_axes(factors: Vector2) -> Affine2 {
+        Affine2::new(factors.x, 0.0, 0.0, factors.y, Vector2::new(0.0, 0.0))
+    }
+
+
+    /// Rotates points counter-clockwise around a pivot
+    pub fn rotation_around(angle: f64, pivot: Vector2) -> Affine2 {
+        Affine2::translation(-pivot)
+            .then(&Affine2::rotation(angle))
+            .then(&Affine2::translation(pivot))
+    }
+
+
+    /// Scales points away from a pivot
+    pub fn scale_around(factor: f64, pivot: Vector2) -> Affine2 {
+        Affine2::translation(-pivot)
+            .then(&Affine2::scale(factor))
+            .then(&Affine2::translation(pivot))
+    }
+
+
+    /// A rotation around the origin followed by a translation
+    pub fn rigid(angle: f64, translation: Vector2) -> Affine2 {
+        let mut transform = Affine2::rotation(angle);
+        transform.translation = translation;
+        transform
+    }
+
+
+    /// Returns the transformation that applies this one and then another
+    pub fn then(&self, other: &Affine2) -> Affine2 {
+        Affine2 {
+            a: other.a * self.a + other.b * self.c,
+            b: other.a * self.b + other.b * self.d,
+            c: other.c * self.a + other.d * self.c,
+            d: other.c * self.b + other.d * self.d,
+            translation: other.transform_point(self.translation),
+        }
+    }
+
+
+    /// Transforms a point
+    pub fn transform_point(&self, point: Vector2) -> Vector2 {
+        self.transform_vector(point) + self.translation
+    }
+
+
+    /// Transforms a direction, ignoring the translation
+    pub fn transform_vector(&self, vector: Vector2) -> Vector2 {
+        Vector2::new(
+            self.a * vector.x + self.b * vector.y,
+            self.c * vector.x + self.d * vector.y
+        )
+    }
+
+
+    /// Transforms a direction by the transpose of the linear part.
+    /// Useful for moving search directions into the space of an untransformed shape.
+    pub fn transpose_vector(&self, vector: Vector2) -> Vector2 {
+        Vector2::new(
+            self.a * vector.x + self.c * vector.y,
+            self.b * vector.x + self.d * vector.y
+        )
+    }
+
+
+    /// Returns the determinant of the linear part, negative if the transformation mirrors
+    pub fn determinant(&self) -> f64 {
+        self.a * self.d - self.b * self.c
+    }
+
+
+    /// Returns how much areas are scaled, as a length
+    pub fn uniform_scale(&self) -> f64 {
+        self.determinant().abs().sqrt()
+    }
+
+
+    /// Returns the transformation that undoes this one, if there is one
+    pub fn inverse(&self) -> Option<Affine2> {
+        let determinant = self.determinant();
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        let inverse = 1.0 / determinant;
+        let mut result = Affine2::new(
+            self.d * inverse, -self.b * inverse,
+            -self.c * inverse, self.a * inverse,
+            Vector2::new(0.0, 0.0)
+        );
+        result.translation = -result.transform_vector(self.translation);
+
+        Some(result)
+    }
+
+
+    /// Splits the linear part into a rotation, a scale along the axes and another rotation,
+    /// such that it equals `rotation(outer) * scale_axes(scale) * rotation(inner)`
+    pub fn decompose(&self) -> (f64, Vector2, f64) {
+        let e = 0.5 * (self.a + self.d);
+        let f = 0.5 * (self.a - self.d);
+        let g = 0.5 * (self.c + self.b);
+        let h = 0.5 * (self.c - self.b);
+
+        let q = (e * e + h * h).sqrt();
+        let r = (f * f + g * g).sqrt();
+
+        let a1 = g.atan2(f);
+        let a2 = h.atan2(e);
+
+        let outer = 0.5 * (a2 + a1);
+        let inner = 0.5 * (a2 - a1);
+
+        (outer, Vector2::new(q + r, q - r), inner)
+    }
+}